@@ -8,6 +8,9 @@ const SHADERS: &[&str] = &[
     "shaders/image.frag",
     "shaders/glyph_sdf.vert",
     "shaders/glyph_sdf.frag",
+    "shaders/glyph_msdf.frag",
+    "shaders/glyph_bitmap.frag",
+    "shaders/glyph_color.frag",
 ];
 
 fn main() {
@@ -74,18 +74,23 @@ impl SceneController for LoginSceneController {
             .with_sizing(Sizing::fill())
             .with_alignment(HorizontalAlignment::Center)
             .with_children(vec![
-                Text::builder("Hello, World!\nWelcome to UXUI!").build_boxed(),
+                Text::builder("Hello, World!\nWelcome to UXUI!")
+                    .with_selectable(true)
+                    .build_boxed(),
                 Image::from_bytes(UXUI_LOGO_IMG)
                     .with_sizing(Sizing::fixed(Size::new(64.0, 64.0)))
                     .build_boxed(),
-                // Input::builder()
-                //     .with_hint("Username")
-                //     .with_binding(self.model.username.create_binding())
-                //     .build(),
-                // Input::builder()
-                //     .with_hint("Password")
-                //     .with_binding(self.model.password.create_binding())
-                //     .build(),
+                Input::builder()
+                    .with_hint("Username")
+                    .with_binding(self.model.username.create_binding())
+                    .with_sizing(Sizing::new(Dimension::fixed(200.0), Dimension::fit()))
+                    .build_boxed(),
+                Input::builder()
+                    .with_hint("Password")
+                    .with_binding(self.model.password.create_binding())
+                    .with_password(true)
+                    .with_sizing(Sizing::new(Dimension::fixed(200.0), Dimension::fit()))
+                    .build_boxed(),
                 Button::builder()
                     //.with_content(Text::new("Login"))
                     .with_sizing(Sizing::fixed(Size::new(100.0, 50.0)))
@@ -18,7 +18,6 @@
 
 layout (set = 1, binding = 0) uniform GlyphInfo {
     mat4 transform;
-    vec4 fill_color;
 } glyph_info;
 
 
@@ -0,0 +1,52 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+#version 450
+
+#define FRAGMENT
+
+#include "glyph_bindings.glsl"
+
+layout(location = 0) in vec2 in_uv;
+layout(location = 1) in vec4 in_color;
+layout(location = 2) in vec4 in_outline_color;
+// outline width and edge softness in distance field units
+layout(location = 3) in vec2 in_effect;
+
+layout(location = 0) out vec4 out_color;
+
+const float SHARPNESS = 2.71;
+
+float median(vec3 v) {
+    return max(min(v.r, v.g), min(max(v.r, v.g), v.b));
+}
+
+void main() {
+    const vec4 texel = texture(sampler2D(glyph_texture, default_sampler), in_uv);
+    const float dist = median(texel.rgb);
+    const float anti_alias = fwidth(dist) / SHARPNESS;
+    const float fill = smoothstep(0.5f - anti_alias, 0.5f + anti_alias, dist);
+
+    // note: the median is only exact near the edge, outlines and shadows use the true distance in alpha
+    const bool expanded = in_effect.x > 0.0f || in_effect.y > 0.0f;
+    const float outer = expanded ? texel.a : dist;
+    const float outer_anti_alias = fwidth(outer) / SHARPNESS;
+    const float edge = 0.5f - in_effect.x;
+    const float coverage = smoothstep(edge - outer_anti_alias - in_effect.y, edge + outer_anti_alias, outer);
+
+    out_color = mix(in_outline_color, in_color, fill);
+    out_color.a *= coverage;
+}
\ No newline at end of file
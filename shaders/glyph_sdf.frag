@@ -21,6 +21,10 @@
 #include "glyph_bindings.glsl"
 
 layout(location = 0) in vec2 in_uv;
+layout(location = 1) in vec4 in_color;
+layout(location = 2) in vec4 in_outline_color;
+// outline width and edge softness in distance field units
+layout(location = 3) in vec2 in_effect;
 
 layout(location = 0) out vec4 out_color;
 
@@ -29,8 +33,12 @@ const float SHARPNESS = 2.71;
 void main() {
     const float dist = texture(sampler2D(glyph_texture, default_sampler), in_uv).r;
     const float anti_alias = fwidth(dist) / SHARPNESS;
-    const float alpha = smoothstep(0.5f - anti_alias, 0.5f + anti_alias, dist);
+    const float fill = smoothstep(0.5f - anti_alias, 0.5f + anti_alias, dist);
 
-    out_color = glyph_info.fill_color;
-    out_color.a *= alpha;
+    // note: the outline moves the outer edge outwards, the softness fades it out
+    const float edge = 0.5f - in_effect.x;
+    const float coverage = smoothstep(edge - anti_alias - in_effect.y, edge + anti_alias, dist);
+
+    out_color = mix(in_outline_color, in_color, fill);
+    out_color.a *= coverage;
 }
\ No newline at end of file
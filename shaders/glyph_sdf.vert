@@ -20,7 +20,16 @@
 
 #include "glyph_bindings.glsl"
 
+layout(location = 0) in vec4 in_rect;
+layout(location = 1) in vec4 in_uv_rect;
+layout(location = 2) in vec4 in_color;
+layout(location = 3) in vec4 in_outline_color;
+layout(location = 4) in vec2 in_effect;
+
 layout(location = 0) out vec2 out_uv;
+layout(location = 1) out vec4 out_color;
+layout(location = 2) out vec4 out_outline_color;
+layout(location = 3) out vec2 out_effect;
 
 vec2 verticies[6] = vec2[](
     vec2(0.0, 0.0),
@@ -31,21 +40,15 @@ vec2 verticies[6] = vec2[](
     vec2(1.0, 1.0)
 );
 
-vec2 uvs[6] = vec2[](
-    vec2(0.0, 0.0),
-    vec2(0.0, 1.0),
-    vec2(1.0, 0.0),
-    vec2(1.0, 0.0),
-    vec2(0.0, 1.0),
-    vec2(1.0, 1.0)
-);
-
 
 void main() {
     vec2 vtx = verticies[gl_VertexIndex];
-    vec2 uv = uvs[gl_VertexIndex];
 
-    out_uv = uv;
+    out_uv = mix(in_uv_rect.xy, in_uv_rect.zw, vtx);
+    out_color = in_color;
+    out_outline_color = in_outline_color;
+    out_effect = in_effect;
 
-    gl_Position = render_info.projection * glyph_info.transform * vec4(vtx, 0.0f, 1.0f);
+    vec2 pos = in_rect.xy + vtx * in_rect.zw;
+    gl_Position = render_info.projection * glyph_info.transform * vec4(pos, 0.0f, 1.0f);
 }
\ No newline at end of file
@@ -20,9 +20,14 @@ use super::window::*;
 use crate::input_handling::*;
 
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use lazy_static::lazy_static;
 
 use winit::event::*;
 use winit::event_loop::*;
+use winit::event_loop::ControlFlow;
 
 /* Application */
 
@@ -72,6 +77,31 @@ impl ApplicationData {
     }
 }
 
+lazy_static! {
+    static ref REDRAW_DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+}
+
+/// Wakes the event loop at `instant` to redraw every window, ex. to blink a caret.
+/// The earliest pending request wins.
+pub(crate) fn request_redraw_at(instant: Instant) {
+    let mut deadline = REDRAW_DEADLINE.lock().unwrap();
+    *deadline = match *deadline {
+        Some(current) if current > Instant::now() && current < instant => Some(current),
+        _ => Some(instant),
+    };
+}
+
+fn redraw_deadline() -> Option<Instant> {
+    let mut deadline = REDRAW_DEADLINE.lock().unwrap();
+    match *deadline {
+        Some(instant) if instant > Instant::now() => Some(instant),
+        _ => {
+            *deadline = None;
+            None
+        }
+    }
+}
+
 pub struct Application<'a> {
     app_data: &'a mut ApplicationData,
     pub(crate) event_loop: &'a EventLoopWindowTarget<()>,
@@ -155,13 +185,16 @@ impl<'a> Application<'a> {
                                 // WindowEvent::ReceivedCharacter(char) => {}
                                 WindowEvent::Focused(_) => {}
                                 WindowEvent::KeyboardInput { event, .. } => {
-                                    let event = input_handling::KeyEvent::new(
+                                    let event = window.key_event(
                                         event.physical_key,
                                         event.state,
+                                        event.text.as_ref().map(|text| text.as_str()),
                                     );
                                     window.on_key(&event);
                                 }
-                                WindowEvent::ModifiersChanged(_) => {}
+                                WindowEvent::ModifiersChanged(modifiers) => {
+                                    window.modifiers_changed(modifiers.state());
+                                }
                                 WindowEvent::Ime(_) => {}
                                 WindowEvent::CursorMoved { position, .. } => {
                                     let event = CursorMovedEvent::new(position.into());
@@ -180,7 +213,7 @@ impl<'a> Application<'a> {
                                     window.on_mouse_wheel(&event);
                                 }
                                 WindowEvent::MouseInput { button, state, .. } => {
-                                    let event = MouseButtonEvent::new(button, state);
+                                    let event = window.mouse_button_event(button, state);
                                     window.on_mouse_button(&event);
                                 }
                                 WindowEvent::TouchpadMagnify { .. } => {}
@@ -189,7 +222,9 @@ impl<'a> Application<'a> {
                                 WindowEvent::TouchpadPressure { .. } => {}
                                 WindowEvent::AxisMotion { .. } => {}
                                 WindowEvent::Touch(_) => {}
-                                WindowEvent::ScaleFactorChanged { .. } => {}
+                                WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                                    window.scale_factor_changed(scale_factor as f32);
+                                }
                                 WindowEvent::ThemeChanged(_) => {}
                                 WindowEvent::Occluded(_) => {}
                                 WindowEvent::ActivationTokenDone { .. } => {}
@@ -210,18 +245,21 @@ impl<'a> Application<'a> {
                     Event::MemoryWarning => {}
                 }
 
-                match controller.run_mode() {
-                    RunMode::Wait => {
-                        event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait)
-                    }
-                    RunMode::WaitTimeout(duration) => event_loop
-                        .set_control_flow(winit::event_loop::ControlFlow::wait_duration(duration)),
-                    RunMode::WaitUntill(instant) => event_loop
-                        .set_control_flow(winit::event_loop::ControlFlow::WaitUntil(instant)),
-                    RunMode::Poll => {
-                        event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll)
+                let control_flow = match controller.run_mode() {
+                    RunMode::Wait => ControlFlow::Wait,
+                    RunMode::WaitTimeout(duration) => ControlFlow::wait_duration(duration),
+                    RunMode::WaitUntill(instant) => ControlFlow::WaitUntil(instant),
+                    RunMode::Poll => ControlFlow::Poll,
+                };
+
+                // note: wake up early for redraws requested by components
+                event_loop.set_control_flow(match (control_flow, redraw_deadline()) {
+                    (ControlFlow::Wait, Some(deadline)) => ControlFlow::WaitUntil(deadline),
+                    (ControlFlow::WaitUntil(instant), Some(deadline)) => {
+                        ControlFlow::WaitUntil(instant.min(deadline))
                     }
-                }
+                    (control_flow, _) => control_flow,
+                });
 
                 if match controller.exit_mode() {
                     ExitMode::Explicit => controller.should_exit(),
@@ -0,0 +1,61 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::{Clipboard, ClipboardError};
+
+use icrate::objc2::rc::Id;
+use icrate::AppKit::{NSPasteboard, NSPasteboardTypeString};
+use icrate::Foundation::NSString;
+
+/// The general `NSPasteboard`.
+pub struct SystemClipboard {
+    pasteboard: Id<NSPasteboard>,
+}
+
+// note: the general pasteboard is shared by the whole process and may be used from any thread
+unsafe impl Send for SystemClipboard {}
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {
+            pasteboard: unsafe { NSPasteboard::generalPasteboard() },
+        })
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let text = unsafe { self.pasteboard.stringForType(NSPasteboardTypeString) }?;
+        Some(text.to_string())
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        let text = NSString::from_str(text);
+        let written = unsafe {
+            self.pasteboard.clearContents();
+            self.pasteboard
+                .setString_forType(&text, NSPasteboardTypeString)
+        };
+
+        if written {
+            Ok(())
+        } else {
+            Err(ClipboardError::System(
+                "the pasteboard refused the text".into(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,136 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(windows)]
+mod windows;
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+mod x11;
+
+#[cfg(target_os = "macos")]
+pub use self::macos::SystemClipboard;
+#[cfg(windows)]
+pub use self::windows::SystemClipboard;
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+pub use self::x11::SystemClipboard;
+
+use lazy_static::lazy_static;
+
+use std::sync::{Mutex, MutexGuard};
+
+/// Error returned when the clipboard can't be opened or written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClipboardError {
+    /// the platform has no clipboard or it can't be reached, ex. no X server is running
+    Unavailable,
+    /// the system clipboard refused the request
+    System(String),
+}
+
+impl std::fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::Unavailable => write!(f, "the system clipboard is unavailable"),
+            ClipboardError::System(reason) => write!(f, "clipboard error: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ClipboardError {}
+
+/// Text storage shared by controls for copy and paste.
+pub trait Clipboard: Send {
+    fn get_text(&mut self) -> Option<String>;
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+/// A clipboard that only lives inside the process.
+/// Used where the platform has no system clipboard, and as a stub in tests.
+#[derive(Debug, Default)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        self.text = Some(text.into());
+        Ok(())
+    }
+}
+
+/// Opens the system clipboard, falling back to a `MemoryClipboard` when it can't be reached.
+#[cfg(any(windows, all(unix, not(any(target_os = "ios", target_os = "android")))))]
+fn system_clipboard() -> Box<dyn Clipboard> {
+    match SystemClipboard::new() {
+        Ok(clipboard) => Box::new(clipboard),
+        Err(_) => Box::new(MemoryClipboard::new()),
+    }
+}
+
+#[cfg(not(any(windows, all(unix, not(any(target_os = "ios", target_os = "android"))))))]
+fn system_clipboard() -> Box<dyn Clipboard> {
+    Box::new(MemoryClipboard::new())
+}
+
+lazy_static! {
+    static ref CLIPBOARD: Mutex<Box<dyn Clipboard>> = Mutex::new(system_clipboard());
+}
+
+/// Replaces the clipboard used by all controls, ex. with a `MemoryClipboard` in tests.
+pub fn set_clipboard(value: impl Clipboard + 'static) {
+    *clipboard() = Box::new(value);
+}
+
+/// Returns the clipboard used by all controls, the system clipboard unless another one was set.
+pub fn clipboard() -> MutexGuard<'static, Box<dyn Clipboard>> {
+    // note: a panic while the clipboard was held leaves it usable
+    CLIPBOARD
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+lazy_static! {
+    static ref TEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Installs an empty `MemoryClipboard` for a test.
+/// The clipboard is global, the guard keeps tests running in parallel from sharing it.
+#[cfg(test)]
+pub(crate) fn test_clipboard() -> MutexGuard<'static, ()> {
+    let guard = TEST_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    set_clipboard(MemoryClipboard::new());
+    guard
+}
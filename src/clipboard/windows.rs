@@ -0,0 +1,131 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::{Clipboard, ClipboardError};
+
+use std::ffi::c_void;
+use std::ptr;
+use std::thread;
+use std::time::Duration;
+
+const CF_UNICODETEXT: u32 = 13;
+const GMEM_MOVEABLE: u32 = 0x0002;
+
+#[link(name = "user32")]
+extern "system" {
+    fn OpenClipboard(owner: *mut c_void) -> i32;
+    fn CloseClipboard() -> i32;
+    fn EmptyClipboard() -> i32;
+    fn GetClipboardData(format: u32) -> *mut c_void;
+    fn SetClipboardData(format: u32, memory: *mut c_void) -> *mut c_void;
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GlobalAlloc(flags: u32, bytes: usize) -> *mut c_void;
+    fn GlobalFree(memory: *mut c_void) -> *mut c_void;
+    fn GlobalLock(memory: *mut c_void) -> *mut c_void;
+    fn GlobalUnlock(memory: *mut c_void) -> i32;
+    fn GlobalSize(memory: *mut c_void) -> usize;
+}
+
+/// The Windows clipboard, opened for each read or write.
+#[derive(Debug)]
+pub struct SystemClipboard {}
+
+impl SystemClipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(Self {})
+    }
+}
+
+/// Keeps the clipboard open until dropped.
+struct OpenClipboardGuard {}
+
+impl OpenClipboardGuard {
+    fn open() -> Result<Self, ClipboardError> {
+        // note: another process may hold the clipboard for a moment
+        for _ in 0..10 {
+            if unsafe { OpenClipboard(ptr::null_mut()) } != 0 {
+                return Ok(Self {});
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        Err(ClipboardError::Unavailable)
+    }
+}
+
+impl Drop for OpenClipboardGuard {
+    fn drop(&mut self) {
+        unsafe {
+            CloseClipboard();
+        }
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let _open = OpenClipboardGuard::open().ok()?;
+        unsafe {
+            let memory = GetClipboardData(CF_UNICODETEXT);
+            if memory.is_null() {
+                return None;
+            }
+            let data = GlobalLock(memory) as *const u16;
+            if data.is_null() {
+                return None;
+            }
+
+            let units = std::slice::from_raw_parts(data, GlobalSize(memory) / 2);
+            let end = units
+                .iter()
+                .position(|&unit| unit == 0)
+                .unwrap_or(units.len());
+            let text = String::from_utf16_lossy(&units[..end]);
+            GlobalUnlock(memory);
+            Some(text)
+        }
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        let units: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+
+        let _open = OpenClipboardGuard::open()?;
+        unsafe {
+            let memory = GlobalAlloc(GMEM_MOVEABLE, units.len() * 2);
+            if memory.is_null() {
+                return Err(ClipboardError::System("out of memory".into()));
+            }
+            let data = GlobalLock(memory) as *mut u16;
+            if data.is_null() {
+                GlobalFree(memory);
+                return Err(ClipboardError::System("out of memory".into()));
+            }
+            ptr::copy_nonoverlapping(units.as_ptr(), data, units.len());
+            GlobalUnlock(memory);
+
+            EmptyClipboard();
+            // note: the clipboard owns the memory once it was set
+            if SetClipboardData(CF_UNICODETEXT, memory).is_null() {
+                GlobalFree(memory);
+                return Err(ClipboardError::System(
+                    "the clipboard refused the text".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
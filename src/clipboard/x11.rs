@@ -0,0 +1,279 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::{Clipboard, ClipboardError};
+
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    AtomEnum, ClientMessageEvent, ConnectionExt, CreateWindowAux, EventMask, PropMode,
+    SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass, SELECTION_NOTIFY_EVENT,
+};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::{ConnectionError, RustConnection};
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, COPY_FROM_PARENT, CURRENT_TIME, NONE};
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+// note: how long to wait for the owner of the selection to answer
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        CLIPBOARD,
+        UTF8_STRING,
+        TARGETS,
+        INCR,
+        UXUI_CLIPBOARD,
+        UXUI_STOP,
+    }
+}
+
+/// State shared with the thread that answers requests for the selection.
+struct Shared {
+    connection: RustConnection,
+    window: Window,
+    atoms: Atoms,
+    /// text of the selection while this process owns it
+    text: Mutex<Option<String>>,
+}
+
+/// The X11 `CLIPBOARD` selection, answered from a background thread while this process owns it.
+/// Wayland sessions are reached through XWayland.
+pub struct SystemClipboard {
+    shared: Arc<Shared>,
+    notifications: Receiver<SelectionNotifyEvent>,
+}
+
+impl SystemClipboard {
+    /// Connects to the X server of `DISPLAY`.
+    pub fn new() -> Result<Self, ClipboardError> {
+        let (connection, screen) = x11rb::connect(None).map_err(|_| ClipboardError::Unavailable)?;
+        let root = connection.setup().roots[screen].root;
+
+        let window = connection.generate_id().map_err(system_error)?;
+        connection
+            .create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_ONLY,
+                COPY_FROM_PARENT,
+                &CreateWindowAux::new(),
+            )
+            .map_err(system_error)?;
+        let atoms = Atoms::new(&connection)
+            .map_err(system_error)?
+            .reply()
+            .map_err(system_error)?;
+
+        let shared = Arc::new(Shared {
+            connection,
+            window,
+            atoms,
+            text: Mutex::new(None),
+        });
+
+        let (sender, notifications) = channel();
+        let serving = shared.clone();
+        thread::Builder::new()
+            .name("uxui clipboard".into())
+            .spawn(move || serving.serve(&sender))
+            .map_err(system_error)?;
+
+        Ok(Self {
+            shared,
+            notifications,
+        })
+    }
+}
+
+impl Shared {
+    fn text(&self) -> MutexGuard<'_, Option<String>> {
+        self.text
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn owns_selection(&self) -> bool {
+        self.connection
+            .get_selection_owner(self.atoms.CLIPBOARD)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .is_some_and(|reply| reply.owner == self.window)
+    }
+
+    /// Handles the events of the window until the clipboard is dropped.
+    fn serve(&self, notifications: &Sender<SelectionNotifyEvent>) {
+        while let Ok(event) = self.connection.wait_for_event() {
+            match event {
+                // note: a failed answer only leaves the requesting client without text
+                Event::SelectionRequest(request) => {
+                    let _ = self.answer(&request);
+                }
+                Event::SelectionClear(_) if !self.owns_selection() => *self.text() = None,
+                Event::SelectionNotify(notify) if notifications.send(notify).is_err() => break,
+                Event::ClientMessage(message) if message.type_ == self.atoms.UXUI_STOP => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Stores the owned text on the requesting client's window and tells it whether that worked.
+    fn answer(&self, request: &SelectionRequestEvent) -> Result<(), ConnectionError> {
+        let atoms = &self.atoms;
+        // note: clients older than ICCCM 2.0 leave the property unset and expect the target to be used
+        let property = if request.property == NONE {
+            request.target
+        } else {
+            request.property
+        };
+
+        let text = self.text().clone();
+        let stored = match text {
+            Some(_) if request.selection != atoms.CLIPBOARD => false,
+            Some(_) if request.target == atoms.TARGETS => {
+                self.connection.change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &[atoms.TARGETS, atoms.UTF8_STRING],
+                )?;
+                true
+            }
+            // todo: send text larger than the maximum request size in increments
+            Some(text) if request.target == atoms.UTF8_STRING => {
+                self.connection.change_property8(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    atoms.UTF8_STRING,
+                    text.as_bytes(),
+                )?;
+                true
+            }
+            _ => false,
+        };
+
+        let notify = SelectionNotifyEvent {
+            response_type: SELECTION_NOTIFY_EVENT,
+            sequence: 0,
+            time: request.time,
+            requestor: request.requestor,
+            selection: request.selection,
+            target: request.target,
+            property: if stored { property } else { NONE },
+        };
+        self.connection
+            .send_event(false, request.requestor, EventMask::NO_EVENT, notify)?;
+        self.connection.flush()
+    }
+}
+
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        let shared = &self.shared;
+        if shared.owns_selection() {
+            return shared.text().clone();
+        }
+
+        // note: drop answers to earlier requests that timed out
+        while self.notifications.try_recv().is_ok() {}
+
+        let atoms = &shared.atoms;
+        shared
+            .connection
+            .convert_selection(
+                shared.window,
+                atoms.CLIPBOARD,
+                atoms.UTF8_STRING,
+                atoms.UXUI_CLIPBOARD,
+                CURRENT_TIME,
+            )
+            .ok()?;
+        shared.connection.flush().ok()?;
+
+        let notify = self.notifications.recv_timeout(TIMEOUT).ok()?;
+        if notify.property == NONE {
+            return None;
+        }
+
+        let reply = shared
+            .connection
+            .get_property(
+                true,
+                shared.window,
+                atoms.UXUI_CLIPBOARD,
+                AtomEnum::ANY,
+                0,
+                u32::MAX,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        // todo: receive text that the owner sends in increments
+        if reply.type_ == atoms.INCR {
+            return None;
+        }
+        String::from_utf8(reply.value).ok()
+    }
+
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        let shared = &self.shared;
+        *shared.text() = Some(text.into());
+
+        shared
+            .connection
+            .set_selection_owner(shared.window, shared.atoms.CLIPBOARD, CURRENT_TIME)
+            .map_err(system_error)?;
+        shared.connection.flush().map_err(system_error)?;
+
+        if shared.owns_selection() {
+            Ok(())
+        } else {
+            Err(ClipboardError::System(
+                "the clipboard selection could not be taken".into(),
+            ))
+        }
+    }
+}
+
+impl Drop for SystemClipboard {
+    fn drop(&mut self) {
+        let shared = &self.shared;
+        let stop = ClientMessageEvent::new(32, shared.window, shared.atoms.UXUI_STOP, [0u32; 5]);
+
+        // note: the text is no longer offered once the window is gone
+        let _ = shared
+            .connection
+            .send_event(false, shared.window, EventMask::NO_EVENT, stop);
+        let _ = shared.connection.destroy_window(shared.window);
+        let _ = shared.connection.flush();
+    }
+}
+
+fn system_error(err: impl std::fmt::Display) -> ClipboardError {
+    ClipboardError::System(err.to_string())
+}
@@ -37,8 +37,9 @@ pub trait Draw {
 pub trait Layout {
     // fn layout_dirty(&self) -> bool { false }
 
-    fn measure(&mut self, available_size: Size) -> Size;
-    fn arrange(&mut self, final_rect: Rect) -> Rect;
+    /// `scale_factor` is the number of physical pixels per logical pixel of the window.
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size;
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect;
 }
 
 /*
@@ -88,11 +88,11 @@ impl Button {
 }
 
 impl Layout for Button {
-    fn measure(&mut self, available_size: Size) -> Size {
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size {
         let available = self.sizing.calc_available_size(available_size);
         let required = match &mut self.content {
             Some(content) => {
-                let content_size = content.content.measure(available);
+                let content_size = content.content.measure(available, scale_factor);
                 content.final_size.set(content_size);
                 content_size
             }
@@ -101,11 +101,11 @@ impl Layout for Button {
         self.sizing.calc_final_size(available, required)
     }
 
-    fn arrange(&mut self, final_rect: Rect) -> Rect {
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
         let _content_rect = match &mut self.content {
             Some(content) => {
                 let child_rect = final_rect.align_center(content.final_size.get());
-                content.content.arrange(child_rect)
+                content.content.arrange(child_rect, scale_factor)
             }
             None => Rect::default(),
         };
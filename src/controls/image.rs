@@ -106,7 +106,7 @@ impl Image {
 }
 
 impl Layout for Image {
-    fn measure(&mut self, available_size: Size) -> Size {
+    fn measure(&mut self, available_size: Size, _scale_factor: f32) -> Size {
         let available = self.sizing.calc_available_size(available_size);
         // let required = Size::new(self.texture.width() as f32, self.texture.height() as f32);
         let final_size = self.sizing.calc_final_size(available, available);
@@ -114,7 +114,7 @@ impl Layout for Image {
         final_size
     }
 
-    fn arrange(&mut self, final_rect: Rect) -> Rect {
+    fn arrange(&mut self, final_rect: Rect, _scale_factor: f32) -> Rect {
         let final_rect = final_rect.align_center(self.final_size.get());
         self.visual = Some(VisualImage::new(final_rect, &self.view));
         final_rect
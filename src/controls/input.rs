@@ -14,68 +14,866 @@
   limitations under the License.
 */
 
-// use crate::component::*;
-// use crate::drawing::*;
-// use crate::*;
-// use input_handling::*;
-
-// pub struct InputBuilder {
-//     hint: Option<String>,
-//     text: Option<BindableString>,
-// }
-//
-// impl InputBuilder {
-//     pub fn with_hint(mut self, text: &str) -> Self {
-//         self.hint.replace(text.into());
-//         self
-//     }
-//
-//     pub fn with_text(mut self, text: &str) -> Self {
-//         self.text.replace(BindableString::Static(text.into()));
-//         self
-//     }
-//
-//     // pub fn with_binding(mut self, binding: StringPropertyBinding) -> Self {
-//     //     self.text.replace(BindableString::Binding(binding));
-//     //     self
-//     // }
-//
-//     pub fn build(self) -> Box<Input> {
-//         Box::new(Input {
-//             hint: self.hint,
-//             text: self.text.unwrap_or_default(),
-//         })
-//     }
-// }
-//
-// pub struct Input {
-//     hint: Option<String>,
-//     text: BindableString,
-// }
-//
-// impl Input {
-//     pub fn builder() -> InputBuilder {
-//         InputBuilder {
-//             hint: None,
-//             text: None,
-//         }
-//     }
-// }
-//
-// impl InputHandler for Input {}
-//
-// impl PreviewInputHandler for Input {}
-//
-// impl ComponentController for Input {
-//     fn measure(&mut self, available_size: Size, children: &[Component]) -> Size {
-//         todo!()
-//     }
-//
-//     fn arrange(&mut self, final_rect: Rect, children: &[Component]) -> Rect {
-//         todo!()
-//     }
-//
-//     fn draw(&self, context: &mut DrawingContext) {
-//         todo!()
-//     }
-// }
+use crate::application::request_redraw_at;
+use crate::clipboard::clipboard;
+use crate::component::*;
+use crate::drawing::*;
+use crate::*;
+use input_handling::*;
+
+use crate::text::{
+    layout_styled_text, Paragraph, ParagraphStyle, TextLayout, TextStyles, TextWrapping, WrapMode,
+};
+
+use super::text::{FontInfo, TextFormatter};
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+/// Time the caret stays visible, then hidden, while blinking.
+const CARET_BLINK: Duration = Duration::from_millis(530);
+
+/// Space between the border and the text, in logical pixels.
+const PADDING: f32 = 4.0;
+
+/// Drawn in place of every grapheme of a password.
+const MASK: char = '\u{2022}';
+
+pub struct InputBuilder {
+    hint: Option<String>,
+    text: String,
+    binding: Option<StringPropertyBinding>,
+    font_info: FontInfo,
+    foreground: Color,
+    hint_foreground: Color,
+    background: Color,
+    selection_color: Color,
+    sizing: Sizing,
+    password: bool,
+}
+
+impl InputBuilder {
+    pub fn with_hint(mut self, text: &str) -> Self {
+        self.hint.replace(text.into());
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    /// Keeps the text and `binding` in sync, the binding's value replaces the text.
+    pub fn with_binding(mut self, binding: StringPropertyBinding) -> Self {
+        self.binding.replace(binding);
+        self
+    }
+
+    pub fn with_font_info(mut self, font_info: FontInfo) -> Self {
+        self.font_info = font_info;
+        self
+    }
+
+    pub fn with_foreground(mut self, color: Color) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    pub fn with_hint_foreground(mut self, color: Color) -> Self {
+        self.hint_foreground = color;
+        self
+    }
+
+    pub fn with_background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: Color) -> Self {
+        self.selection_color = color;
+        self
+    }
+
+    pub fn with_sizing(mut self, sizing: Sizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Masks every character and disables copy and cut.
+    pub fn with_password(mut self, password: bool) -> Self {
+        self.password = password;
+        self
+    }
+}
+
+impl Builder<Input> for InputBuilder {
+    fn build(self) -> Input {
+        let text = match &self.binding {
+            Some(binding) => binding.get(),
+            None => self.text,
+        };
+
+        let mut input = Input {
+            layout: TextLayout::default(),
+            hint_layout: None,
+            caret: text.len(),
+            anchor: text.len(),
+            text,
+            hint: self.hint,
+            binding: self.binding,
+            font_info: self.font_info,
+            foreground: self.foreground,
+            hint_foreground: self.hint_foreground,
+            background_color: self.background,
+            selection_color: self.selection_color,
+            sizing: self.sizing,
+            password: self.password,
+            masked: String::new(),
+            scroll: 0.0,
+            scale_factor: 1.0,
+            focused: false,
+            dragging: false,
+            blink_start: Instant::now(),
+            visual_dirty: true,
+            final_rect: Rect::default(),
+            content_rect: Rect::default(),
+            background: None,
+            formatted_text: None,
+            caret_visual: None,
+            caret_visible: false,
+            selection_visuals: Vec::new(),
+            selection_visible: 0,
+        };
+        input.relayout();
+        input
+    }
+}
+
+/// A single line text box with a caret, selection and clipboard support.
+#[derive(Debug)]
+pub struct Input {
+    text: String,
+    hint: Option<String>,
+    binding: Option<StringPropertyBinding>,
+    font_info: FontInfo,
+    foreground: Color,
+    hint_foreground: Color,
+    background_color: Color,
+    selection_color: Color,
+    sizing: Sizing,
+    password: bool,
+    /// drawn text of a password input, one mask per grapheme of `text`
+    masked: String,
+    /// layout of the drawn text, kept up to date with every edit
+    layout: TextLayout,
+    hint_layout: Option<TextLayout>,
+    /// byte index of the caret
+    caret: usize,
+    /// byte index the selection started at, equal to `caret` when nothing is selected
+    anchor: usize,
+    /// how far the text is scrolled left to keep the caret in view
+    scroll: f32,
+    /// scale factor of the window the text is laid out for, updated on every measure
+    scale_factor: f32,
+    focused: bool,
+    dragging: bool,
+    blink_start: Instant,
+    /// set when the drawn text no longer matches `text` or the hint
+    visual_dirty: bool,
+    final_rect: Rect,
+    content_rect: Rect,
+    /// created on the first arrange, like the other visuals
+    background: Option<VisualRectangle>,
+    formatted_text: Option<VisualText>,
+    caret_visual: Option<VisualRectangle>,
+    caret_visible: bool,
+    selection_visuals: Vec<VisualRectangle>,
+    /// number of `selection_visuals` covering the current selection
+    selection_visible: usize,
+}
+
+impl Input {
+    pub fn builder() -> InputBuilder {
+        InputBuilder {
+            hint: None,
+            text: String::new(),
+            binding: None,
+            font_info: Default::default(),
+            foreground: Color::BLACK,
+            hint_foreground: Color::grey(0.55),
+            background: Color::WHITE,
+            selection_color: Color::rgba(0.2, 0.45, 0.9, 0.35),
+            sizing: Sizing::new(Dimension::fill(), Dimension::fit()),
+            password: false,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replaces the text and moves the caret to its end.
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.into();
+        self.caret = self.text.len();
+        self.anchor = self.caret;
+        self.text_changed();
+    }
+
+    /// Selected byte range of the text, empty when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        self.anchor.min(self.caret)..self.anchor.max(self.caret)
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Text as it is drawn, masked in password inputs.
+    fn shown_text(&self) -> &str {
+        if self.password {
+            &self.masked
+        } else {
+            &self.text
+        }
+    }
+
+    /// Geometry of the drawn text, its indices are converted with `shown_index` and `text_index`.
+    fn paragraph(&self) -> Paragraph<'_> {
+        self.layout.paragraph(self.shown_text())
+    }
+
+    /// Maps a byte index of the text to the drawn text.
+    fn shown_index(&self, index: usize) -> usize {
+        if !self.password {
+            return index;
+        }
+        self.text[..index].graphemes(true).count() * MASK.len_utf8()
+    }
+
+    /// Maps a byte index of the drawn text back to the text.
+    fn text_index(&self, index: usize) -> usize {
+        if !self.password {
+            return index;
+        }
+        self.text
+            .grapheme_indices(true)
+            .nth(index / MASK.len_utf8())
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    fn shown_range(&self, range: Range<usize>) -> Range<usize> {
+        self.shown_index(range.start)..self.shown_index(range.end)
+    }
+
+    /// Runs a caret query of the drawn paragraph from the caret, ex. the previous word.
+    fn caret_query(&self, query: impl Fn(&Paragraph, usize) -> usize) -> usize {
+        self.text_index(query(&self.paragraph(), self.shown_index(self.caret)))
+    }
+
+    fn layout_line(&self, text: &str, color: Color) -> TextLayout {
+        let style = match TextFormatter::text_style(&self.font_info, color) {
+            Ok(style) => style,
+            // note: without any font there is nothing to draw, the empty layout is retried next frame
+            Err(_) => return TextLayout::default(),
+        };
+        let styles = TextStyles::uniform(style);
+        let wrapping = TextWrapping {
+            mode: WrapMode::None,
+            ..Default::default()
+        };
+        layout_styled_text(
+            text,
+            &styles,
+            self.font_info.direction,
+            &wrapping,
+            &ParagraphStyle::default(),
+            f32::INFINITY,
+            self.scale_factor,
+        )
+    }
+
+    /// Lays out the text again, masked in password inputs.
+    fn layout_text(&mut self) {
+        if self.password {
+            self.masked = self.text.graphemes(true).map(|_| MASK).collect();
+        }
+        self.layout = self.layout_line(self.shown_text(), self.foreground);
+        self.visual_dirty = true;
+    }
+
+    fn relayout(&mut self) {
+        self.layout_text();
+        self.hint_layout = self
+            .hint
+            .as_ref()
+            .map(|hint| self.layout_line(hint, self.hint_foreground));
+    }
+
+    fn text_changed(&mut self) {
+        self.layout_text();
+        if let Some(binding) = &self.binding {
+            binding.set(&self.text);
+        }
+        self.reset_blink();
+    }
+
+    fn reset_blink(&mut self) {
+        self.blink_start = Instant::now();
+    }
+
+    /// Moves the caret, keeping the selection anchor when `extend` is set.
+    fn move_caret(&mut self, index: usize, extend: bool) {
+        self.caret = index;
+        if !extend {
+            self.anchor = index;
+        }
+        self.reset_blink();
+    }
+
+    /// Replaces the selection with `text`, leaving the caret after the inserted text.
+    fn replace_selection(&mut self, text: &str) {
+        // note: the input is a single line, drop line breaks and other control characters
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+
+        let range = self.selection();
+        self.text.replace_range(range.clone(), &text);
+        self.caret = range.start + text.len();
+        self.anchor = self.caret;
+        self.text_changed();
+    }
+
+    /// Deletes the selection, or the text between the caret and `index` when nothing is selected.
+    fn delete_to(&mut self, index: usize) {
+        if self.anchor == self.caret {
+            self.anchor = index;
+        }
+        self.replace_selection("");
+    }
+
+    /// Copies the selection to the clipboard, passwords are never copied.
+    fn copy(&self) -> bool {
+        let range = self.selection();
+        !self.password && !range.is_empty() && clipboard().set_text(&self.text[range]).is_ok()
+    }
+
+    fn paste(&mut self) -> bool {
+        match clipboard().get_text() {
+            Some(text) => {
+                self.replace_selection(&text);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the caret position under `pos`, in window coordinates.
+    fn hit_test(&self, pos: Point) -> usize {
+        let origin = self
+            .formatted_text
+            .as_ref()
+            .map_or(self.content_rect.pos, |visual| visual.rect().pos);
+        let hit = self
+            .paragraph()
+            .hit_test(Point::new(pos.x - origin.x, pos.y - origin.y));
+        self.text_index(hit.index)
+    }
+
+    /// Scrolls the text so the caret stays inside the content box.
+    fn scroll_to_caret(&mut self) {
+        let caret = self.paragraph().caret_rect(self.shown_index(self.caret));
+        let width = self.content_rect.size.width;
+
+        if caret.pos.x + caret.size.width - self.scroll > width {
+            self.scroll = caret.pos.x + caret.size.width - width;
+        }
+        if caret.pos.x < self.scroll {
+            self.scroll = caret.pos.x;
+        }
+
+        // note: scroll back when text is removed from the end
+        let max_scroll = (self.layout.size.width + caret.size.width - width).max(0.0);
+        self.scroll = self.scroll.min(max_scroll).max(0.0);
+    }
+
+    fn update_selection_visuals(&mut self, origin: Point) {
+        let rects = self
+            .paragraph()
+            .selection_rects(self.shown_range(self.selection()));
+        for (index, rect) in rects.iter().enumerate() {
+            let rect = Rect::new(
+                Point::new(origin.x + rect.pos.x, origin.y + rect.pos.y),
+                rect.size,
+            );
+            match self.selection_visuals.get(index) {
+                Some(visual) => visual.update(rect, self.selection_color),
+                None => self
+                    .selection_visuals
+                    .push(VisualRectangle::new(rect, self.selection_color)),
+            }
+        }
+        self.selection_visible = rects.len();
+    }
+
+    /// Updates the caret, blinking while focused and scheduling the next blink.
+    fn update_caret_visual(&mut self, origin: Point) {
+        if !self.focused {
+            self.caret_visible = false;
+            return;
+        }
+
+        let elapsed = self.blink_start.elapsed();
+        let phase = elapsed.as_millis() / CARET_BLINK.as_millis();
+        self.caret_visible = phase.is_multiple_of(2);
+        request_redraw_at(self.blink_start + CARET_BLINK * (phase as u32 + 1));
+
+        let caret = self.paragraph().caret_rect(self.shown_index(self.caret));
+        update_rectangle(
+            &mut self.caret_visual,
+            Rect::new(
+                Point::new(origin.x + caret.pos.x, origin.y + caret.pos.y),
+                caret.size,
+            ),
+            self.foreground,
+        );
+    }
+}
+
+impl Layout for Input {
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size {
+        self.scale_factor = scale_factor;
+
+        // note: pick up values set on the bound property since the last layout
+        if let Some(binding) = &self.binding {
+            let value = binding.get();
+            if value != self.text {
+                self.text = value;
+                self.caret = self.caret.min(self.text.len());
+                self.anchor = self.caret;
+                while !self.text.is_char_boundary(self.caret) {
+                    self.caret -= 1;
+                    self.anchor = self.caret;
+                }
+                self.relayout();
+            }
+        }
+
+        if !self.layout.is_valid_for(f32::INFINITY, scale_factor) {
+            self.relayout();
+        }
+
+        let padding = PADDING * scale_factor;
+        let line = match &self.hint_layout {
+            Some(hint) if self.text.is_empty() => hint.size,
+            _ => self.layout.size,
+        };
+
+        let available = self.sizing.calc_available_size(available_size);
+        let required = Size::new(
+            line.width + scale_factor + padding * 2.0,
+            line.height + padding * 2.0,
+        );
+        self.sizing.calc_final_size(available, required)
+    }
+
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
+        let padding = PADDING * scale_factor;
+        let content_rect = Rect::new(
+            Point::new(final_rect.pos.x + padding, final_rect.pos.y + padding),
+            Size::new(
+                (final_rect.size.width - padding * 2.0).max(0.0),
+                (final_rect.size.height - padding * 2.0).max(0.0),
+            ),
+        );
+        self.content_rect = content_rect;
+        self.final_rect = final_rect;
+        update_rectangle(&mut self.background, final_rect, self.background_color);
+
+        self.scroll_to_caret();
+
+        let shown = match &self.hint_layout {
+            Some(hint) if self.text.is_empty() => hint,
+            _ => &self.layout,
+        };
+        let origin = Point::new(
+            content_rect.pos.x - self.scroll,
+            content_rect.pos.y + (content_rect.size.height - shown.size.height) / 2.0,
+        );
+        let rect = Rect::new(origin, shown.size);
+
+        match &mut self.formatted_text {
+            Some(visual) if !self.visual_dirty => {
+                visual.update_pos(origin);
+                visual.refresh();
+            }
+            Some(visual) => visual.update_layout(shown.clone(), rect),
+            None => self.formatted_text = Some(VisualText::new(shown.clone(), rect)),
+        }
+        self.visual_dirty = false;
+
+        self.update_selection_visuals(origin);
+        self.update_caret_visual(origin);
+        final_rect
+    }
+}
+
+impl Draw for Input {
+    fn draw<'a>(&'a self, context: &mut DrawingContext<'a>) {
+        if let Some(background) = &self.background {
+            context.draw(background);
+        }
+
+        context.push_scissor(self.content_rect);
+        for visual in &self.selection_visuals[..self.selection_visible] {
+            context.draw(visual);
+        }
+        if let Some(visual) = &self.formatted_text {
+            context.draw(visual);
+        }
+        if let Some(caret) = self.caret_visual.as_ref().filter(|_| self.caret_visible) {
+            context.draw(caret);
+        }
+        context.pop_scissor();
+    }
+}
+
+impl InputHandler for Input {
+    fn on_key(&mut self, event: &KeyEvent) -> bool {
+        if !self.focused || event.state() != ElementState::Pressed {
+            return false;
+        }
+
+        let modifiers = event.modifiers();
+        let extend = modifiers.shift_key();
+        let by_word = event.word_modifier();
+
+        let code = match event.key() {
+            PhysicalKey::Code(code) => Some(code),
+            PhysicalKey::Unidentified(_) => None,
+        };
+
+        match code {
+            Some(KeyCode::ArrowLeft) => {
+                let index = match self.selection() {
+                    range if !range.is_empty() && !extend && !by_word => range.start,
+                    _ if by_word => {
+                        self.caret_query(|paragraph, index| paragraph.previous_word(index))
+                    }
+                    _ => self.caret_query(|paragraph, index| paragraph.previous_grapheme(index)),
+                };
+                self.move_caret(index, extend);
+            }
+            Some(KeyCode::ArrowRight) => {
+                let index = match self.selection() {
+                    range if !range.is_empty() && !extend && !by_word => range.end,
+                    _ if by_word => self.caret_query(|paragraph, index| paragraph.next_word(index)),
+                    _ => self.caret_query(|paragraph, index| paragraph.next_grapheme(index)),
+                };
+                self.move_caret(index, extend);
+            }
+            Some(KeyCode::Home) => self.move_caret(0, extend),
+            Some(KeyCode::End) => self.move_caret(self.text.len(), extend),
+            Some(KeyCode::Backspace) => {
+                let index = if by_word {
+                    self.caret_query(|paragraph, index| paragraph.previous_word(index))
+                } else {
+                    self.caret_query(|paragraph, index| paragraph.previous_grapheme(index))
+                };
+                self.delete_to(index);
+            }
+            Some(KeyCode::Delete) => {
+                let index = if by_word {
+                    self.caret_query(|paragraph, index| paragraph.next_word(index))
+                } else {
+                    self.caret_query(|paragraph, index| paragraph.next_grapheme(index))
+                };
+                self.delete_to(index);
+            }
+            Some(KeyCode::KeyA) if event.shortcut() => {
+                self.anchor = 0;
+                self.move_caret(self.text.len(), true);
+            }
+            Some(KeyCode::KeyC) if event.shortcut() => {
+                self.copy();
+            }
+            Some(KeyCode::KeyX) if event.shortcut() => {
+                if self.copy() {
+                    self.replace_selection("");
+                }
+            }
+            Some(KeyCode::KeyV) if event.shortcut() => {
+                self.paste();
+            }
+            _ => match event.text() {
+                // note: shortcuts that are not handled above should not type their letter
+                Some(text) if !event.shortcut() && text.chars().any(|c| !c.is_control()) => {
+                    self.replace_selection(text);
+                }
+                _ => return false,
+            },
+        }
+
+        true
+    }
+
+    fn on_mouse_button(&mut self, event: &MouseButtonEvent) -> bool {
+        if event.button() != MouseButton::Left {
+            return false;
+        }
+
+        if event.state() == ElementState::Released {
+            self.dragging = false;
+            return false;
+        }
+
+        if !self.final_rect.contains(event.pos()) {
+            self.focused = false;
+            self.dragging = false;
+            self.anchor = self.caret;
+            return false;
+        }
+
+        self.focused = true;
+        let index = self.hit_test(event.pos());
+        match event.click_count() {
+            1 => {
+                self.move_caret(index, false);
+                self.dragging = true;
+            }
+            // note: the words of a password are hidden, a double click selects all of it
+            2 if !self.password => {
+                let word = self.paragraph().word_at(self.shown_index(index));
+                self.anchor = self.text_index(word.start);
+                self.move_caret(self.text_index(word.end), true);
+            }
+            _ => {
+                self.anchor = 0;
+                self.move_caret(self.text.len(), true);
+            }
+        }
+
+        // note: let the press reach other inputs so they lose focus
+        false
+    }
+
+    fn on_cursor_moved(&mut self, event: &CursorMovedEvent) -> bool {
+        if !self.dragging {
+            return false;
+        }
+
+        let index = self.hit_test(event.pos());
+        self.move_caret(index, true);
+        true
+    }
+}
+
+impl PreviewInputHandler for Input {}
+
+impl DispatchInput for Input {}
+
+impl Component for Input {}
+
+/// Moves `visual` over `rect`, creating it on first use.
+fn update_rectangle(visual: &mut Option<VisualRectangle>, rect: Rect, color: Color) {
+    match visual {
+        Some(visual) => visual.update(rect, color),
+        None => *visual = Some(VisualRectangle::new(rect, color)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::test_clipboard;
+
+    fn focused(builder: InputBuilder) -> Input {
+        let mut input = builder.build();
+        input.focused = true;
+        input
+    }
+
+    fn key_with(code: KeyCode, modifiers: Modifiers) -> KeyEvent {
+        KeyEvent::new(
+            PhysicalKey::Code(code),
+            ElementState::Pressed,
+            modifiers,
+            None,
+        )
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        key_with(code, Modifiers::empty())
+    }
+
+    fn shortcut(code: KeyCode) -> KeyEvent {
+        if cfg!(target_os = "macos") {
+            key_with(code, Modifiers::SUPER)
+        } else {
+            key_with(code, Modifiers::CONTROL)
+        }
+    }
+
+    /// Option on macOS, Ctrl elsewhere.
+    fn word_modifier() -> Modifiers {
+        if cfg!(target_os = "macos") {
+            Modifiers::ALT
+        } else {
+            Modifiers::CONTROL
+        }
+    }
+
+    #[test]
+    fn moves_the_caret() {
+        let mut input = focused(Input::builder().with_text("héllo"));
+        assert_eq!(input.caret, 6);
+
+        assert!(input.on_key(&key(KeyCode::ArrowLeft)));
+        assert_eq!(input.selection(), 5..5);
+        assert!(input.on_key(&key(KeyCode::Home)));
+        assert_eq!(input.selection(), 0..0);
+        // note: "é" is two bytes
+        assert!(input.on_key(&key(KeyCode::ArrowRight)));
+        assert!(input.on_key(&key(KeyCode::ArrowRight)));
+        assert_eq!(input.selection(), 3..3);
+        assert!(input.on_key(&key(KeyCode::End)));
+        assert_eq!(input.selection(), 6..6);
+
+        input.focused = false;
+        assert!(!input.on_key(&key(KeyCode::Home)));
+        assert_eq!(input.caret, 6);
+    }
+
+    #[test]
+    fn jumps_over_words() {
+        let mut input = focused(Input::builder().with_text("hello big world"));
+
+        assert!(input.on_key(&key_with(KeyCode::ArrowLeft, word_modifier())));
+        assert_eq!(input.caret, 10);
+        assert!(input.on_key(&key_with(KeyCode::ArrowLeft, word_modifier())));
+        assert_eq!(input.caret, 6);
+        assert!(input.on_key(&key_with(KeyCode::ArrowRight, word_modifier())));
+        assert_eq!(input.caret, 9);
+
+        // note: the other platform's word modifier moves by a single grapheme
+        let other = if cfg!(target_os = "macos") {
+            Modifiers::CONTROL
+        } else {
+            Modifiers::ALT
+        };
+        assert!(input.on_key(&key_with(KeyCode::ArrowLeft, other)));
+        assert_eq!(input.caret, 8);
+
+        assert!(input.on_key(&key_with(KeyCode::Backspace, word_modifier())));
+        assert_eq!(input.text(), "hello g world");
+        assert_eq!(input.caret, 6);
+    }
+
+    #[test]
+    fn extends_the_selection_with_shift() {
+        let mut input = focused(Input::builder().with_text("hello world"));
+
+        assert!(input.on_key(&key_with(KeyCode::ArrowLeft, Modifiers::SHIFT)));
+        assert!(input.on_key(&key_with(KeyCode::ArrowLeft, Modifiers::SHIFT)));
+        assert_eq!(input.selection(), 9..11);
+        assert!(input.on_key(&key_with(
+            KeyCode::ArrowLeft,
+            Modifiers::SHIFT | word_modifier()
+        )));
+        assert_eq!(input.selection(), 6..11);
+        assert!(input.on_key(&key_with(KeyCode::Home, Modifiers::SHIFT)));
+        assert_eq!(input.selection(), 0..11);
+
+        // note: without shift the caret collapses to the edge of the selection
+        assert!(input.on_key(&key(KeyCode::ArrowRight)));
+        assert_eq!(input.selection(), 11..11);
+
+        assert!(input.on_key(&shortcut(KeyCode::KeyA)));
+        assert_eq!(input.selection(), 0..11);
+        assert!(input.on_key(&key(KeyCode::ArrowLeft)));
+        assert_eq!(input.selection(), 0..0);
+    }
+
+    #[test]
+    fn cut_copy_and_paste() {
+        let _clipboard = test_clipboard();
+        let mut input = focused(Input::builder().with_text("hello world"));
+
+        // note: nothing is selected, the clipboard is left alone
+        assert!(input.on_key(&shortcut(KeyCode::KeyX)));
+        assert_eq!(clipboard().get_text(), None);
+        assert_eq!(input.text(), "hello world");
+
+        input.anchor = 0;
+        input.caret = 6;
+        assert!(input.on_key(&shortcut(KeyCode::KeyC)));
+        assert_eq!(clipboard().get_text().as_deref(), Some("hello "));
+        assert_eq!(input.text(), "hello world");
+
+        assert!(input.on_key(&shortcut(KeyCode::KeyX)));
+        assert_eq!(clipboard().get_text().as_deref(), Some("hello "));
+        assert_eq!(input.text(), "world");
+        assert_eq!(input.selection(), 0..0);
+
+        assert!(input.on_key(&key(KeyCode::End)));
+        assert!(input.on_key(&shortcut(KeyCode::KeyV)));
+        assert_eq!(input.text(), "worldhello ");
+        assert_eq!(input.selection(), 11..11);
+
+        // note: pasted line breaks are dropped
+        clipboard().set_text("a\nb").unwrap();
+        assert!(input.on_key(&shortcut(KeyCode::KeyV)));
+        assert_eq!(input.text(), "worldhello ab");
+    }
+
+    #[test]
+    fn masks_passwords() {
+        let _clipboard = test_clipboard();
+        let mut input = focused(Input::builder().with_text("pa😀s").with_password(true));
+        assert_eq!(input.shown_text(), "\u{2022}\u{2022}\u{2022}\u{2022}");
+        assert_eq!(input.shown_index(6), 3 * MASK.len_utf8());
+        assert_eq!(input.text_index(3 * MASK.len_utf8()), 6);
+
+        assert!(input.on_key(&shortcut(KeyCode::KeyA)));
+        assert!(input.on_key(&shortcut(KeyCode::KeyC)));
+        assert!(input.on_key(&shortcut(KeyCode::KeyX)));
+        assert_eq!(clipboard().get_text(), None);
+        assert_eq!(input.text(), "pa😀s");
+
+        // note: the caret moves over the hidden graphemes of the text
+        assert!(input.on_key(&key(KeyCode::End)));
+        assert!(input.on_key(&key(KeyCode::ArrowLeft)));
+        assert!(input.on_key(&key(KeyCode::ArrowLeft)));
+        assert_eq!(input.caret, 2);
+
+        // note: a word jump goes over the whole password
+        assert!(input.on_key(&key_with(KeyCode::ArrowRight, word_modifier())));
+        assert_eq!(input.caret, 7);
+    }
+
+    #[test]
+    fn scrolls_to_the_caret() {
+        let mut input = focused(Input::builder().with_text("a long line of text"));
+        let width = input.layout.size.width;
+        input.content_rect = Rect::new(Point::new(0.0, 0.0), Size::new(width / 2.0, 20.0));
+
+        input.scroll_to_caret();
+        let end = input.scroll;
+        assert!(end > 0.0);
+        assert!(end <= width);
+
+        assert!(input.on_key(&key(KeyCode::Home)));
+        input.scroll_to_caret();
+        assert_eq!(input.scroll, 0.0);
+
+        // note: the whole text fits, nothing is scrolled
+        assert!(input.on_key(&key(KeyCode::End)));
+        input.content_rect.size.width = width * 2.0;
+        input.scroll_to_caret();
+        assert_eq!(input.scroll, 0.0);
+    }
+}
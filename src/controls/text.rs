@@ -14,46 +14,267 @@
   limitations under the License.
 */
 
+use crate::clipboard::clipboard;
 use crate::component::*;
 use crate::drawing::*;
 use crate::*;
 use input_handling::*;
 
 use crate::font::*;
+use crate::text::{
+    layout_styled_text, AttributedString, LineHeight, ParagraphStyle, SpanStyle, StyleRun,
+    TabStops, TextDecoration, TextDirection, TextLayout, TextOutline, TextShadow, TextSpan,
+    TextStyle, TextStyles, TextWrapping, WrapMode,
+};
 
-#[cfg(windows)]
-const DEFAULT_FONT_FAMILY: &str = "Segoe UI";
+use std::collections::HashMap;
+use std::ops::Range;
+
+use winit::event::{ElementState, MouseButton};
+use winit::keyboard::KeyCode;
 
 #[derive(Debug, Clone)]
 pub struct FontInfo {
-    pub family: Box<str>,
+    /// families are tried in order, ex. `["Inter", SansSerif]`
+    pub families: Vec<FamilyName>,
     pub weight: FontWeight,
     pub width: FontWidth,
+    pub type_: FontType,
+    /// exact axis values for variable fonts
+    pub variation: FontVariation,
     pub size: FontSize,
+    /// base direction of each paragraph, detected from the text by default
+    pub direction: TextDirection,
+    /// bitmaps or distance fields, picked from the size by default
+    pub rendering: GlyphRendering,
+    /// extra space after every cluster in logical pixels
+    pub letter_spacing: f32,
+    pub decoration: TextDecoration,
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
 }
 
 impl Default for FontInfo {
     fn default() -> Self {
         Self {
-            family: DEFAULT_FONT_FAMILY.into(),
-            size: FontSize::em(1.0),
+            families: vec![FamilyName::Generic(FontCatagory::SansSerif)],
+            size: FontSize::rem(1.0),
             weight: FontWeight::Normal,
             width: FontWidth::Normal,
+            type_: FontType::Normal,
+            variation: FontVariation::default(),
+            direction: TextDirection::Auto,
+            rendering: GlyphRendering::Auto,
+            letter_spacing: 0.0,
+            decoration: TextDecoration::default(),
+            outline: None,
+            shadow: None,
+        }
+    }
+}
+
+impl FontInfo {
+    pub fn style(&self) -> FontStyle {
+        FontStyle {
+            weight: self.weight,
+            width: self.width,
+            type_: self.type_,
+            variation: self.variation,
+        }
+    }
+
+    /// Returns a copy with the values set by `span` replaced.
+    pub fn with_span(&self, span: &SpanStyle) -> FontInfo {
+        FontInfo {
+            families: span
+                .families
+                .clone()
+                .unwrap_or_else(|| self.families.clone()),
+            weight: span.weight.unwrap_or(self.weight),
+            width: span.width.unwrap_or(self.width),
+            type_: span.type_.unwrap_or(self.type_),
+            variation: span.variation.unwrap_or(self.variation),
+            size: span
+                .size
+                .map_or(self.size, |size| size.relative_to(self.size)),
+            direction: self.direction,
+            rendering: self.rendering,
+            letter_spacing: span.letter_spacing.unwrap_or(self.letter_spacing),
+            decoration: span.decoration.unwrap_or(self.decoration),
+            outline: self.outline,
+            shadow: self.shadow,
         }
     }
 }
 
 pub struct TextBuilder {
     text: BindableString,
+    spans: Vec<TextSpan>,
     font_info: FontInfo,
+    foreground: Color,
+    wrapping: TextWrapping,
+    paragraph: ParagraphStyle,
+    alignment: Alignment,
+    selectable: bool,
+    selection_color: Color,
+}
+
+impl TextBuilder {
+    pub fn with_font_info(mut self, font_info: FontInfo) -> Self {
+        self.font_info = font_info;
+        self
+    }
+
+    pub fn with_font_families(mut self, families: Vec<FamilyName>) -> Self {
+        self.font_info.families = families;
+        self
+    }
+
+    pub fn with_font_size(mut self, size: FontSize) -> Self {
+        self.font_info.size = size;
+        self
+    }
+
+    pub fn with_font_weight(mut self, weight: FontWeight) -> Self {
+        self.font_info.weight = weight;
+        self
+    }
+
+    pub fn with_font_width(mut self, width: FontWidth) -> Self {
+        self.font_info.width = width;
+        self
+    }
+
+    pub fn with_font_type(mut self, type_: FontType) -> Self {
+        self.font_info.type_ = type_;
+        self
+    }
+
+    pub fn with_font_variation(mut self, variation: FontVariation) -> Self {
+        self.font_info.variation = variation;
+        self
+    }
+
+    pub fn with_foreground(mut self, color: Color) -> Self {
+        self.foreground = color;
+        self
+    }
+
+    pub fn with_direction(mut self, direction: TextDirection) -> Self {
+        self.font_info.direction = direction;
+        self
+    }
+
+    pub fn with_glyph_rendering(mut self, rendering: GlyphRendering) -> Self {
+        self.font_info.rendering = rendering;
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.font_info.letter_spacing = letter_spacing;
+        self
+    }
+
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.font_info.decoration.underline = underline;
+        self
+    }
+
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.font_info.decoration.strikethrough = strikethrough;
+        self
+    }
+
+    pub fn with_decoration(mut self, decoration: TextDecoration) -> Self {
+        self.font_info.decoration = decoration;
+        self
+    }
+
+    pub fn with_outline(mut self, width: f32, color: Color) -> Self {
+        self.font_info.outline = Some(TextOutline::new(width, color));
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: TextShadow) -> Self {
+        self.font_info.shadow = Some(shadow);
+        self
+    }
+
+    pub fn with_horizontal_alignment(mut self, alignment: HorizontalAlignment) -> Self {
+        self.alignment.horizontal = alignment;
+        self
+    }
+
+    pub fn with_vertical_alignment(mut self, alignment: VerticalAlignment) -> Self {
+        self.alignment.vertical = alignment;
+        self
+    }
+
+    pub fn with_wrap_mode(mut self, mode: WrapMode) -> Self {
+        self.wrapping.mode = mode;
+        self
+    }
+
+    pub fn with_max_lines(mut self, max_lines: usize) -> Self {
+        self.wrapping.max_lines = Some(max_lines);
+        self
+    }
+
+    pub fn with_ellipsis(mut self, ellipsis: bool) -> Self {
+        self.wrapping.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn with_paragraph_style(mut self, paragraph: ParagraphStyle) -> Self {
+        self.paragraph = paragraph;
+        self
+    }
+
+    pub fn with_line_height(mut self, line_height: LineHeight) -> Self {
+        self.paragraph.line_height = line_height;
+        self
+    }
+
+    pub fn with_paragraph_spacing(mut self, paragraph_spacing: f32) -> Self {
+        self.paragraph.paragraph_spacing = paragraph_spacing;
+        self
+    }
+
+    pub fn with_tab_stops(mut self, tab_stops: TabStops) -> Self {
+        self.paragraph.tab_stops = tab_stops;
+        self
+    }
+
+    /// Lets the user select the text with the mouse and copy it.
+    pub fn with_selectable(mut self, selectable: bool) -> Self {
+        self.selectable = selectable;
+        self
+    }
+
+    pub fn with_selection_color(mut self, color: Color) -> Self {
+        self.selection_color = color;
+        self
+    }
 }
 
 impl Builder<Text> for TextBuilder {
     fn build(self) -> Text {
         Text {
             text: self.text,
+            spans: self.spans,
             font_info: self.font_info,
+            foreground: self.foreground,
+            wrapping: self.wrapping,
+            paragraph: self.paragraph,
+            alignment: self.alignment,
+            layout: None,
+            layout_changed: false,
             formatted_text: None,
+            selection: TextSelection {
+                enabled: self.selectable,
+                color: self.selection_color,
+                ..Default::default()
+            },
         }
     }
 }
@@ -61,59 +282,320 @@ impl Builder<Text> for TextBuilder {
 #[derive(Debug)]
 pub struct Text {
     text: BindableString,
+    spans: Vec<TextSpan>,
     font_info: FontInfo,
+    foreground: Color,
+    wrapping: TextWrapping,
+    paragraph: ParagraphStyle,
+    alignment: Alignment,
+    layout: Option<TextLayout>,
+    /// set when `layout` was replaced since the last arrange
+    layout_changed: bool,
     formatted_text: Option<VisualText>,
+    selection: TextSelection,
+}
+
+/// Selection state of a selectable `Text`.
+#[derive(Debug, Default)]
+struct TextSelection {
+    enabled: bool,
+    color: Color,
+    /// byte index the selection started at
+    anchor: usize,
+    /// byte index the selection extends to, before `anchor` when selecting backwards
+    focus: usize,
+    dragging: bool,
+    /// set when the highlight no longer matches the selection
+    dirty: bool,
+    visuals: Vec<VisualRectangle>,
+    /// number of `visuals` covering the current selection
+    visible: usize,
+}
+
+impl TextSelection {
+    fn range(&self) -> Range<usize> {
+        self.anchor.min(self.focus)..self.anchor.max(self.focus)
+    }
+
+    fn select(&mut self, anchor: usize, focus: usize) {
+        if (anchor, focus) != (self.anchor, self.focus) {
+            self.anchor = anchor;
+            self.focus = focus;
+            self.dirty = true;
+        }
+    }
 }
 
 impl Text {
     pub fn builder(text: &str) -> TextBuilder {
         TextBuilder {
             text: BindableString::Static(text.into()),
+            spans: Vec::new(),
             font_info: Default::default(),
+            foreground: Color::BLACK,
+            wrapping: Default::default(),
+            paragraph: Default::default(),
+            alignment: Default::default(),
+            selectable: false,
+            selection_color: Color::rgba(0.2, 0.45, 0.9, 0.35),
         }
     }
-}
 
-impl Layout for Text {
-    fn measure(&mut self, available_size: Size) -> Size {
-        let font = find_best_font(&BestFontQuery {
-            query: FontQuery::FamilyName(self.font_info.family.as_ref()),
-            style: Default::default(),
-        })
-        .unwrap();
+    /// Creates a text with styled spans, the builder's font and color apply to unstyled text.
+    pub fn rich(text: AttributedString) -> TextBuilder {
+        let mut builder = Self::builder(text.text());
+        builder.spans = text.spans().to_vec();
+        builder
+    }
+}
 
-        let text = match &self.text {
+impl Text {
+    fn text(&self) -> &str {
+        match &self.text {
             BindableString::Static(text) => text.as_str(),
             // BindableString::Binding(binding) => todo!(),
+        }
+    }
+
+    /// Replaces the text, spans keep their byte ranges and are cut to the new text.
+    /// The text is laid out again on the next layout pass, reusing the existing visual.
+    pub fn set_text(&mut self, text: &str) {
+        if text == self.text() {
+            return;
+        }
+
+        self.text = BindableString::Static(text.into());
+        for span in &mut self.spans {
+            span.range = char_floor(text, span.range.start)..char_floor(text, span.range.end);
+        }
+        self.spans.retain(|span| !span.range.is_empty());
+        self.selection.select(0, 0);
+        self.layout = None;
+    }
+
+    /// Changes the color of the text outside of spans that set their own color.
+    /// The glyphs are recolored in place, the text is not laid out again.
+    pub fn set_foreground(&mut self, color: Color) {
+        self.foreground = color;
+
+        let colors = TextFormatter::style_colors(self.text(), color, &self.spans);
+        if let Some(layout) = &mut self.layout {
+            for (style, &color) in layout.styles.iter_mut().zip(&colors) {
+                style.color = color;
+            }
+        }
+        if let Some(visual) = &mut self.formatted_text {
+            visual.update_colors(&colors);
+        }
+    }
+
+    /// Selects a byte range of the text, ex. to copy it with the shortcut.
+    pub fn select(&mut self, range: Range<usize>) {
+        let text = self.text();
+        let (start, end) = (char_floor(text, range.start), char_floor(text, range.end));
+        self.selection.select(start, end);
+    }
+
+    /// Selected byte range of the text, empty when nothing is selected.
+    pub fn selection(&self) -> Range<usize> {
+        let range = self.selection.range();
+        let len = self.text().len();
+        range.start.min(len)..range.end.min(len)
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text()[self.selection()]
+    }
+
+    /// Returns the caret position under `pos` and the paragraph it hit, if `pos` is over the text.
+    fn hit_test(&self, pos: Point) -> Option<(usize, Range<usize>, Range<usize>)> {
+        let visual = self.formatted_text.as_ref()?;
+        let rect = visual.rect();
+        if !rect.contains(pos) {
+            return None;
+        }
+
+        let paragraph = visual.paragraph(self.text());
+        let hit = paragraph.hit_test(Point::new(pos.x - rect.pos.x, pos.y - rect.pos.y));
+        let line = paragraph.lines().get(hit.line)?;
+        Some((
+            hit.index,
+            paragraph.word_at(hit.index),
+            line.paragraph.clone(),
+        ))
+    }
+
+    /// Moves the highlight rectangles over the selection.
+    fn update_selection_visuals(&mut self) {
+        let visual = match &self.formatted_text {
+            Some(visual) => visual,
+            None => return,
         };
 
-        TextFormatter::calculate_bounding_box(text, font, self.font_info.size)
+        let origin = visual.rect().pos;
+        let rects = visual
+            .paragraph(self.text())
+            .selection_rects(self.selection());
+
+        for (index, rect) in rects.iter().enumerate() {
+            let rect = Rect::new(
+                Point::new(origin.x + rect.pos.x, origin.y + rect.pos.y),
+                rect.size,
+            );
+            match self.selection.visuals.get(index) {
+                Some(visual) => visual.update(rect, self.selection.color),
+                None => self
+                    .selection
+                    .visuals
+                    .push(VisualRectangle::new(rect, self.selection.color)),
+            }
+        }
+
+        self.selection.visible = rects.len();
+        self.selection.dirty = false;
     }
 
-    fn arrange(&mut self, final_rect: Rect) -> Rect {
-        let text = match &self.text {
-            BindableString::Static(text) => text.as_str(),
-            // BindableString::Binding(binding) => todo!(),
+    fn layout(&self, max_width: f32, scale_factor: f32) -> TextLayout {
+        let styles = match TextFormatter::resolve_styles(
+            self.text(),
+            &self.font_info,
+            self.foreground,
+            &self.spans,
+        ) {
+            Ok(styles) => styles,
+            // note: without any font there is nothing to draw, the empty layout is retried next frame
+            Err(_) => return TextLayout::default(),
+        };
+        TextFormatter::layout(
+            self.text(),
+            &styles,
+            &self.font_info,
+            &self.wrapping,
+            &self.paragraph,
+            max_width,
+            scale_factor,
+        )
+    }
+}
+
+impl Layout for Text {
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size {
+        if let Some(layout) = &self.layout {
+            if layout.is_valid_for(available_size.width, scale_factor) {
+                return layout.size;
+            }
+        }
+
+        let layout = self.layout(available_size.width, scale_factor);
+        let size = layout.size;
+        self.layout = Some(layout);
+        self.layout_changed = true;
+        size
+    }
+
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
+        let layout = match self.layout.take() {
+            Some(layout) if layout.is_valid_for(final_rect.size.width, scale_factor) => layout,
+            _ => {
+                self.layout_changed = true;
+                self.layout(final_rect.size.width, scale_factor)
+            }
         };
 
-        let font = find_best_font(&BestFontQuery {
-            query: FontQuery::FamilyName(self.font_info.family.as_ref()),
-            style: Default::default(),
-        })
-        .unwrap();
+        // note: only re-align and re-upload glyphs when the lines or the box size changed
+        let (changed, moved) = match &self.formatted_text {
+            Some(visual) => (
+                self.layout_changed || visual.rect().size != final_rect.size,
+                visual.rect().pos != final_rect.pos,
+            ),
+            None => (true, true),
+        };
+
+        if changed {
+            // note: alignment stretches justified lines, keep the measured layout untouched
+            let mut aligned = layout.clone();
+            aligned.align(self.text(), self.alignment, final_rect.size);
 
-        self.formatted_text = Some(VisualText::new(text, final_rect, font, self.font_info.size));
+            match &mut self.formatted_text {
+                Some(visual) => visual.update_layout(aligned, final_rect),
+                None => self.formatted_text = Some(VisualText::new(aligned, final_rect)),
+            }
+        } else if let Some(visual) = &mut self.formatted_text {
+            if moved {
+                visual.update_pos(final_rect.pos);
+            }
+            visual.refresh();
+        }
+
+        if self.selection.enabled && (changed || moved || self.selection.dirty) {
+            self.update_selection_visuals();
+        }
+
+        self.layout = Some(layout);
+        self.layout_changed = false;
         final_rect
     }
 }
 
 impl Draw for Text {
     fn draw<'a>(&'a self, context: &mut DrawingContext<'a>) {
+        for visual in &self.selection.visuals[..self.selection.visible] {
+            context.draw(visual);
+        }
         context.draw(self.formatted_text.as_ref().unwrap());
     }
 }
 
-impl InputHandler for Text {}
+impl InputHandler for Text {
+    fn on_key(&mut self, event: &KeyEvent) -> bool {
+        if !self.selection.enabled || !event.is_shortcut(KeyCode::KeyC) {
+            return false;
+        }
+
+        let text = self.selected_text();
+        !text.is_empty() && clipboard().set_text(text).is_ok()
+    }
+
+    fn on_mouse_button(&mut self, event: &MouseButtonEvent) -> bool {
+        if !self.selection.enabled || event.button() != MouseButton::Left {
+            return false;
+        }
+
+        if event.state() == ElementState::Released {
+            self.selection.dragging = false;
+            return false;
+        }
+
+        match self.hit_test(event.pos()) {
+            Some((index, word, line)) => {
+                match event.click_count() {
+                    1 => self.selection.select(index, index),
+                    2 => self.selection.select(word.start, word.end),
+                    _ => self.selection.select(line.start, line.end),
+                }
+                self.selection.dragging = event.click_count() == 1;
+            }
+            None => self.selection.select(0, 0),
+        }
+
+        // note: let the press reach other texts so they clear their selection
+        false
+    }
+
+    fn on_cursor_moved(&mut self, event: &CursorMovedEvent) -> bool {
+        if !self.selection.dragging {
+            return false;
+        }
+
+        match self.hit_test(event.pos()) {
+            Some((index, ..)) => {
+                self.selection.select(self.selection.anchor, index);
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 impl PreviewInputHandler for Text {}
 
@@ -121,35 +603,225 @@ impl DispatchInput for Text {}
 
 impl Component for Text {}
 
-struct TextFormatter {}
+pub(super) struct TextFormatter {}
 
 impl TextFormatter {
-    fn calculate_bounding_box(text: &str, font: &Font, size: FontSize) -> Size {
-        let font_scale = calculate_font_scale(size);
+    /// Fails only when no font at all is installed.
+    pub(super) fn text_style(font_info: &FontInfo, color: Color) -> Result<TextStyle, FontError> {
+        let query = BestFontQuery {
+            query: FontQuery::Families(&font_info.families),
+            style: font_info.style(),
+        };
+        // note: text in a family that is not installed is still drawn, only an empty font cache fails
+        let fonts =
+            FontFallback::new(&query).or_else(|_| FontFallback::last_resort(query.style))?;
+
+        let mut style = TextStyle::new(fonts, font_info.size)
+            .with_color(color)
+            .with_rendering(font_info.rendering)
+            .with_letter_spacing(font_info.letter_spacing)
+            .with_decoration(font_info.decoration);
+        style.outline = font_info.outline;
+        style.shadow = font_info.shadow;
+        Ok(style)
+    }
 
-        let line_height = font.line_height() * font_scale;
+    /// Splits the text at every span boundary and resolves the style of each piece.
+    /// Pieces covered by the same spans share a style.
+    fn resolve_styles(
+        text: &str,
+        font_info: &FontInfo,
+        foreground: Color,
+        spans: &[TextSpan],
+    ) -> Result<TextStyles, FontError> {
+        let (runs, covering) = Self::span_runs(text, spans);
+        let mut styles = TextStyles::uniform(Self::text_style(font_info, foreground)?);
 
-        let mut height: f32 = 0.0;
-        let mut width: f32 = 0.0;
-        let mut max_width: f32 = 0.0;
+        for covering in &covering[1..] {
+            let info = covering.iter().fold(font_info.clone(), |info, &index| {
+                info.with_span(&spans[index].style)
+            });
+            let color = Self::span_color(foreground, spans, covering);
+            styles.styles.push(Self::text_style(&info, color)?);
+        }
 
-        for line in text.lines() {
-            height += line_height;
+        styles.runs = runs;
+        Ok(styles)
+    }
 
-            for c in line.chars() {
-                match c {
-                    codepoint => {
-                        let glyph = font.get_glyph(codepoint).unwrap();
-                        width += glyph.advance() * font_scale;
-                    }
-                }
+    /// Colors of the styles `resolve_styles` returns, without resolving their fonts.
+    fn style_colors(text: &str, foreground: Color, spans: &[TextSpan]) -> Vec<Color> {
+        let (_, covering) = Self::span_runs(text, spans);
+        covering
+            .iter()
+            .map(|covering| Self::span_color(foreground, spans, covering))
+            .collect()
+    }
+
+    fn span_color(foreground: Color, spans: &[TextSpan], covering: &[usize]) -> Color {
+        covering.iter().fold(foreground, |color, &index| {
+            spans[index].style.color.unwrap_or(color)
+        })
+    }
+
+    /// Returns the runs of the text covered by spans and the spans covering each style,
+    /// the first style is the base style and covered by none.
+    fn span_runs(text: &str, spans: &[TextSpan]) -> (Vec<StyleRun>, Vec<Vec<usize>>) {
+        let len = text.len();
+        let mut bounds: Vec<usize> = spans
+            .iter()
+            .flat_map(|span| vec![span.range.start.min(len), span.range.end.min(len)])
+            .chain(vec![0, len])
+            .collect();
+        bounds.sort();
+        bounds.dedup();
+
+        let mut runs = Vec::new();
+        let mut styles: Vec<Vec<usize>> = vec![Vec::new()];
+        let mut resolved: HashMap<Vec<usize>, usize> = HashMap::new();
+
+        for bound in bounds.windows(2) {
+            let range = bound[0]..bound[1];
+
+            let covering: Vec<usize> = spans
+                .iter()
+                .enumerate()
+                .filter(|(_, span)| span.range.start <= range.start && span.range.end >= range.end)
+                .map(|(index, _)| index)
+                .collect();
+            if covering.is_empty() {
+                continue;
             }
 
-            max_width = max_width.max(width);
-            width = 0.0;
+            let style = *resolved.entry(covering.clone()).or_insert_with(|| {
+                styles.push(covering);
+                styles.len() - 1
+            });
+
+            runs.push(StyleRun { range, style });
         }
 
-        max_width = max_width.max(width);
-        Size::new(max_width, height)
+        (runs, styles)
+    }
+
+    fn layout(
+        text: &str,
+        styles: &TextStyles,
+        font_info: &FontInfo,
+        wrapping: &TextWrapping,
+        paragraph: &ParagraphStyle,
+        max_width: f32,
+        scale_factor: f32,
+    ) -> TextLayout {
+        layout_styled_text(
+            text,
+            styles,
+            font_info.direction,
+            wrapping,
+            paragraph,
+            max_width,
+            scale_factor,
+        )
+    }
+}
+
+/// Moves `index` back to the nearest char boundary of `text`.
+fn char_floor(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clipboard::test_clipboard;
+    use winit::keyboard::PhysicalKey;
+
+    fn red() -> Color {
+        Color::rgb(1.0, 0.0, 0.0)
+    }
+
+    fn rich_text() -> Text {
+        let mut text = AttributedString::new("plain ");
+        text.push_styled("red", SpanStyle::new().with_foreground(red()));
+        text.push_str(" plain");
+        Text::rich(text).build()
+    }
+
+    fn rgb(color: Color) -> (f32, f32, f32) {
+        (color.r, color.g, color.b)
+    }
+
+    #[test]
+    fn set_foreground_keeps_span_colors_and_lines() {
+        let mut text = rich_text();
+        text.measure(Size::new(1000.0, 1000.0), 1.0);
+        let lines = text.layout.as_ref().unwrap().lines.len();
+
+        text.set_foreground(Color::rgb(0.0, 0.0, 1.0));
+
+        let layout = text.layout.as_ref().unwrap();
+        assert_eq!(layout.lines.len(), lines);
+        assert_eq!(rgb(layout.styles[0].color), (0.0, 0.0, 1.0));
+        assert_eq!(rgb(layout.styles[1].color), rgb(red()));
+    }
+
+    #[test]
+    fn set_text_keeps_spans_inside_the_text() {
+        let mut text = rich_text();
+        text.measure(Size::new(1000.0, 1000.0), 1.0);
+
+        text.set_text("plain éé");
+        assert!(text.layout.is_none());
+        assert_eq!(text.spans.len(), 1);
+        // note: the span ends inside the second "é", it is cut back to the char boundary
+        assert_eq!(text.spans[0].range, 6..8);
+
+        text.measure(Size::new(1000.0, 1000.0), 1.0);
+        let layout = text.layout.as_ref().unwrap();
+        assert_eq!(rgb(layout.styles[1].color), rgb(red()));
+
+        text.set_text("gone");
+        assert!(text.spans.is_empty());
+    }
+
+    fn shortcut(code: KeyCode) -> KeyEvent {
+        let modifiers = if cfg!(target_os = "macos") {
+            Modifiers::SUPER
+        } else {
+            Modifiers::CONTROL
+        };
+        KeyEvent::new(
+            PhysicalKey::Code(code),
+            ElementState::Pressed,
+            modifiers,
+            None,
+        )
+    }
+
+    #[test]
+    fn copies_the_selection() {
+        let _clipboard = test_clipboard();
+
+        let mut text = Text::builder("hello world").build();
+        text.select(6..11);
+        // note: only selectable texts handle the shortcut
+        assert!(!text.on_key(&shortcut(KeyCode::KeyC)));
+        assert_eq!(clipboard().get_text(), None);
+
+        let mut text = Text::builder("hello wörld").with_selectable(true).build();
+        // note: "ö" is 7..9, a range ending inside it is cut back
+        text.select(6..8);
+        assert_eq!(text.selection(), 6..7);
+        text.select(6..9);
+        assert!(text.on_key(&shortcut(KeyCode::KeyC)));
+        assert_eq!(clipboard().get_text().as_deref(), Some("wö"));
+
+        text.select(0..0);
+        assert!(!text.on_key(&shortcut(KeyCode::KeyC)));
+        assert_eq!(clipboard().get_text().as_deref(), Some("wö"));
     }
 }
@@ -1,7 +1,7 @@
 use std::ops::{Add, Div, Mul, Neg, Sub};
 use winit::dpi::PhysicalPosition;
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Size {
     pub width: f32,
     pub height: f32,
@@ -90,7 +90,7 @@ impl From<winit::dpi::PhysicalSize<u32>> for Size {
     }
 }
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -184,7 +184,7 @@ impl num_traits::Zero for Point {
 
 pub type Delta = Point;
 
-#[derive(Debug, Default, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
 pub struct Rect {
     pub pos: Point,
     pub size: Size,
@@ -221,6 +221,18 @@ impl Rect {
             && self.pos.y < other.pos.y + other.size.height
             && self.pos.y + self.size.height > other.pos.y
     }
+
+    /// Returns the overlap of both rects, an empty rect at this rect's position if they do not intersect.
+    pub fn intersection(&self, other: Rect) -> Rect {
+        let left = self.pos.x.max(other.pos.x);
+        let top = self.pos.y.max(other.pos.y);
+        let right = (self.pos.x + self.size.width).min(other.pos.x + other.size.width);
+        let bottom = (self.pos.y + self.size.height).min(other.pos.y + other.size.height);
+        if right <= left || bottom <= top {
+            return Rect::new(self.pos, Size::new(0.0, 0.0));
+        }
+        Rect::new(Point::new(left, top), Size::new(right - left, bottom - top))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -314,6 +326,8 @@ pub enum HorizontalAlignment {
     Left,
     Center,
     Right,
+    /// stretch lines of text to fill the width, layouts treat it as `Left`
+    Justify,
 }
 
 impl Default for HorizontalAlignment {
@@ -336,7 +350,7 @@ impl Default for VerticalAlignment {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Default, Copy, Clone)]
 pub struct Alignment {
     pub horizontal: HorizontalAlignment,
     pub vertical: VerticalAlignment,
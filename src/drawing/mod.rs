@@ -63,6 +63,8 @@ impl<'a> DrawingContext<'a> {
     }
 
     pub fn push_scissor(&mut self, rect: Rect) {
+        // note: a nested scissor can not reach outside of its parent or the render target
+        let rect = self.scissor_stack.last().unwrap().intersection(rect);
         self.render_pass.set_scissor_rect(
             rect.pos.x as u32,
             rect.pos.y as u32,
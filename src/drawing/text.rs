@@ -15,142 +15,549 @@
 */
 
 use super::*;
-use crate::font::Font;
+use crate::font::{
+    atlas_page_view, distance_field_settings, locate_glyphs, pixel_size, AtlasLocation,
+    DistanceFieldMode, Glyph, GlyphRendering,
+};
 use crate::gfx::*;
+use crate::text::{Paragraph, TextLayout, TextStyle};
 use drawing::{UniformBuffer, Visual};
-use font::FontSize;
-use glm::{Mat4, Vec4};
+use glm::Mat4;
 use lazy_static::lazy_static;
 use {wgpu, Color};
 use {Point, Size};
 
+use std::mem::size_of;
+use std::ops::Range;
+
 #[repr(packed)]
 #[derive(Debug)]
 struct GlyphUniform {
     transform: Mat4,
+}
+
+/// Per glyph vertex data, one instance is drawn for each glyph.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GlyphInstance {
+    /// x, y, width, height relative to the origin of the text
+    rect: [f32; 4],
+    /// top left and bottom right texture coordinates in the atlas page
+    uv: [f32; 4],
     color: Color,
+    outline_color: Color,
+    /// outline width and edge softness in distance field units, unused by bitmaps
+    effect: [f32; 2],
+}
+
+/// Order glyphs and lines are drawn in, shadows go below everything else.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum GlyphLayer {
+    Shadow,
+    Decoration,
+    Text,
 }
 
+/// A glyph placed relative to the origin of its text.
 #[derive(Debug)]
-struct VisualGlyph {
-    buffer: UniformBuffer<GlyphUniform>,
-    bind_group: wgpu::BindGroup,
+struct GlyphPlacement {
+    glyph: &'static Glyph,
+    rect: Rect,
+    color: Color,
+    outline_color: Color,
+    effect: [f32; 2],
+    layer: GlyphLayer,
 }
 
-impl VisualGlyph {
-    fn new(rect: Rect, texture_view: &wgpu::TextureView) -> Self {
-        let device = get_device();
-        let buffer = UniformBuffer::new_initialized(GlyphUniform {
-            transform: model_projection(rect, 0.0),
-            color: Color::BLACK,
-        });
+/// An underline or strikethrough placed relative to the origin of its text.
+#[derive(Debug)]
+struct LinePlacement {
+    rect: Rect,
+    color: Color,
+    layer: GlyphLayer,
+}
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &GLYPH_INFO_BIND_LAYOUT,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                        buffer: buffer.as_ref(),
-                        offset: 0,
-                        size: None,
-                    }),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::TextureView(texture_view),
-                },
-            ],
-        });
+/// Pipeline a glyph is drawn with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum GlyphPipeline {
+    DistanceField,
+    Bitmap,
+    /// color bitmaps drawn as is, the text color only sets the opacity
+    Color,
+}
 
-        Self { buffer, bind_group }
+impl GlyphPipeline {
+    fn of(glyph: &Glyph) -> Self {
+        match glyph.rendering() {
+            _ if glyph.is_color() => GlyphPipeline::Color,
+            GlyphRendering::Bitmap => GlyphPipeline::Bitmap,
+            _ => GlyphPipeline::DistanceField,
+        }
     }
 }
 
-impl Visual for VisualGlyph {
-    fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        // note: pipeline bound by FormattedText
-        // render_pass.set_pipeline(get_glyph_pipeline());
-        render_pass.set_bind_group(1, &self.bind_group, &[]);
-        render_pass.draw(0..6, 0..1);
-    }
+/// Glyphs that are drawn from the same atlas page with the same pipeline.
+#[derive(Debug)]
+struct GlyphBatch {
+    /// none for lines, drawn from `SOLID_TEXTURE_VIEW`
+    page: Option<usize>,
+    pipeline: GlyphPipeline,
+    instances: Range<u32>,
 }
 
 #[derive(Debug)]
 pub struct VisualText {
-    text: Box<str>,
-    font: &'static Font,
-    size: ::font::FontSize,
     rect: Rect,
-    glyphs: Vec<VisualGlyph>,
+    layout: TextLayout,
+    placements: Vec<GlyphPlacement>,
+    decorations: Vec<LinePlacement>,
+    locations: Vec<Option<AtlasLocation>>,
+    uniform: UniformBuffer<GlyphUniform>,
+    instances: Option<wgpu::Buffer>,
+    batches: Vec<GlyphBatch>,
+    /// one bind group per atlas page, created when the page is first used
+    bind_groups: Vec<Option<wgpu::BindGroup>>,
+    solid_bind_group: Option<wgpu::BindGroup>,
 }
 
 impl VisualText {
-    // todo: implement text alignment
-    pub fn new(text: &str, rect: Rect, font: &'static Font, size: FontSize) -> Self {
-        let mut glyphs = Vec::new();
+    /// Creates the glyph visuals of an aligned layout, see `TextLayout::align`.
+    pub fn new(layout: TextLayout, rect: Rect) -> Self {
+        let (placements, decorations) = Self::place(&layout);
+        let mut visual = Self {
+            rect,
+            placements,
+            decorations,
+            layout,
+            locations: Vec::new(),
+            uniform: UniformBuffer::new_initialized(GlyphUniform {
+                transform: Self::transform(rect.pos),
+            }),
+            instances: None,
+            batches: Vec::new(),
+            bind_groups: Vec::new(),
+            solid_bind_group: None,
+        };
+        visual.upload();
+        visual
+    }
 
-        let font_scale = ::font::calculate_font_scale(size);
-        let line_height = font.line_height() * font_scale;
-        let accent = font.ascent() * font_scale;
-        //let decent = font.descent() * font_scale;
+    fn transform(origin: Point) -> Mat4 {
+        // note: keep the origin on a whole pixel so bitmap glyphs stay sharp
+        let origin = Point::new(origin.x.round(), origin.y.round());
+        model_projection(Rect::new(origin, Size::new(1.0, 1.0)), 0.0)
+    }
 
-        let mut pos = rect.pos;
-        pos.y = pos.y - line_height + accent;
+    fn place(layout: &TextLayout) -> (Vec<GlyphPlacement>, Vec<LinePlacement>) {
+        let mut placements = Vec::new();
+        let mut decorations = Vec::new();
+        // note: glyphs are placed in the physical pixels the layout was shaped in
+        let scale_factor = layout.scale_factor;
 
-        for line in text.lines() {
-            for c in line.chars() {
-                if c.is_control() {
-                    continue;
+        for line in &layout.lines {
+            let mut pos = Point::new(line.x, line.y + line.ascent);
+            // style and left edge of the decorated run the pen is in
+            let mut decorated: Option<(usize, f32)> = None;
+
+            for shaped in &line.shaped.glyphs {
+                let style = &layout.styles[shaped.style];
+
+                if decorated.is_some_and(|(run_style, _)| run_style != shaped.style) {
+                    let (run_style, left) = decorated.take().unwrap();
+                    Self::decorate(
+                        &layout.styles[run_style],
+                        pos.y,
+                        left..pos.x,
+                        scale_factor,
+                        &mut decorations,
+                    );
+                }
+                if decorated.is_none() && !style.decoration.is_empty() {
+                    decorated = Some((shaped.style, pos.x));
                 }
 
-                let glyph = font.get_glyph(c).unwrap();
-                if let Some(texture_view) = glyph.texture_view() {
-                    let size = glyph.size() * font_scale;
-                    let mut off = glyph.bearing() * font_scale;
+                let font_scale = style.font_scale(scale_factor);
+                let pixel_size = pixel_size(style.size, scale_factor);
+
+                // note: color fonts are always rasterized, distance fields can't hold their colors
+                let glyph = match style.glyph_rendering(scale_factor) {
+                    _ if shaped.font.has_color() => shaped
+                        .font
+                        .get_color_glyph(shaped.glyph_index, pixel_size)
+                        .ok(),
+                    GlyphRendering::Bitmap => shaped
+                        .font
+                        .get_bitmap_glyph(shaped.glyph_index, pixel_size)
+                        .ok(),
+                    _ => shaped.font.get_glyph_by_index(shaped.glyph_index).ok(),
+                };
+                // note: glyphs that fail to render are skipped, the text around them is still drawn
+                let glyph = match glyph {
+                    Some(glyph) => glyph,
+                    None => {
+                        pos.x += shaped.advance;
+                        continue;
+                    }
+                };
+
+                if glyph.bitmap_dimensions() != (0, 0) {
+                    let mut off = glyph.texture_offset();
                     off.y = -off.y;
 
-                    let tex_scale = glyph.texture_size() / glyph.size();
-                    let tex_off = -(size * tex_scale - size) / 2.0;
+                    let rect = match glyph.rendering() {
+                        // note: emoji strikes come in a few fixed sizes
+                        _ if glyph.is_color() => {
+                            let scale = pixel_size / glyph.pixel_size();
+                            Rect::new(
+                                pos + shaped.offset + off * scale,
+                                glyph.texture_size() * scale,
+                            )
+                        }
+                        GlyphRendering::Bitmap => {
+                            // note: hinted bitmaps are drawn 1:1 on whole pixels
+                            let p = pos + shaped.offset;
+                            Rect::new(
+                                Point::new(p.x.round(), p.y.round()) + off,
+                                glyph.texture_size(),
+                            )
+                        }
+                        _ => Rect::new(
+                            pos + shaped.offset + off * font_scale,
+                            glyph.texture_size() * font_scale,
+                        ),
+                    };
 
-                    let p = pos + off + tex_off;
-                    let s = size * tex_scale;
+                    // note: field values run from 0 to 1 over twice the spread, in field pixels
+                    let units = match GlyphPipeline::of(glyph) {
+                        GlyphPipeline::DistanceField => {
+                            1.0 / (2.0 * distance_field_settings().spread * font_scale)
+                        }
+                        _ => 0.0,
+                    };
+                    let outline = style.outline.map_or(0.0, |outline| {
+                        (outline.width * scale_factor * units).min(0.5)
+                    });
 
-                    let mut rect = Rect::new(p, s);
-                    rect.pos.y = rect.pos.y + line_height;
+                    if let Some(shadow) = style.shadow.filter(|_| !glyph.is_color()) {
+                        let softness = (shadow.blur * scale_factor * units).min(0.5 - outline);
+                        placements.push(GlyphPlacement {
+                            glyph,
+                            rect: Rect::new(rect.pos + shadow.offset * scale_factor, rect.size),
+                            color: shadow.color,
+                            outline_color: shadow.color,
+                            effect: [outline, softness],
+                            layer: GlyphLayer::Shadow,
+                        });
+                    }
 
-                    glyphs.push(VisualGlyph::new(rect, texture_view));
+                    placements.push(GlyphPlacement {
+                        glyph,
+                        rect,
+                        color: style.color,
+                        outline_color: style.outline.map_or(style.color, |outline| outline.color),
+                        effect: [outline, 0.0],
+                        layer: GlyphLayer::Text,
+                    });
                 }
-                pos.x += glyph.advance() * font_scale;
+                pos.x += shaped.advance;
+            }
+
+            if let Some((run_style, left)) = decorated {
+                Self::decorate(
+                    &layout.styles[run_style],
+                    pos.y,
+                    left..pos.x,
+                    scale_factor,
+                    &mut decorations,
+                );
             }
-            pos.x = rect.pos.x;
-            pos.y += line_height;
         }
 
-        Self {
-            text: text.into(),
-            font,
-            size,
-            rect,
-            glyphs,
+        (placements, decorations)
+    }
+
+    /// Places the underline and strikethrough of a run of text between `span` on the `baseline`.
+    fn decorate(
+        style: &TextStyle,
+        baseline: f32,
+        span: Range<f32>,
+        scale_factor: f32,
+        decorations: &mut Vec<LinePlacement>,
+    ) {
+        let metrics = match style.fonts.primary().metrics(style.size, scale_factor) {
+            Ok(metrics) => metrics,
+            Err(_) => return,
+        };
+        let color = style.decoration.color.unwrap_or(style.color);
+
+        let mut strokes = Vec::new();
+        if style.decoration.underline {
+            strokes.push((metrics.underline_position, metrics.underline_thickness));
+        }
+        if style.decoration.strikethrough {
+            strokes.push((metrics.strikeout_position, metrics.strikeout_thickness));
+        }
+
+        for (position, thickness) in strokes {
+            // note: keep thin lines on whole pixels so they stay sharp
+            let rect = Rect::new(
+                Point::new(span.start, (baseline - position).round()),
+                Size::new(span.end - span.start, thickness.round().max(1.0)),
+            );
+            let outlined = match style.outline {
+                Some(outline) => {
+                    let width = outline.width * scale_factor;
+                    Rect::new(
+                        rect.pos - Point::new(width, width),
+                        Size::new(
+                            rect.size.width + width * 2.0,
+                            rect.size.height + width * 2.0,
+                        ),
+                    )
+                }
+                None => rect,
+            };
+
+            // note: lines are drawn solid, their shadows are never blurred
+            if let Some(shadow) = style.shadow {
+                decorations.push(LinePlacement {
+                    rect: Rect::new(outlined.pos + shadow.offset * scale_factor, outlined.size),
+                    color: shadow.color,
+                    layer: GlyphLayer::Shadow,
+                });
+            }
+            if let Some(outline) = style.outline {
+                decorations.push(LinePlacement {
+                    rect: outlined,
+                    color: outline.color,
+                    layer: GlyphLayer::Decoration,
+                });
+            }
+            decorations.push(LinePlacement {
+                rect,
+                color,
+                layer: GlyphLayer::Decoration,
+            });
         }
     }
 
+    /// Makes sure every glyph is resident in the atlas and rebuilds the instance buffer.
+    fn upload(&mut self) {
+        let glyphs: Vec<&Glyph> = self.placements.iter().map(|p| p.glyph).collect();
+        self.locations = locate_glyphs(&glyphs);
+        self.build_instances();
+    }
+
+    fn build_instances(&mut self) {
+        let placements = &self.placements;
+        let mut entries: Vec<(GlyphLayer, Option<usize>, GlyphPipeline, GlyphInstance)> = self
+            .locations
+            .iter()
+            .enumerate()
+            .filter_map(|(index, location)| location.map(|location| (index, location)))
+            .map(|(index, location)| {
+                let placement = &placements[index];
+                let uv = location.uv;
+                (
+                    placement.layer,
+                    Some(location.page),
+                    GlyphPipeline::of(placement.glyph),
+                    GlyphInstance {
+                        rect: rect_to_array(placement.rect),
+                        uv: [
+                            uv.pos.x,
+                            uv.pos.y,
+                            uv.pos.x + uv.size.width,
+                            uv.pos.y + uv.size.height,
+                        ],
+                        color: placement.color,
+                        outline_color: placement.outline_color,
+                        effect: placement.effect,
+                    },
+                )
+            })
+            .collect();
+
+        // note: lines sample a solid texture with the bitmap pipeline, ignoring the effects
+        entries.extend(self.decorations.iter().map(|line| {
+            (
+                line.layer,
+                None,
+                GlyphPipeline::Bitmap,
+                GlyphInstance {
+                    rect: rect_to_array(line.rect),
+                    uv: [0.0, 0.0, 1.0, 1.0],
+                    color: line.color,
+                    outline_color: line.color,
+                    effect: [0.0, 0.0],
+                },
+            )
+        }));
+
+        // note: the sort is stable, so line outlines stay below their lines
+        entries.sort_by_key(|&(layer, page, pipeline, _)| (layer, page, pipeline));
+
+        let mut instances = Vec::with_capacity(entries.len());
+        self.batches.clear();
+
+        for (_, page, pipeline, instance) in entries {
+            match self.batches.last_mut() {
+                Some(batch) if batch.page == page && batch.pipeline == pipeline => {
+                    batch.instances.end += 1
+                }
+                _ => self.batches.push(GlyphBatch {
+                    page,
+                    pipeline,
+                    instances: instances.len() as u32..instances.len() as u32 + 1,
+                }),
+            }
+            instances.push(instance);
+        }
+
+        for batch in &self.batches {
+            let page = match batch.page {
+                Some(page) => page,
+                None => {
+                    if self.solid_bind_group.is_none() {
+                        self.solid_bind_group =
+                            Some(Self::create_bind_group(&self.uniform, &SOLID_TEXTURE_VIEW));
+                    }
+                    continue;
+                }
+            };
+            if self.bind_groups.len() <= page {
+                self.bind_groups.resize_with(page + 1, || None);
+            }
+            if self.bind_groups[page].is_none() {
+                self.bind_groups[page] = Some(Self::create_bind_group(
+                    &self.uniform,
+                    atlas_page_view(page),
+                ));
+            }
+        }
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                instances.as_ptr() as *const u8,
+                instances.len() * size_of::<GlyphInstance>(),
+            )
+        };
+
+        // note: only grow the instance buffer, shrinking text reuses the existing one
+        let capacity = self.instances.as_ref().map_or(0, |buffer| buffer.size());
+        if (bytes.len() as u64) > capacity {
+            self.instances = Some(get_device().create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Glyph Instance Buffer"),
+                size: bytes.len() as u64,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+        if let Some(buffer) = &self.instances {
+            if !bytes.is_empty() {
+                get_queue().write_buffer(buffer, 0, bytes);
+            }
+        }
+    }
+
+    fn create_bind_group(
+        uniform: &UniformBuffer<GlyphUniform>,
+        texture_view: &wgpu::TextureView,
+    ) -> wgpu::BindGroup {
+        get_device().create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Glyph Bind Group"),
+            layout: &GLYPH_INFO_BIND_LAYOUT,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                        buffer: uniform.as_ref(),
+                        offset: 0,
+                        size: None,
+                    }),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(texture_view),
+                },
+            ],
+        })
+    }
+
+    /// Caret, selection and hit testing geometry relative to `rect`, `text` is the drawn text.
+    pub fn paragraph<'a>(&'a self, text: &'a str) -> Paragraph<'a> {
+        self.layout.paragraph(text)
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// Replaces the drawn layout, reusing the existing instance buffer and bind groups.
+    pub fn update_layout(&mut self, layout: TextLayout, rect: Rect) {
+        (self.placements, self.decorations) = Self::place(&layout);
+        self.layout = layout;
+        self.update_pos(rect.pos);
+        self.rect = rect;
+        self.upload();
+    }
+
     pub fn update_pos(&mut self, origin: Point) {
-        todo!()
+        if self.rect.pos == origin {
+            return;
+        }
+
+        self.rect.pos = origin;
+        self.uniform.update(&GlyphUniform {
+            transform: Self::transform(origin),
+        });
     }
 
-    pub fn update_text(&mut self, text: &str) {
-        todo!()
+    /// Sets the color of each style of the layout, `colors` is indexed like `TextLayout::styles`.
+    /// The glyphs are placed again, the text is not shaped again.
+    pub fn update_colors(&mut self, colors: &[Color]) {
+        for (style, &color) in self.layout.styles.iter_mut().zip(colors) {
+            style.color = color;
+        }
+        // note: outlines, shadows and lines that follow the text color are placed again
+        (self.placements, self.decorations) = Self::place(&self.layout);
+        self.upload();
+    }
+
+    /// Re-uploads glyphs that were evicted from the atlas since the text was last updated.
+    /// Called once per frame, even when the text itself did not change.
+    pub fn refresh(&mut self) {
+        let glyphs: Vec<&Glyph> = self.placements.iter().map(|p| p.glyph).collect();
+        let locations = locate_glyphs(&glyphs);
+        if locations != self.locations {
+            self.locations = locations;
+            self.build_instances();
+        }
     }
 }
 
 impl Visual for VisualText {
     fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
-        render_pass.set_pipeline(&GLYPH_RENDER_PIPELINE);
-        for glyph in &self.glyphs {
-            glyph.draw(render_pass);
+        let instances = match &self.instances {
+            Some(instances) if !self.batches.is_empty() => instances,
+            _ => return,
+        };
+
+        render_pass.set_vertex_buffer(0, instances.slice(..));
+        for batch in &self.batches {
+            render_pass.set_pipeline(match batch.pipeline {
+                GlyphPipeline::DistanceField => &GLYPH_RENDER_PIPELINE,
+                GlyphPipeline::Bitmap => &GLYPH_BITMAP_PIPELINE,
+                GlyphPipeline::Color => &GLYPH_COLOR_PIPELINE,
+            });
+            let bind_group = match batch.page {
+                Some(page) => &self.bind_groups[page],
+                None => &self.solid_bind_group,
+            };
+            if let Some(bind_group) = bind_group {
+                render_pass.set_bind_group(1, bind_group, &[]);
+                render_pass.draw(0..6, batch.instances.clone());
+            }
         }
     }
 }
@@ -163,11 +570,64 @@ lazy_static! {
         )))
     };
     static ref GLYPH_FRAGMENT_SHADER: wgpu::ShaderModule = unsafe {
+        match distance_field_settings().mode {
+            DistanceFieldMode::Sdf => get_device().create_shader_module_spirv(&include_spirv_raw!(
+                concat!(env!("OUT_DIR"), "/shaders/glyph_sdf.frag.spv")
+            )),
+            DistanceFieldMode::Msdf => get_device().create_shader_module_spirv(
+                &include_spirv_raw!(concat!(env!("OUT_DIR"), "/shaders/glyph_msdf.frag.spv")),
+            ),
+        }
+    };
+    static ref GLYPH_BITMAP_FRAGMENT_SHADER: wgpu::ShaderModule = unsafe {
+        get_device().create_shader_module_spirv(&include_spirv_raw!(concat!(
+            env!("OUT_DIR"),
+            "/shaders/glyph_bitmap.frag.spv"
+        )))
+    };
+    static ref GLYPH_COLOR_FRAGMENT_SHADER: wgpu::ShaderModule = unsafe {
         get_device().create_shader_module_spirv(&include_spirv_raw!(concat!(
             env!("OUT_DIR"),
-            "/shaders/glyph_sdf.frag.spv"
+            "/shaders/glyph_color.frag.spv"
         )))
     };
+    /// A single opaque texel, lines are drawn from it with the bitmap pipeline.
+    static ref SOLID_TEXTURE_VIEW: wgpu::TextureView = {
+        let size = wgpu::Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        };
+        let texture = get_device().create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Solid Texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[wgpu::TextureFormat::R8Unorm],
+        });
+        get_queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: Default::default(),
+            },
+            &[255],
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(1),
+                rows_per_image: Some(1),
+            },
+            size,
+        );
+
+        // note: like the atlas pages the texture lives as long as the program
+        let texture: &'static wgpu::Texture = Box::leak(Box::new(texture));
+        texture.create_view(&Default::default())
+    };
     static ref GLYPH_INFO_BIND_LAYOUT: wgpu::BindGroupLayout = {
         get_device().create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Glyph Info Binding Layout"),
@@ -205,40 +665,61 @@ lazy_static! {
             push_constant_ranges: &[],
         })
     };
-    static ref GLYPH_RENDER_PIPELINE: wgpu::RenderPipeline = {
-        get_device().create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Glyph Render Pipeline"),
-            layout: Some(&GLYPH_PIPELINE_LAYOUT),
-            vertex: wgpu::VertexState {
-                module: &GLYPH_VERTEX_SHADER,
-                entry_point: "main",
-                buffers: &[],
-            },
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: None,
-                unclipped_depth: false,
-                polygon_mode: wgpu::PolygonMode::Fill,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &GLYPH_FRAGMENT_SHADER,
-                entry_point: "main",
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview: None,
-        })
-    };
+    static ref GLYPH_RENDER_PIPELINE: wgpu::RenderPipeline =
+        create_glyph_pipeline("Glyph Render Pipeline", &GLYPH_FRAGMENT_SHADER);
+    static ref GLYPH_BITMAP_PIPELINE: wgpu::RenderPipeline =
+        create_glyph_pipeline("Glyph Bitmap Pipeline", &GLYPH_BITMAP_FRAGMENT_SHADER);
+    static ref GLYPH_COLOR_PIPELINE: wgpu::RenderPipeline =
+        create_glyph_pipeline("Glyph Color Pipeline", &GLYPH_COLOR_FRAGMENT_SHADER);
+}
+
+fn rect_to_array(rect: Rect) -> [f32; 4] {
+    [rect.pos.x, rect.pos.y, rect.size.width, rect.size.height]
+}
+
+fn create_glyph_pipeline(label: &str, fragment: &wgpu::ShaderModule) -> wgpu::RenderPipeline {
+    get_device().create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(&GLYPH_PIPELINE_LAYOUT),
+        vertex: wgpu::VertexState {
+            module: &GLYPH_VERTEX_SHADER,
+            entry_point: "main",
+            buffers: &[wgpu::VertexBufferLayout {
+                array_stride: size_of::<GlyphInstance>() as u64,
+                step_mode: wgpu::VertexStepMode::Instance,
+                attributes: &wgpu::vertex_attr_array![
+                    0 => Float32x4,
+                    1 => Float32x4,
+                    2 => Float32x4,
+                    3 => Float32x4,
+                    4 => Float32x2
+                ],
+            }],
+        },
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            unclipped_depth: false,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: fragment,
+            entry_point: "main",
+            targets: &[Some(wgpu::ColorTargetState {
+                format: wgpu::TextureFormat::Bgra8UnormSrgb,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        multiview: None,
+    })
 }
@@ -0,0 +1,379 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+use Rect;
+
+const PAGE_SIZE: u32 = 2048;
+// note: per texture format
+const MAX_PAGES: usize = 4;
+// note: keeps linear filtering from sampling the neighbouring glyph
+const PADDING: u32 = 1;
+
+/// Location of a glyph inside the atlas.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct AtlasLocation {
+    pub page: usize,
+    /// normalized texture coordinates
+    pub uv: Rect,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct AtlasSlot {
+    location: AtlasLocation,
+    /// epoch of the page when the glyph was uploaded, the slot is stale once the page is cleared
+    epoch: u64,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    x: u32,
+}
+
+/// Packs rectangles into rows of similar height.
+#[derive(Debug, Default)]
+struct ShelfPacker {
+    shelves: Vec<Shelf>,
+    next_y: u32,
+}
+
+impl ShelfPacker {
+    fn allocate(&mut self, width: u32, height: u32) -> Option<(u32, u32)> {
+        // note: pick the tightest shelf that still fits to limit wasted height
+        let best = self
+            .shelves
+            .iter_mut()
+            .filter(|shelf| {
+                shelf.height >= height
+                    && shelf.height <= height + height / 4 + 2
+                    && shelf.x + width <= PAGE_SIZE
+            })
+            .min_by_key(|shelf| shelf.height);
+
+        if let Some(shelf) = best {
+            let x = shelf.x;
+            shelf.x += width;
+            return Some((x, shelf.y));
+        }
+
+        if self.next_y + height > PAGE_SIZE || width > PAGE_SIZE {
+            return None;
+        }
+
+        let y = self.next_y;
+        self.next_y += height;
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+        Some((0, y))
+    }
+}
+
+#[derive(Debug)]
+struct PageTexture {
+    texture: &'static wgpu::Texture,
+    view: &'static wgpu::TextureView,
+}
+
+#[derive(Debug)]
+struct AtlasPage {
+    format: wgpu::TextureFormat,
+    /// created on the first upload
+    texture: Option<PageTexture>,
+    packer: ShelfPacker,
+    epoch: u64,
+    last_used: u64,
+}
+
+/// Texture format of the page a glyph is packed into, one byte per channel of its bitmap.
+/// Color glyphs are srgb encoded, distance fields and coverage are linear.
+fn page_format(glyph: &Glyph) -> wgpu::TextureFormat {
+    match glyph.channels() {
+        _ if glyph.is_color() => wgpu::TextureFormat::Rgba8UnormSrgb,
+        1 => wgpu::TextureFormat::R8Unorm,
+        _ => wgpu::TextureFormat::Rgba8Unorm,
+    }
+}
+
+impl PageTexture {
+    fn new(format: wgpu::TextureFormat) -> Self {
+        let texture = get_device().create_texture(&wgpu::TextureDescriptor {
+            label: Some("Glyph Atlas Page"),
+            size: wgpu::Extent3d {
+                width: PAGE_SIZE,
+                height: PAGE_SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[format],
+        });
+
+        // note: pages are never freed, evicting a page only clears its packer
+        let texture: &'static wgpu::Texture = Box::leak(Box::new(texture));
+        let view = Box::leak(Box::new(texture.create_view(&Default::default())));
+
+        Self { texture, view }
+    }
+}
+
+impl AtlasPage {
+    fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            texture: None,
+            packer: Default::default(),
+            epoch: 0,
+            last_used: 0,
+        }
+    }
+
+    fn texture(&mut self) -> &PageTexture {
+        let format = self.format;
+        self.texture.get_or_insert_with(|| PageTexture::new(format))
+    }
+
+    fn clear(&mut self) {
+        self.packer = Default::default();
+        self.epoch += 1;
+    }
+}
+
+/// Glyph bitmaps packed into a list of shared textures.
+///
+/// Each page holds glyphs of a single texture format.
+/// When every page of a format is full the page that has gone unused the longest is cleared,
+/// glyphs that lived on it are uploaded again the next time they are drawn.
+/// Pages used during the current frame are never evicted.
+#[derive(Debug, Default)]
+pub(crate) struct GlyphAtlas {
+    pages: Vec<AtlasPage>,
+    frame: u64,
+}
+
+impl GlyphAtlas {
+    fn locate(&mut self, glyph: &Glyph) -> Option<AtlasLocation> {
+        let (width, height) = glyph.bitmap_dimensions();
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let mut slot = glyph.atlas_slot.lock().unwrap();
+        if let Some(current) = *slot {
+            let page = &mut self.pages[current.location.page];
+            if page.epoch == current.epoch {
+                page.last_used = self.frame;
+                return Some(current.location);
+            }
+        }
+
+        let (page_index, x, y) = self.allocate(
+            page_format(glyph),
+            width + PADDING * 2,
+            height + PADDING * 2,
+        )?;
+        let page = &mut self.pages[page_index];
+        page.last_used = self.frame;
+
+        get_queue().write_texture(
+            wgpu::ImageCopyTexture {
+                texture: page.texture().texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: x + PADDING,
+                    y: y + PADDING,
+                    z: 0,
+                },
+                aspect: Default::default(),
+            },
+            glyph.bitmap(),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(width * glyph.channels()),
+                rows_per_image: Some(height),
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        let location = AtlasLocation {
+            page: page_index,
+            uv: Rect::new(
+                Point::new((x + PADDING) as f32, (y + PADDING) as f32) / PAGE_SIZE as f32,
+                Size::new(width as f32, height as f32) / PAGE_SIZE as f32,
+            ),
+        };
+        *slot = Some(AtlasSlot {
+            location,
+            epoch: page.epoch,
+        });
+
+        Some(location)
+    }
+
+    fn allocate(
+        &mut self,
+        format: wgpu::TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Option<(usize, u32, u32)> {
+        for (index, page) in self.pages.iter_mut().enumerate() {
+            if page.format != format {
+                continue;
+            }
+            if let Some((x, y)) = page.packer.allocate(width, height) {
+                return Some((index, x, y));
+            }
+        }
+
+        let frame = self.frame;
+        let pages = self
+            .pages
+            .iter()
+            .filter(|page| page.format == format)
+            .count();
+        let evict = self
+            .pages
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| page.format == format && page.last_used < frame)
+            .min_by_key(|(_, page)| page.last_used)
+            .map(|(index, _)| index);
+
+        let index = match evict {
+            Some(index) if pages >= MAX_PAGES => {
+                self.pages[index].clear();
+                index
+            }
+            _ => {
+                #[cfg(debug_assertions)]
+                if pages >= MAX_PAGES {
+                    eprintln!(
+                        "Glyph atlas is full with glyphs used this frame, adding page {}",
+                        pages + 1
+                    );
+                }
+                self.pages.push(AtlasPage::new(format));
+                self.pages.len() - 1
+            }
+        };
+
+        let (x, y) = self.pages[index].packer.allocate(width, height)?;
+        Some((index, x, y))
+    }
+}
+
+lazy_static! {
+    static ref GLYPH_ATLAS: Mutex<GlyphAtlas> = Mutex::new(Default::default());
+}
+
+/// Marks the start of a frame, pages used from here on are kept until the next frame.
+pub(crate) fn begin_atlas_frame() {
+    GLYPH_ATLAS.lock().unwrap().frame += 1;
+}
+
+/// Uploads any of `glyphs` that are not resident and returns where each one is.
+/// Glyphs without a bitmap, ex. spaces, have no location.
+pub(crate) fn locate_glyphs(glyphs: &[&Glyph]) -> Vec<Option<AtlasLocation>> {
+    let mut atlas = GLYPH_ATLAS.lock().unwrap();
+    glyphs.iter().map(|glyph| atlas.locate(glyph)).collect()
+}
+
+pub(crate) fn atlas_page_view(page: usize) -> &'static wgpu::TextureView {
+    GLYPH_ATLAS.lock().unwrap().pages[page].texture().view
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R8Unorm;
+
+    /// An atlas with `count` full pages, page `i` last used in frame `i`.
+    fn full_atlas(count: usize) -> GlyphAtlas {
+        let mut atlas = GlyphAtlas::default();
+        for frame in 0..count {
+            atlas.frame = frame as u64;
+            let (index, _, _) = atlas.allocate(FORMAT, PAGE_SIZE, PAGE_SIZE).unwrap();
+            atlas.pages[index].last_used = frame as u64;
+        }
+        atlas
+    }
+
+    #[test]
+    fn packs_shelves() {
+        let mut packer = ShelfPacker::default();
+        assert_eq!(packer.allocate(10, 20), Some((0, 0)));
+        assert_eq!(packer.allocate(30, 18), Some((10, 0)));
+        // note: too short for the first shelf to be worth sharing
+        assert_eq!(packer.allocate(10, 8), Some((0, 20)));
+        // note: the rest of the row is too narrow, a new shelf is started
+        assert_eq!(packer.allocate(PAGE_SIZE - 30, 20), Some((0, 28)));
+
+        assert_eq!(packer.allocate(PAGE_SIZE + 1, 1), None);
+        assert_eq!(packer.allocate(1, PAGE_SIZE - 47), None);
+        assert_eq!(packer.allocate(1, PAGE_SIZE - 48), Some((0, 48)));
+    }
+
+    #[test]
+    fn overflows_into_a_new_page() {
+        let mut atlas = GlyphAtlas::default();
+        let height = PAGE_SIZE / 2 + 1;
+        assert_eq!(atlas.allocate(FORMAT, PAGE_SIZE, height), Some((0, 0, 0)));
+        assert_eq!(atlas.allocate(FORMAT, PAGE_SIZE, height), Some((1, 0, 0)));
+        // note: the first page still has room for a smaller glyph
+        assert_eq!(atlas.allocate(FORMAT, 10, 10), Some((0, 0, height)));
+
+        // note: pages only hold glyphs of their own format
+        let color = wgpu::TextureFormat::Rgba8UnormSrgb;
+        assert_eq!(atlas.allocate(color, 10, 10), Some((2, 0, 0)));
+        assert_eq!(atlas.pages.len(), 3);
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used_page() {
+        let mut atlas = full_atlas(MAX_PAGES);
+        atlas.frame = MAX_PAGES as u64;
+        atlas.pages[0].last_used = atlas.frame - 1;
+
+        assert_eq!(atlas.allocate(FORMAT, 10, 10), Some((1, 0, 0)));
+        assert_eq!(atlas.pages.len(), MAX_PAGES);
+        assert_eq!(atlas.pages[1].epoch, 1);
+    }
+
+    #[test]
+    fn keeps_pages_used_this_frame() {
+        let mut atlas = full_atlas(MAX_PAGES);
+        atlas.frame = MAX_PAGES as u64;
+        for page in &mut atlas.pages {
+            page.last_used = atlas.frame;
+        }
+
+        assert_eq!(atlas.allocate(FORMAT, 10, 10), Some((MAX_PAGES, 0, 0)));
+        assert_eq!(atlas.pages.len(), MAX_PAGES + 1);
+        assert!(atlas.pages.iter().all(|page| page.epoch == 0));
+    }
+}
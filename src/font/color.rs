@@ -0,0 +1,170 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+use freetype::ffi;
+
+/// Selects the bitmap strike used for `pixel_size`, returns the size of the strike in pixels.
+/// The smallest strike at least as large is preferred so the bitmap is only scaled down.
+pub(crate) fn select_strike(face: &mut FontFace, pixel_size: f32) -> Result<f32, ft::Error> {
+    let raw = face.raw_mut();
+    if raw.num_fixed_sizes <= 0 || raw.available_sizes.is_null() {
+        return Err(ft::Error::InvalidPixelSize);
+    }
+
+    let strikes =
+        unsafe { std::slice::from_raw_parts(raw.available_sizes, raw.num_fixed_sizes as usize) };
+    let ppem = |index: usize| strikes[index].y_ppem as f32 / 64.0;
+
+    let mut best = 0;
+    for index in 1..strikes.len() {
+        let (size, best_size) = (ppem(index), ppem(best));
+        let better = if best_size < pixel_size {
+            size > best_size
+        } else {
+            size >= pixel_size && size < best_size
+        };
+        if better {
+            best = index;
+        }
+    }
+
+    let size = ppem(best);
+    let error = unsafe { ffi::FT_Select_Size(raw, best as i32) };
+    match error {
+        0 => Ok(size),
+        _ => Err(error.into()),
+    }
+}
+
+/// Copies a premultiplied BGRA bitmap into tightly packed, premultiplied RGBA rows.
+pub(crate) fn bgra_to_rgba(bitmap: &ft::Bitmap) -> Vec<u8> {
+    let width = bitmap.width() as usize * 4;
+    let pitch = bitmap.pitch().unsigned_abs() as usize;
+    let buffer = bitmap.buffer();
+
+    let mut pixels = Vec::with_capacity(width * bitmap.rows() as usize);
+    for row in 0..bitmap.rows() as usize {
+        let start = row * pitch;
+        if start + width > buffer.len() {
+            break;
+        }
+        for bgra in buffer[start..start + width].chunks_exact(4) {
+            pixels.extend_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+    pixels
+}
+
+/// Returns true for characters drawn as emoji unless a text presentation is requested,
+/// ex. 😀 or the regional indicators that make up flags.
+pub(crate) fn is_emoji_presentation(c: char) -> bool {
+    matches!(c as u32,
+        0x231A..=0x231B
+        | 0x23E9..=0x23EC
+        | 0x23F0
+        | 0x23F3
+        | 0x25FD..=0x25FE
+        | 0x2614..=0x2615
+        | 0x2648..=0x2653
+        | 0x267F
+        | 0x2693
+        | 0x26A1
+        | 0x26AA..=0x26AB
+        | 0x26BD..=0x26BE
+        | 0x26C4..=0x26C5
+        | 0x26CE
+        | 0x26D4
+        | 0x26EA
+        | 0x26F2..=0x26F3
+        | 0x26F5
+        | 0x26FA
+        | 0x26FD
+        | 0x2705
+        | 0x270A..=0x270B
+        | 0x2728
+        | 0x274C
+        | 0x274E
+        | 0x2753..=0x2755
+        | 0x2757
+        | 0x2795..=0x2797
+        | 0x27B0
+        | 0x27BF
+        | 0x2B1B..=0x2B1C
+        | 0x2B50
+        | 0x2B55
+        | 0x1F004
+        | 0x1F0CF
+        | 0x1F18E
+        | 0x1F191..=0x1F19A
+        | 0x1F1E6..=0x1F1FF
+        | 0x1F201
+        | 0x1F21A
+        | 0x1F22F
+        | 0x1F232..=0x1F236
+        | 0x1F238..=0x1F23A
+        | 0x1F250..=0x1F251
+        | 0x1F300..=0x1F320
+        | 0x1F32D..=0x1F335
+        | 0x1F337..=0x1F37C
+        | 0x1F37E..=0x1F393
+        | 0x1F3A0..=0x1F3CA
+        | 0x1F3CF..=0x1F3D3
+        | 0x1F3E0..=0x1F3F0
+        | 0x1F3F4
+        | 0x1F3F8..=0x1F43E
+        | 0x1F440
+        | 0x1F442..=0x1F4FC
+        | 0x1F4FF..=0x1F53D
+        | 0x1F54B..=0x1F54E
+        | 0x1F550..=0x1F567
+        | 0x1F57A
+        | 0x1F595..=0x1F596
+        | 0x1F5A4
+        | 0x1F5FB..=0x1F64F
+        | 0x1F680..=0x1F6C5
+        | 0x1F6CC
+        | 0x1F6D0..=0x1F6D2
+        | 0x1F6D5..=0x1F6D7
+        | 0x1F6DC..=0x1F6DF
+        | 0x1F6EB..=0x1F6EC
+        | 0x1F6F4..=0x1F6FC
+        | 0x1F7E0..=0x1F7EB
+        | 0x1F7F0
+        | 0x1F90C..=0x1F93A
+        | 0x1F93C..=0x1F945
+        | 0x1F947..=0x1F9FF
+        | 0x1FA70..=0x1FAFF
+    )
+}
+
+/// Variation selector that asks for the emoji presentation of the previous character, ex. ❤️.
+pub(crate) const EMOJI_VARIATION_SELECTOR: char = '\u{FE0F}';
+
+/// Variation selector that asks for the text presentation of the previous character.
+pub(crate) const TEXT_VARIATION_SELECTOR: char = '\u{FE0E}';
+
+/// Returns true for characters that continue an emoji sequence and must be drawn with the same font,
+/// ex. the zero width joiner in 👩‍💻 or the skin tone in 👍🏽.
+pub(crate) fn is_emoji_continuation(c: char) -> bool {
+    matches!(c as u32,
+        0x200D
+        | 0x20E3
+        | 0xFE0E..=0xFE0F
+        | 0x1F3FB..=0x1F3FF
+        | 0xE0020..=0xE007F
+    )
+}
@@ -0,0 +1,221 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Summary of a system font scan.
+#[derive(Debug, Default, Clone)]
+pub struct FontDiscoveryReport {
+    /// font directories that existed and were scanned
+    pub directories: Vec<PathBuf>,
+    /// display names of every family that was found
+    pub families: Vec<Box<str>>,
+    /// number of faces that were successfully cached
+    pub face_count: usize,
+    /// files or directories that could not be read, along with the reason
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+impl FontDiscoveryReport {
+    fn skip(&mut self, path: &Path, reason: impl ToString) {
+        self.skipped.push((path.into(), reason.to_string()));
+    }
+}
+
+trait FontFileExt {
+    fn is_font_file(&self) -> bool;
+}
+
+impl<T> FontFileExt for T
+where
+    T: AsRef<Path>,
+{
+    fn is_font_file(&self) -> bool {
+        let p = self.as_ref();
+        p.is_file()
+            && p.extension().is_some_and(|ext| {
+                ["ttf", "otf", "ttc", "otc", "woff", "woff2"]
+                    .iter()
+                    .any(|font_ext| ext.eq_ignore_ascii_case(font_ext))
+            })
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn system_font_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    if let Some(windir) = env_path("WINDIR") {
+        directories.push(windir.join("Fonts"));
+    }
+    if let Some(local_app_data) = env_path("LOCALAPPDATA") {
+        directories.push(local_app_data.join("Microsoft\\Windows\\Fonts"));
+    }
+
+    directories
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn system_font_directories() -> Vec<PathBuf> {
+    let mut directories = vec![
+        PathBuf::from("/System/Library/Fonts"),
+        PathBuf::from("/Library/Fonts"),
+        PathBuf::from("/Network/Library/Fonts"),
+    ];
+
+    if let Some(home) = env_path("HOME") {
+        directories.push(home.join("Library/Fonts"));
+    }
+
+    directories
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn system_font_directories() -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    let home = env_path("HOME");
+    let data_home =
+        env_path("XDG_DATA_HOME").or_else(|| home.as_ref().map(|h| h.join(".local/share")));
+
+    if let Some(data_home) = data_home {
+        directories.push(data_home.join("fonts"));
+    }
+    if let Some(home) = &home {
+        directories.push(home.join(".fonts"));
+    }
+
+    if let Some(data_dirs) = env_path("XDG_DATA_DIRS") {
+        directories.extend(std::env::split_paths(&data_dirs).map(|dir| dir.join("fonts")));
+    }
+
+    directories.push(PathBuf::from("/usr/local/share/fonts"));
+    directories.push(PathBuf::from("/usr/share/fonts"));
+
+    directories
+}
+
+/// Recursively scans `path`, adding every readable face to `cache`.
+/// Unreadable entries are recorded in `report` and otherwise ignored.
+pub(crate) fn cache_fonts<P: AsRef<Path>>(
+    cache: &mut FontCache,
+    path: P,
+    visited: &mut HashSet<PathBuf>,
+    report: &mut FontDiscoveryReport,
+) {
+    let mut pending = vec![path.as_ref().to_path_buf()];
+
+    while let Some(directory) = pending.pop() {
+        // note: canonicalize so symlinked directories are only scanned once
+        match directory.canonicalize() {
+            Ok(canonical) => {
+                if !visited.insert(canonical) {
+                    continue;
+                }
+            }
+            Err(err) => {
+                report.skip(&directory, err);
+                continue;
+            }
+        }
+
+        let entries = match std::fs::read_dir(&directory) {
+            Ok(entries) => entries,
+            Err(err) => {
+                report.skip(&directory, err);
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let path = match entry {
+                Ok(entry) => entry.path(),
+                Err(err) => {
+                    report.skip(&directory, err);
+                    continue;
+                }
+            };
+
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+
+            if !path.is_font_file() {
+                continue;
+            }
+
+            let source = FontSource::File(path.as_path().into());
+            let faces = match source.open_faces() {
+                Ok(faces) => faces,
+                Err(err) => {
+                    report.skip(&path, err);
+                    continue;
+                }
+            };
+
+            for (index, face) in faces {
+                let family_name = match face.family_name() {
+                    Some(family_name) => family_name,
+                    None => {
+                        report.skip(&path, "font has no family name");
+                        continue;
+                    }
+                };
+
+                let font = match Font::new(source.clone(), index, face) {
+                    Ok(font) => font,
+                    Err(err) => {
+                        report.skip(&path, err);
+                        continue;
+                    }
+                };
+
+                insert_font(cache, &family_name, font, false);
+                report.face_count += 1;
+            }
+        }
+    }
+}
+
+pub(crate) fn create_font_cache() -> (FontCache, FontDiscoveryReport) {
+    let mut cache = HashMap::new();
+    let mut report = FontDiscoveryReport::default();
+    let mut visited = HashSet::new();
+
+    for directory in system_font_directories() {
+        if !directory.is_dir() {
+            continue;
+        }
+        cache_fonts(&mut cache, &directory, &mut visited, &mut report);
+        report.directories.push(directory);
+    }
+
+    let mut families: Vec<Box<str>> = cache.values().map(|family| family.name.clone()).collect();
+    families.sort();
+    report.families = families;
+
+    (cache, report)
+}
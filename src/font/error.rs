@@ -0,0 +1,77 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use freetype as ft;
+
+/// Error returned when a font can't be read, registered or resolved.
+#[derive(Debug)]
+pub enum FontError {
+    /// the font file could not be read
+    Io(std::io::Error),
+    /// the data is not in a format that can be loaded, ex. an unknown file
+    UnsupportedFormat(&'static str),
+    /// the data claims a supported format but is damaged or truncated
+    InvalidData(String),
+    /// FreeType failed to load the face
+    FreeType(ft::Error),
+    /// the face has no family name to register it under
+    MissingFamilyName,
+    /// no installed family matches the query
+    FamilyNotFound,
+    /// settings that must be made before the first font is loaded were changed afterwards
+    AlreadyInitialized,
+}
+
+impl std::fmt::Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontError::Io(err) => write!(f, "failed to read font: {}", err),
+            FontError::UnsupportedFormat(format) => {
+                write!(f, "unsupported font format: {}", format)
+            }
+            FontError::InvalidData(reason) => write!(f, "invalid font data: {}", reason),
+            FontError::FreeType(err) => write!(f, "failed to load face: {}", err),
+            FontError::MissingFamilyName => write!(f, "font has no family name"),
+            FontError::FamilyNotFound => write!(f, "no installed font family matches the query"),
+            FontError::AlreadyInitialized => write!(f, "fonts have already been loaded"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Io(err) => Some(err),
+            FontError::FreeType(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for FontError {
+    fn from(err: std::io::Error) -> Self {
+        FontError::Io(err)
+    }
+}
+
+impl From<ft::Error> for FontError {
+    fn from(err: ft::Error) -> Self {
+        match err {
+            ft::Error::UnknownFileFormat => FontError::UnsupportedFormat("unknown file format"),
+            err => FontError::FreeType(err),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+
+use std::ops::Range;
+
+#[cfg(target_os = "windows")]
+const SYSTEM_FALLBACK_FAMILIES: &[&str] = &[
+    "Segoe UI Symbol",
+    "Segoe UI Emoji",
+    "Segoe UI Historic",
+    "Nirmala UI",
+    "Microsoft YaHei",
+    "Yu Gothic",
+    "Malgun Gothic",
+    "Arial Unicode MS",
+];
+
+#[cfg(target_os = "macos")]
+const SYSTEM_FALLBACK_FAMILIES: &[&str] = &[
+    "Apple Symbols",
+    "Apple Color Emoji",
+    "PingFang SC",
+    "Hiragino Sans",
+    "Apple SD Gothic Neo",
+    "Kohinoor Devanagari",
+    "Arial Unicode MS",
+];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const SYSTEM_FALLBACK_FAMILIES: &[&str] = &[
+    "Noto Sans Symbols",
+    "Noto Sans Symbols2",
+    "Noto Color Emoji",
+    "Noto Sans CJK SC",
+    "Noto Sans CJK JP",
+    "Noto Sans CJK KR",
+    "Noto Sans Devanagari",
+    "Noto Sans Arabic",
+    "Noto Sans Hebrew",
+    "DejaVu Sans",
+    "FreeSans",
+    "Unifont",
+];
+
+#[cfg(target_os = "windows")]
+const EMOJI_FAMILIES: &[&str] = &["Segoe UI Emoji"];
+
+#[cfg(target_os = "macos")]
+const EMOJI_FAMILIES: &[&str] = &["Apple Color Emoji"];
+
+#[cfg(all(unix, not(target_os = "macos")))]
+const EMOJI_FAMILIES: &[&str] = &["Noto Color Emoji", "Twemoji", "JoyPixels", "EmojiOne Color"];
+
+/// Finds the family emoji are drawn with.
+/// The platform emoji family is preferred, then any installed family with color glyphs.
+pub fn find_emoji_family() -> Option<&'static FontFamily> {
+    if let Some(family) = EMOJI_FAMILIES
+        .iter()
+        .find_map(|&family| find_family(family))
+    {
+        return Some(family);
+    }
+
+    let mut candidates: Vec<&'static FontFamily> = get_font_cache()
+        .values()
+        .map(|family| static_family(family))
+        .filter(|family| family.fonts().any(|font| font.has_color()))
+        .collect();
+    candidates.sort_by(|a, b| a.name().cmp(b.name()));
+    candidates.first().copied()
+}
+
+lazy_static! {
+    // note: last resort lookups scan every installed family, remember the family per codepoint,
+    // the face is picked from it for each style
+    pub(super) static ref LAST_RESORT_CACHE: RwLock<HashMap<char, Option<&'static FontFamily>>> =
+        RwLock::new(HashMap::new());
+}
+
+/// Forgets remembered last resort lookups, called when new faces are registered.
+pub(crate) fn clear_last_resort_cache() {
+    LAST_RESORT_CACHE.write().unwrap().clear();
+}
+
+/// Finds the first installed family, by name, with a face that has `codepoint`.
+fn find_last_resort_family(codepoint: char) -> Option<&'static FontFamily> {
+    if let Some(&family) = LAST_RESORT_CACHE.read().unwrap().get(&codepoint) {
+        return family;
+    }
+
+    let mut families: Vec<&'static FontFamily> = get_font_cache()
+        .values()
+        .map(|family| static_family(family))
+        .collect();
+    families.sort_by(|a, b| a.name().cmp(b.name()));
+
+    // note: the scan runs without the lock held, two threads may both scan for the same codepoint
+    let family = families
+        .into_iter()
+        .find(|family| family.fonts().any(|font| font.has_glyph(codepoint)));
+    LAST_RESORT_CACHE.write().unwrap().insert(codepoint, family);
+    family
+}
+
+fn find_last_resort_font(codepoint: char, style: FontStyle) -> Option<&'static Font> {
+    let family = find_last_resort_family(codepoint)?;
+    // note: the closest style may lack the glyph, ex. a symbol only drawn in the regular face
+    family
+        .find_best_match(style)
+        .ok()
+        .filter(|font| font.has_glyph(codepoint))
+        .or_else(|| family.fonts().find(|font| font.has_glyph(codepoint)))
+}
+
+/// A run of text that is drawn with a single font.
+#[derive(Debug, Clone)]
+pub struct FontRun {
+    pub font: &'static Font,
+    /// byte range into the source text
+    pub range: Range<usize>,
+}
+
+/// Ordered list of fonts used to find a face that covers each codepoint.
+///
+/// The first font is the primary face, followed by the remaining families of
+/// the query's fallback chain and then the platform fallback families.
+#[derive(Debug, Clone)]
+pub struct FontFallback {
+    fonts: Vec<&'static Font>,
+    /// tried first for characters with an emoji presentation
+    emoji: Option<&'static Font>,
+    style: FontStyle,
+}
+
+impl FontFallback {
+    pub fn new(query: &BestFontQuery) -> Result<Self, FontError> {
+        let primary = find_best_font(query)?;
+
+        let mut fallback = Self {
+            fonts: vec![primary],
+            emoji: find_emoji_family().and_then(|family| family.find_best_match(query.style).ok()),
+            style: query.style,
+        };
+
+        let chain: Vec<&'static FontFamily> = match query.query {
+            FontQuery::FamilyName(_) => Vec::new(),
+            FontQuery::FontType(font_type) => find_generic_family(font_type).into_iter().collect(),
+            FontQuery::FamilyWithFallback(family_name, font_type) => find_family(family_name)
+                .into_iter()
+                .chain(find_generic_family(font_type))
+                .collect(),
+            FontQuery::Families(families) => families.iter().filter_map(resolve_family).collect(),
+        };
+
+        chain
+            .into_iter()
+            .chain(
+                SYSTEM_FALLBACK_FAMILIES
+                    .iter()
+                    .filter_map(|&family| find_family(family)),
+            )
+            .filter_map(|family| family.find_best_match(query.style).ok())
+            .for_each(|font| fallback.push(font));
+
+        Ok(fallback)
+    }
+
+    /// Fallback chain for text whose families are not installed.
+    /// Starts at the sans serif family, or the first installed family by name if there is none.
+    /// Fails only when no fonts are installed at all.
+    pub fn last_resort(style: FontStyle) -> Result<Self, FontError> {
+        let sans_serif = Self::new(&BestFontQuery {
+            query: FontQuery::FontType(FontCatagory::SansSerif),
+            style,
+        });
+        if sans_serif.is_ok() {
+            return sans_serif;
+        }
+
+        let mut families: Vec<&'static FontFamily> = get_font_cache()
+            .values()
+            .map(|family| static_family(family))
+            .collect();
+        families.sort_by(|a, b| a.name().cmp(b.name()));
+
+        let family = families.first().ok_or(FontError::FamilyNotFound)?;
+        Self::new(&BestFontQuery {
+            query: FontQuery::FamilyName(family.name()),
+            style,
+        })
+    }
+
+    fn push(&mut self, font: &'static Font) {
+        if !self.fonts.iter().any(|&f| std::ptr::eq(f, font)) {
+            self.fonts.push(font);
+        }
+    }
+
+    pub fn primary(&self) -> &'static Font {
+        self.fonts[0]
+    }
+
+    pub fn fonts(&self) -> &[&'static Font] {
+        &self.fonts
+    }
+
+    /// Returns the first font that has a glyph for `codepoint`.
+    /// If no installed font covers it, the primary font is returned so its `.notdef` glyph is drawn.
+    pub fn font_for(&self, codepoint: char) -> &'static Font {
+        self.fonts
+            .iter()
+            .copied()
+            .find(|font| font.has_glyph(codepoint))
+            .or_else(|| find_last_resort_font(codepoint, self.style))
+            .unwrap_or(self.primary())
+    }
+
+    /// Returns the emoji font if it has a glyph for `codepoint`, otherwise the same as `font_for`.
+    pub fn emoji_font_for(&self, codepoint: char) -> &'static Font {
+        match self.emoji {
+            Some(font) if font.has_glyph(codepoint) => font,
+            _ => self.font_for(codepoint),
+        }
+    }
+
+    /// Splits `text` into runs that can each be drawn with a single font.
+    /// Whitespace, control characters and the joiners / modifiers of emoji sequences stay in the current run.
+    pub fn runs(&self, text: &str) -> Vec<FontRun> {
+        let mut runs: Vec<FontRun> = Vec::new();
+
+        let mut chars = text.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            let end = index + c.len_utf8();
+            let next = chars.peek().map(|&(_, next)| next);
+
+            let font = if c.is_whitespace() || c.is_control() || is_emoji_continuation(c) {
+                match runs.last_mut() {
+                    Some(run) => {
+                        run.range.end = end;
+                        continue;
+                    }
+                    None => self.primary(),
+                }
+            } else if next == Some(EMOJI_VARIATION_SELECTOR)
+                || (is_emoji_presentation(c) && next != Some(TEXT_VARIATION_SELECTOR))
+            {
+                self.emoji_font_for(c)
+            } else {
+                self.font_for(c)
+            };
+
+            match runs.last_mut() {
+                Some(run) if std::ptr::eq(run.font, font) => run.range.end = end,
+                _ => runs.push(FontRun {
+                    font,
+                    range: index..end,
+                }),
+            }
+        }
+
+        runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // note: tests/fonts/make_color_font.py builds this bitmap only CBDT font
+    const COLOR_EMOJI: &[u8] = include_bytes!("../../tests/fonts/color-emoji.ttf");
+
+    #[test]
+    fn emoji_resolves_to_a_color_font() {
+        register_font_bytes(COLOR_EMOJI).unwrap();
+
+        let font = find_family("Uxui Test Emoji")
+            .unwrap()
+            .find_best_match(FontStyle::default())
+            .unwrap();
+        assert!(font.has_color());
+        assert!(font.has_glyph('😀'));
+
+        let fallback = FontFallback::last_resort(FontStyle::default()).unwrap();
+        let emoji = fallback.emoji_font_for('😀');
+        assert!(emoji.has_color());
+        assert!(emoji.has_glyph('😀'));
+
+        let runs = fallback.runs("smile 😀");
+        assert_eq!(runs.last().unwrap().range, 6..10);
+        assert!(std::ptr::eq(runs.last().unwrap().font, emoji));
+    }
+
+    #[test]
+    fn last_resort_font_follows_the_style() {
+        let regular = FontStyle::default();
+        let bold = FontStyle {
+            weight: FontWeight::Bold,
+            ..Default::default()
+        };
+        let italic = FontStyle {
+            type_: FontType::Italic,
+            ..Default::default()
+        };
+
+        let family = find_last_resort_family('a').unwrap();
+        // note: the first style to ask must not fix the face for the others
+        for style in [regular, bold, italic, regular] {
+            let font = find_last_resort_font('a', style).unwrap();
+            assert!(std::ptr::eq(font, family.find_best_match(style).unwrap()));
+        }
+    }
+}
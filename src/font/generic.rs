@@ -0,0 +1,199 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+
+/// A single entry in a font family fallback chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FamilyName {
+    /// a concrete family, ex. "Inter"
+    Named(Box<str>),
+    /// any installed family of the given category
+    Generic(FontCatagory),
+}
+
+impl From<&str> for FamilyName {
+    fn from(value: &str) -> Self {
+        FamilyName::Named(value.into())
+    }
+}
+
+impl From<FontCatagory> for FamilyName {
+    fn from(value: FontCatagory) -> Self {
+        FamilyName::Generic(value)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn preferred_families(category: FontCatagory) -> &'static [&'static str] {
+    match category {
+        FontCatagory::Serif => &["Cambria", "Times New Roman", "Georgia"],
+        FontCatagory::SansSerif => &["Segoe UI", "Arial", "Tahoma", "Verdana"],
+        FontCatagory::Monospace => &["Cascadia Mono", "Consolas", "Courier New"],
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn preferred_families(category: FontCatagory) -> &'static [&'static str] {
+    match category {
+        FontCatagory::Serif => &["New York", "Times New Roman", "Times", "Georgia"],
+        FontCatagory::SansSerif => &["SF Pro", "Helvetica Neue", "Helvetica", "Arial"],
+        FontCatagory::Monospace => &["SF Mono", "Menlo", "Monaco", "Courier New"],
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn preferred_families(category: FontCatagory) -> &'static [&'static str] {
+    match category {
+        FontCatagory::Serif => &[
+            "Noto Serif",
+            "DejaVu Serif",
+            "Liberation Serif",
+            "FreeSerif",
+        ],
+        FontCatagory::SansSerif => &[
+            "Noto Sans",
+            "DejaVu Sans",
+            "Liberation Sans",
+            "Cantarell",
+            "Ubuntu",
+            "FreeSans",
+        ],
+        FontCatagory::Monospace => &[
+            "Noto Sans Mono",
+            "DejaVu Sans Mono",
+            "Liberation Mono",
+            "Ubuntu Mono",
+            "FreeMono",
+        ],
+    }
+}
+
+lazy_static! {
+    static ref GENERIC_FAMILY_OVERRIDES: RwLock<HashMap<FontCatagory, Vec<Box<str>>>> =
+        RwLock::new(HashMap::new());
+}
+
+#[cfg(test)]
+lazy_static! {
+    /// Held for writing by tests that override generic families, and for reading by tests that resolve them.
+    pub(crate) static ref GENERIC_FAMILIES_TEST_LOCK: RwLock<()> = RwLock::new(());
+}
+
+/// Overrides the families a generic category resolves to.
+/// The families are tried in order before the platform defaults.
+pub fn set_generic_families(category: FontCatagory, families: &[&str]) {
+    GENERIC_FAMILY_OVERRIDES
+        .write()
+        .unwrap()
+        .insert(category, families.iter().map(|&f| f.into()).collect());
+}
+
+/// Restores the platform defaults for a generic category.
+pub fn clear_generic_families(category: FontCatagory) {
+    GENERIC_FAMILY_OVERRIDES.write().unwrap().remove(&category);
+}
+
+pub(crate) fn find_family(family_name: &str) -> Option<&'static FontFamily> {
+    let key = family_name.to_lowercase();
+    get_font_cache()
+        .get(key.as_str())
+        .map(|family| static_family(family))
+}
+
+/// Resolves a generic category to an installed family.
+///
+/// User overrides are tried first, then the platform preference table,
+/// and finally any installed family that classifies itself as `category`.
+pub fn find_generic_family(category: FontCatagory) -> Option<&'static FontFamily> {
+    let overridden = GENERIC_FAMILY_OVERRIDES
+        .read()
+        .unwrap()
+        .get(&category)
+        .and_then(|families| families.iter().find_map(|family| find_family(family)));
+    if overridden.is_some() {
+        return overridden;
+    }
+
+    if let Some(family) = preferred_families(category)
+        .iter()
+        .find_map(|&family| find_family(family))
+    {
+        return Some(family);
+    }
+
+    let mut candidates: Vec<&'static FontFamily> = get_font_cache()
+        .values()
+        .map(|family| static_family(family))
+        .filter(|family| family.category() == Some(category))
+        .collect();
+    candidates.sort_by(|a, b| a.name().cmp(b.name()));
+    candidates.first().copied()
+}
+
+pub(crate) fn resolve_family(name: &FamilyName) -> Option<&'static FontFamily> {
+    match name {
+        FamilyName::Named(family_name) => find_family(family_name),
+        FamilyName::Generic(category) => find_generic_family(*category),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generic_name(category: FontCatagory) -> Option<&'static str> {
+        find_generic_family(category).map(|family| family.name())
+    }
+
+    #[test]
+    fn overrides_replace_the_preferred_families() {
+        let _lock = GENERIC_FAMILIES_TEST_LOCK
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let category = FontCatagory::Monospace;
+
+        let default = generic_name(category);
+        if let Some(preferred) = preferred_families(category)
+            .iter()
+            .find_map(|&family| find_family(family))
+        {
+            assert_eq!(default, Some(preferred.name()));
+        }
+
+        let other = get_font_cache()
+            .values()
+            .map(|family| static_family(family).name())
+            .filter(|&name| Some(name) != default)
+            .min()
+            .expect("a second font family is installed");
+
+        // note: families that are not installed are skipped
+        set_generic_families(category, &["Missing Family", other]);
+        assert_eq!(generic_name(category), Some(other));
+        assert_eq!(
+            resolve_family(&FamilyName::Generic(category)).map(|family| family.name()),
+            Some(other)
+        );
+
+        set_generic_families(category, &["Missing Family"]);
+        assert_eq!(generic_name(category), default);
+
+        set_generic_families(category, &[other]);
+        clear_generic_families(category);
+        assert_eq!(generic_name(category), default);
+    }
+}
@@ -0,0 +1,213 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+use rustybuzz::ttf_parser;
+use Rect;
+
+/// Face wide metrics in font units, read from the hhea, OS/2 and post tables.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct UnitMetrics {
+    units_per_em: f32,
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+    x_height: f32,
+    cap_height: f32,
+    underline_position: f32,
+    underline_thickness: f32,
+    strikeout_position: f32,
+    strikeout_thickness: f32,
+}
+
+impl UnitMetrics {
+    pub(crate) fn read(face: &ttf_parser::Face) -> Self {
+        let units_per_em = face.units_per_em() as f32;
+
+        // note: OS/2 tables before version 2 have no x / cap height, measure the glyphs instead
+        let glyph_top = |c: char| {
+            face.glyph_index(c)
+                .and_then(|glyph| face.glyph_bounding_box(glyph))
+                .map(|bounds| bounds.y_max as f32)
+        };
+        let x_height = face
+            .x_height()
+            .map(|h| h as f32)
+            .filter(|&h| h > 0.0)
+            .or_else(|| glyph_top('x'))
+            .unwrap_or(units_per_em * 0.5);
+        let cap_height = face
+            .capital_height()
+            .map(|h| h as f32)
+            .filter(|&h| h > 0.0)
+            .or_else(|| glyph_top('H'))
+            .unwrap_or(units_per_em * 0.7);
+
+        let default_thickness = units_per_em / 20.0;
+        let thickness = |t: i16| {
+            if t > 0 {
+                t as f32
+            } else {
+                default_thickness
+            }
+        };
+
+        let (underline_position, underline_thickness) = match face.underline_metrics() {
+            Some(line) => (line.position as f32, thickness(line.thickness)),
+            None => (-units_per_em / 10.0, default_thickness),
+        };
+        let (strikeout_position, strikeout_thickness) = match face.strikeout_metrics() {
+            Some(line) if line.position != 0 => (line.position as f32, thickness(line.thickness)),
+            // note: the position is the top of the stroke, center it on half the x-height
+            _ => ((x_height + underline_thickness) / 2.0, underline_thickness),
+        };
+
+        Self {
+            units_per_em,
+            ascent: face.ascender() as f32,
+            descent: face.descender() as f32,
+            line_gap: face.line_gap() as f32,
+            x_height,
+            cap_height,
+            underline_position,
+            underline_thickness,
+            strikeout_position,
+            strikeout_thickness,
+        }
+    }
+}
+
+/// Metrics of a font scaled to a `FontSize`, in pixels.
+/// Vertical values are measured upwards from the baseline, so `descent` and `underline_position` are usually negative.
+#[derive(Debug, Copy, Clone)]
+pub struct FontMetrics<'a> {
+    font: &'a Font,
+    /// pixels per font unit
+    scale: f32,
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    /// `ascent - descent + line_gap`
+    pub line_height: f32,
+    pub x_height: f32,
+    pub cap_height: f32,
+    /// top of the underline stroke
+    pub underline_position: f32,
+    pub underline_thickness: f32,
+    /// top of the strikeout stroke
+    pub strikeout_position: f32,
+    pub strikeout_thickness: f32,
+}
+
+impl<'a> FontMetrics<'a> {
+    pub(crate) fn new(
+        font: &'a Font,
+        units: &UnitMetrics,
+        size: FontSize,
+        scale_factor: f32,
+    ) -> Self {
+        let scale = pixel_size(size, scale_factor) / units.units_per_em;
+        Self {
+            font,
+            scale,
+            ascent: units.ascent * scale,
+            descent: units.descent * scale,
+            line_gap: units.line_gap * scale,
+            line_height: (units.ascent - units.descent + units.line_gap) * scale,
+            x_height: units.x_height * scale,
+            cap_height: units.cap_height * scale,
+            underline_position: units.underline_position * scale,
+            underline_thickness: units.underline_thickness * scale,
+            strikeout_position: units.strikeout_position * scale,
+            strikeout_thickness: units.strikeout_thickness * scale,
+        }
+    }
+
+    /// Pixels per font unit.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Horizontal advance of a glyph, without kerning.
+    pub fn advance(&self, glyph_index: u32) -> f32 {
+        self.font
+            .shaping_face()
+            .and_then(|face| face.glyph_hor_advance(ttf_parser::GlyphId(glyph_index as u16)))
+            .map_or(0.0, |advance| advance as f32 * self.scale)
+    }
+
+    /// Tight bounds of a glyph's outline relative to the pen on the baseline, y increases downwards.
+    /// Returns `None` for glyphs without an outline, ex. a space.
+    pub fn glyph_bounds(&self, glyph_index: u32) -> Option<Rect> {
+        let face = self.font.shaping_face()?;
+
+        // note: outlining applies the variation, the bounding box in the glyf table does not
+        let bounds = face.outline_glyph(ttf_parser::GlyphId(glyph_index as u16), &mut NoOutline)?;
+        Some(Rect::new(
+            Point::new(
+                bounds.x_min as f32 * self.scale,
+                -bounds.y_max as f32 * self.scale,
+            ),
+            Size::new(
+                bounds.width() as f32 * self.scale,
+                bounds.height() as f32 * self.scale,
+            ),
+        ))
+    }
+
+    /// Adjustment applied to the advance of `left` when it is followed by `right`,
+    /// from the GPOS or kern tables. Negative values move the pair closer.
+    pub fn kerning(&self, left: char, right: char) -> f32 {
+        let face = match self.font.shaping_face() {
+            Some(face) => face,
+            None => return 0.0,
+        };
+
+        // note: ligatures would merge the pair into one glyph
+        let features: Vec<rustybuzz::Feature> = [b"liga", b"clig", b"calt"]
+            .iter()
+            .map(|&tag| rustybuzz::Feature::new(ttf_parser::Tag::from_bytes(tag), 0, ..))
+            .collect();
+
+        let first_advance = |text: &str| {
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(text);
+            buffer.guess_segment_properties();
+            let output = rustybuzz::shape(&face, &features, buffer);
+            output
+                .glyph_positions()
+                .first()
+                .map_or(0, |position| position.x_advance)
+        };
+
+        let mut pair = String::new();
+        pair.push(left);
+        pair.push(right);
+        let kerned = first_advance(&pair);
+        let plain = first_advance(&pair[..left.len_utf8()]);
+        (kerned - plain) as f32 * self.scale
+    }
+}
+
+struct NoOutline;
+
+impl ttf_parser::OutlineBuilder for NoOutline {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
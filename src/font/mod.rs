@@ -17,160 +17,255 @@
 use freetype::ffi::{FT_Size_Metrics, FT_UShort};
 use {freetype as ft, Size};
 
-use std::cell::*;
-
 use gfx::*;
 use wgpu;
 
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::pin::Pin;
+use std::mem::ManuallyDrop;
 use std::sync::*;
 use lazy_static::lazy_static;
 use Point;
 
-// #[cfg(target_os = "windows")]
-// const SYSTEM_FONT_PATH: &str = "C:/Windows/Fonts/";
+mod atlas;
+mod color;
+mod discovery;
+mod error;
+mod fallback;
+mod generic;
+mod metrics;
+mod registry;
+mod rendering;
+mod sdf;
+mod variation;
+mod woff;
+pub(crate) use self::atlas::*;
+use self::color::*;
+pub use self::discovery::FontDiscoveryReport;
+use self::discovery::*;
+pub use self::error::FontError;
+use self::woff::*;
+pub use self::fallback::*;
+pub use self::generic::*;
+pub use self::metrics::FontMetrics;
+use self::metrics::*;
+pub use self::registry::*;
+pub use self::rendering::*;
+pub use self::sdf::*;
+pub use self::variation::{FontVariation, VariationAxis};
+use self::variation::*;
+
+/// Dots per inch of a display with a scale factor of 1, a logical pixel is 1/96th of an inch.
+const DPI: u32 = 96;
 
-#[cfg(target_os = "linux")]
-const SYSTEM_FONT_PATH: &str = "/usr/share/fonts/";
+/// Size of the root em in points, `FontSize::rem(1.0)`.
+pub(crate) const PT_PER_EM: f32 = 12.0;
 
-#[cfg(target_os = "macos")]
-const SYSTEM_FONT_PATH: &str = "/System/Library/Fonts/";
+/// Size in points glyphs are rendered at, see `DistanceFieldSettings::resolution`.
+fn render_size_pt() -> f32 {
+    distance_field_settings().resolution * 72.0 / DPI as f32
+}
 
-const DPI: u32 = 96;
+pub(crate) fn calculate_font_scale(size: FontSize, scale_factor: f32) -> f32 {
+    size.as_px(scale_factor) / distance_field_settings().resolution
+}
 
-pub(crate) const PT_PER_EM: f32 = 12.0;
+/// The FreeType library every face is created from.
+pub(crate) struct FreeTypeLibrary(ft::Library);
+
+// note: the library is only reachable through `FREETYPE_LIBRARY`'s mutex
+unsafe impl Send for FreeTypeLibrary {}
 
-const RENDER_SIZE_EM: f32 = 8.0;
-const RENDER_SIZE_PT: f32 = RENDER_SIZE_EM * PT_PER_EM;
+impl Deref for FreeTypeLibrary {
+    type Target = ft::Library;
 
-pub(crate) fn calculate_font_scale(size: FontSize) -> f32 {
-    size.as_em() / RENDER_SIZE_EM
+    fn deref(&self) -> &ft::Library {
+        &self.0
+    }
 }
 
-static mut FREETYPE_LIBRARY: Option<ft::Library> = None;
+static FREETYPE_LIBRARY: OnceLock<Mutex<FreeTypeLibrary>> = OnceLock::new();
 
-fn create_freetype_library() -> ft::Library {
-    ft::Library::init().unwrap()
+fn create_freetype_library() -> Mutex<FreeTypeLibrary> {
+    Mutex::new(FreeTypeLibrary(ft::Library::init().unwrap()))
 }
 
-pub(crate) fn get_freetype_library() -> &'static ft::Library {
-    unsafe { FREETYPE_LIBRARY.get_or_insert_with(create_freetype_library) }
+/// Locks the FreeType library.
+/// FreeType allows faces to be used from any thread, but creating and freeing them must be serialized.
+pub(crate) fn lock_freetype_library() -> MutexGuard<'static, FreeTypeLibrary> {
+    FREETYPE_LIBRARY
+        .get_or_init(create_freetype_library)
+        .lock()
+        .unwrap()
 }
 
-#[derive(Debug, Copy, Clone)]
+/// A FreeType face that is freed with the library locked, see `lock_freetype_library`.
+pub(crate) struct FontFace(ManuallyDrop<ft::Face>);
+
+// note: faces never share their data, and each one is only used by one thread at a time through `Font::face`
+unsafe impl Send for FontFace {}
+
+impl Deref for FontFace {
+    type Target = ft::Face;
+
+    fn deref(&self) -> &ft::Face {
+        &self.0
+    }
+}
+
+impl DerefMut for FontFace {
+    fn deref_mut(&mut self) -> &mut ft::Face {
+        &mut self.0
+    }
+}
+
+impl Drop for FontFace {
+    fn drop(&mut self) {
+        let _library = lock_freetype_library();
+        unsafe { ManuallyDrop::drop(&mut self.0) }
+    }
+}
+
+impl std::fmt::Debug for FontFace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Unit of a `FontSize`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum FontUnit {
+    /// logical pixels, scaled by the window's scale factor
+    Px,
+    /// points, 1/72th of an inch at 96 logical pixels per inch
+    Pt,
+    /// multiple of the enclosing text's size, or of the root size if there is none
+    Em,
+    /// multiple of the root size, 12pt
+    Rem,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct FontSize {
-    size_em: f32,
+    value: f32,
+    unit: FontUnit,
 }
 
 impl FontSize {
     pub fn pt(pt: f32) -> Self {
         Self {
-            size_em: pt / PT_PER_EM,
+            value: pt,
+            unit: FontUnit::Pt,
         }
     }
 
     pub fn px(px: f32) -> Self {
-        todo!()
+        Self {
+            value: px,
+            unit: FontUnit::Px,
+        }
     }
 
     pub fn em(em: f32) -> Self {
-        Self { size_em: em }
+        Self {
+            value: em,
+            unit: FontUnit::Em,
+        }
     }
 
-    pub fn as_pt(&self) -> f32 {
-        self.size_em * PT_PER_EM
+    pub fn rem(rem: f32) -> Self {
+        Self {
+            value: rem,
+            unit: FontUnit::Rem,
+        }
     }
 
-    pub fn as_px(&self) -> f32 {
-        todo!()
+    pub fn value(&self) -> f32 {
+        self.value
     }
 
-    pub fn as_em(&self) -> f32 {
-        self.size_em
+    pub fn unit(&self) -> FontUnit {
+        self.unit
     }
-}
 
-static mut FONT_CACHE: Option<HashMap<Box<str>, FontFamily>> = None;
+    /// Resolves an em size against the size of the enclosing text, other units are absolute.
+    pub fn relative_to(self, parent: FontSize) -> FontSize {
+        match self.unit {
+            FontUnit::Em => FontSize::pt(self.value * parent.as_pt()),
+            _ => self,
+        }
+    }
 
-trait FontFileExt {
-    fn is_font_file(&self) -> bool;
-}
+    /// Size in points, independent of the scale factor.
+    pub fn as_pt(&self) -> f32 {
+        match self.unit {
+            FontUnit::Px => self.value * 72.0 / DPI as f32,
+            FontUnit::Pt => self.value,
+            FontUnit::Em | FontUnit::Rem => self.value * PT_PER_EM,
+        }
+    }
 
-impl<T> FontFileExt for T
-where
-    T: AsRef<Path>,
-{
-    fn is_font_file(&self) -> bool {
-        let p = self.as_ref();
-        p.is_file()
-            && (p.extension().unwrap().eq_ignore_ascii_case("ttf")
-                || p.extension().unwrap().eq_ignore_ascii_case("otf"))
+    /// Size in physical pixels on a window with `scale_factor` physical pixels per logical pixel.
+    pub fn as_px(&self, scale_factor: f32) -> f32 {
+        self.as_logical_px() * scale_factor
     }
-}
 
-fn cache_fonts<P: AsRef<Path>>(cache: &mut HashMap<Box<str>, FontFamily>, path: P) {
-    let library = get_freetype_library();
+    /// Size in logical pixels, independent of the scale factor.
+    pub fn as_logical_px(&self) -> f32 {
+        match self.unit {
+            FontUnit::Px => self.value,
+            _ => self.as_pt() * DPI as f32 / 72.0,
+        }
+    }
 
-    for font_path in std::fs::read_dir(path).unwrap() {
-        let face = match &font_path {
-            Ok(entry) => {
-                let path = entry.path();
+    /// Size relative to the root size.
+    pub fn as_em(&self) -> f32 {
+        self.as_pt() / PT_PER_EM
+    }
+}
 
-                if !path.is_font_file() {
-                    continue;
-                }
-                match library.new_face(path, 0) {
-                    Ok(face) => face,
-                    Err(err) => {
-                        #[cfg(debug_assertions)]
-                        eprintln!("Error loading font {}: {}", entry.path().display(), err);
-                        continue;
-                    }
-                }
-            }
-            Err(err) => {
-                panic!("Error reading font directory: {}", err)
-            }
-        };
+/// Families by lowercase name.
+/// note: families and fonts are boxed so registering new faces never moves the ones already handed out
+pub type FontCache = HashMap<Box<str>, Pin<Box<FontFamily>>>;
 
-        let family_name = face.family_name().unwrap();
-        let key = family_name.to_lowercase();
+static FONT_CACHE: OnceLock<RwLock<FontCache>> = OnceLock::new();
 
-        let family = if let Some(family) = cache.get_mut(key.as_str()) {
-            family
-        } else {
-            cache.insert(key.clone().into(), FontFamily::new(family_name.as_str()));
-            cache.get_mut(key.as_str()).unwrap()
-        };
+static FONT_DISCOVERY_REPORT: OnceLock<FontDiscoveryReport> = OnceLock::new();
 
-        family
-            .fonts
-            .push(Font::new(font_path.unwrap().path().as_path(), face));
-    }
+fn initialize_font_cache() -> RwLock<FontCache> {
+    let (cache, report) = create_font_cache();
+    let _ = FONT_DISCOVERY_REPORT.set(report);
+    RwLock::new(cache)
 }
 
-#[cfg(target_os = "windows")]
-fn create_font_cache() -> HashMap<Box<str>, FontFamily> {
-    let mut cache = HashMap::new();
+/// Locks the font cache for reading, the system fonts are scanned on first use.
+pub fn get_font_cache() -> RwLockReadGuard<'static, FontCache> {
+    FONT_CACHE
+        .get_or_init(initialize_font_cache)
+        .read()
+        .unwrap()
+}
 
-    let system_font_path = PathBuf::from(std::env::var("WINDIR").unwrap()).join("Fonts");
-    let user_font_path =
-        PathBuf::from(std::env::var("LOCALAPPDATA").unwrap()).join("Microsoft\\Windows\\Fonts");
-    if system_font_path.exists() {
-        cache_fonts(&mut cache, system_font_path);
-    }
-    if user_font_path.exists() {
-        cache_fonts(&mut cache, user_font_path);
-    }
+/// Locks the font cache for registering new faces.
+pub(crate) fn get_font_cache_mut() -> RwLockWriteGuard<'static, FontCache> {
+    FONT_CACHE
+        .get_or_init(initialize_font_cache)
+        .write()
+        .unwrap()
+}
 
-    cache
+/// Extends the borrow of a family in the cache past the cache lock.
+pub(crate) fn static_family(family: &FontFamily) -> &'static FontFamily {
+    // note: families are pinned and never removed from the cache
+    unsafe { &*(family as *const FontFamily) }
 }
 
-pub fn get_font_cache() -> &'static mut HashMap<Box<str>, FontFamily> {
-    unsafe { FONT_CACHE.get_or_insert_with(create_font_cache) }
+/// Returns the results of the system font scan, performing the scan if it has not happened yet.
+pub fn discovery_report() -> &'static FontDiscoveryReport {
+    FONT_CACHE.get_or_init(initialize_font_cache);
+    FONT_DISCOVERY_REPORT.get().unwrap()
 }
 
 #[derive(Debug, Clone)]
@@ -178,6 +273,8 @@ pub enum FontQuery<'a> {
     FamilyName(&'a str),
     FontType(FontCatagory),
     FamilyWithFallback(&'a str, FontCatagory),
+    /// families are tried in order until one is installed
+    Families(&'a [FamilyName]),
 }
 
 #[derive(Debug, Clone)]
@@ -186,33 +283,19 @@ pub struct BestFontQuery<'a> {
     pub style: FontStyle,
 }
 
-pub fn find_best_font(query: &BestFontQuery) -> Result<&'static Font, ()> {
-    let cache = get_font_cache();
-
-    let mut best_font: Option<&Font> = None;
-
-    match query.query {
-        FontQuery::FamilyName(family_name) => {
-            let key = family_name.to_lowercase();
-            if let Some(family) = cache.get(key.as_str()) {
-                let font = match family.find_best_match(query.style) {
-                    Ok(font) => font,
-                    Err(error) => return Err(error),
-                };
-                best_font = Some(font);
-            }
-        }
-        FontQuery::FontType(font_type) => {
-            todo!();
-        }
+pub fn find_best_font(query: &BestFontQuery) -> Result<&'static Font, FontError> {
+    let family = match query.query {
+        FontQuery::FamilyName(family_name) => find_family(family_name),
+        FontQuery::FontType(font_type) => find_generic_family(font_type),
         FontQuery::FamilyWithFallback(family_name, font_type) => {
-            todo!()
+            find_family(family_name).or_else(|| find_generic_family(font_type))
         }
-    }
+        FontQuery::Families(families) => families.iter().find_map(resolve_family),
+    };
 
-    match best_font {
-        Some(font) => Ok(font),
-        None => Err(()),
+    match family {
+        Some(family) => family.find_best_match(query.style),
+        None => Err(FontError::FamilyNotFound),
     }
 }
 
@@ -226,66 +309,142 @@ fn align_size(size: usize, alignment: usize) -> usize {
 
 #[derive(Debug)]
 pub struct Glyph {
-    texture: Option<wgpu::Texture>,
-    texture_view: Option<wgpu::TextureView>,
+    /// tightly packed rows of the distance field, uploaded to the atlas on demand
+    bitmap: Box<[u8]>,
     bitmap_size: Size,
+    /// position of the top left corner of the bitmap relative to the pen, y increases upwards
     bitmap_offset: Point,
+    channels: u32,
+    rendering: GlyphRendering,
+    /// premultiplied rgba instead of coverage or distance
+    color: bool,
+    /// pixels per em the bitmap was rendered at
+    pixel_size: f32,
     metrics: ft::GlyphMetrics,
+    atlas_slot: Mutex<Option<AtlasSlot>>,
 }
 
 impl Glyph {
-    fn new(glyph: &ft::GlyphSlot) -> Self {
-        let device = get_device();
+    fn new(glyph: &ft::GlyphSlot, settings: &DistanceFieldSettings) -> Self {
+        let outline = Self::outline(glyph);
+
+        let (field, offset) = match FieldFrame::covering(&outline, settings.spread) {
+            Some(frame) => (
+                generate_distance_field(&outline, frame, settings.mode),
+                frame.origin,
+            ),
+            None => (
+                DistanceField {
+                    width: 0,
+                    height: 0,
+                    channels: 1,
+                    data: Vec::new(),
+                },
+                Point::new(0.0, 0.0),
+            ),
+        };
 
+        Self {
+            bitmap: field.data.into_boxed_slice(),
+            bitmap_size: Size::new(field.width as f32, field.height as f32),
+            bitmap_offset: offset,
+            channels: field.channels,
+            rendering: GlyphRendering::DistanceField,
+            color: false,
+            pixel_size: settings.resolution,
+            metrics: glyph.metrics(),
+            atlas_slot: Mutex::new(None),
+        }
+    }
+
+    /// Copies the coverage bitmap FreeType rendered into the slot.
+    fn new_bitmap(glyph: &ft::GlyphSlot, pixel_size: f32) -> Self {
         let bitmap = glyph.bitmap();
 
-        let texture = if bitmap.buffer().len() == 0 {
-            None
-        } else {
-            let texture = device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Glyph Texture"),
-                size: wgpu::Extent3d {
-                    width: bitmap.width() as u32,
-                    height: bitmap.rows() as u32,
-                    depth_or_array_layers: 1,
-                },
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::R8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[wgpu::TextureFormat::R8Unorm],
-            });
-
-            get_queue().write_texture(
-                wgpu::ImageCopyTexture {
-                    texture: &texture,
-                    mip_level: 0,
-                    origin: Default::default(),
-                    aspect: Default::default(),
-                },
-                bitmap.buffer(),
-                wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: Some(bitmap.pitch() as u32),
-                    rows_per_image: Some(bitmap.rows() as u32),
-                },
-                texture.size(),
-            );
+        let width = bitmap.width() as usize;
+        let pitch = bitmap.pitch().unsigned_abs() as usize;
+        let buffer = bitmap.buffer();
 
-            Some(texture)
-        };
+        let mut pixels = Vec::with_capacity(width * bitmap.rows() as usize);
+        for row in 0..bitmap.rows() as usize {
+            let start = row * pitch;
+            if start + width > buffer.len() {
+                break;
+            }
+            pixels.extend_from_slice(&buffer[start..start + width]);
+        }
 
-        let texture_view = texture
-            .as_ref()
-            .map(|texture| texture.create_view(&Default::default()));
+        Self {
+            bitmap: pixels.into_boxed_slice(),
+            bitmap_size: Size::new(bitmap.width() as f32, bitmap.rows() as f32),
+            bitmap_offset: Point::new(glyph.bitmap_left() as f32, glyph.bitmap_top() as f32),
+            channels: 1,
+            rendering: GlyphRendering::Bitmap,
+            color: false,
+            pixel_size,
+            metrics: glyph.metrics(),
+            atlas_slot: Mutex::new(None),
+        }
+    }
+
+    /// Copies the BGRA bitmap of a color glyph, ex. an emoji, FreeType rendered into the slot.
+    fn new_color(glyph: &ft::GlyphSlot, pixel_size: f32) -> Self {
+        let bitmap = glyph.bitmap();
 
         Self {
-            texture,
-            texture_view,
+            bitmap: bgra_to_rgba(&bitmap).into_boxed_slice(),
             bitmap_size: Size::new(bitmap.width() as f32, bitmap.rows() as f32),
             bitmap_offset: Point::new(glyph.bitmap_left() as f32, glyph.bitmap_top() as f32),
+            channels: 4,
+            rendering: GlyphRendering::Bitmap,
+            color: true,
+            pixel_size,
             metrics: glyph.metrics(),
+            atlas_slot: Mutex::new(None),
+        }
+    }
+
+    /// Converts the loaded outline from 26.6 fixed point to pixels.
+    fn outline(glyph: &ft::GlyphSlot) -> Outline {
+        let mut outline = Outline::new();
+        let ft_outline = match glyph.outline() {
+            // note: empty glyphs may have null contour arrays, which the iterator can't handle
+            Some(ft_outline) if glyph.raw().outline.n_contours > 0 => ft_outline,
+            _ => return outline,
+        };
+
+        let point = |v: &ft::Vector| Point::new(v.x as f32 / 64.0, v.y as f32 / 64.0);
+
+        for contour in ft_outline.contours_iter() {
+            outline.move_to(point(contour.start()));
+            for curve in contour {
+                match curve {
+                    ft::outline::Curve::Line(p) => outline.line_to(point(&p)),
+                    ft::outline::Curve::Bezier2(c, p) => outline.quad_to(point(&c), point(&p)),
+                    ft::outline::Curve::Bezier3(c1, c2, p) => {
+                        outline.cubic_to(point(&c1), point(&c2), point(&p))
+                    }
+                }
+            }
+            outline.close();
+        }
+
+        outline
+    }
+
+    pub(crate) fn bitmap(&self) -> &[u8] {
+        &self.bitmap
+    }
+
+    /// Width and rows of the bitmap, zero if the glyph has nothing to draw.
+    pub(crate) fn bitmap_dimensions(&self) -> (u32, u32) {
+        if self.bitmap.is_empty() {
+            (0, 0)
+        } else {
+            (
+                self.bitmap_size.width as u32,
+                self.bitmap_size.height as u32,
+            )
         }
     }
 
@@ -311,11 +470,29 @@ impl Glyph {
         self.bitmap_size
     }
 
-    pub(crate) fn texture(&self) -> Option<&wgpu::Texture> {
-        self.texture.as_ref()
+    /// Offset of the top left corner of the bitmap from the pen position, y increases upwards.
+    pub fn texture_offset(&self) -> Point {
+        self.bitmap_offset
     }
-    pub(crate) fn texture_view(&self) -> Option<&wgpu::TextureView> {
-        self.texture_view.as_ref()
+
+    /// Bytes per texel of the bitmap.
+    pub(crate) fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// Either `Bitmap` or `DistanceField`, bitmaps are drawn without scaling.
+    pub fn rendering(&self) -> GlyphRendering {
+        self.rendering
+    }
+
+    /// Returns true if the bitmap holds colors, they are drawn as is instead of tinted.
+    pub fn is_color(&self) -> bool {
+        self.color
+    }
+
+    /// Pixels per em the bitmap was rendered at, color bitmaps are scaled from it.
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
     }
 }
 
@@ -324,55 +501,343 @@ pub struct FontStyle {
     pub weight: FontWeight,
     pub width: FontWidth,
     pub type_: FontType,
+    /// exact axis values, only variable fonts can match these exactly
+    pub variation: FontVariation,
 }
 
 impl FontStyle {
+    /// Requested `wght` value, the exact weight if one is set.
+    pub fn weight_value(&self) -> f32 {
+        self.variation.weight.unwrap_or(self.weight as u16 as f32)
+    }
+
+    /// Requested `wdth` value in percent of the normal width.
+    pub fn width_value(&self) -> f32 {
+        self.variation.width.unwrap_or(self.width.percent())
+    }
+
     fn vectorize(&self) -> glm::Vec3 {
+        // note: oblique sits next to italic so either satisfies a query for the other
+        let slant = match self.type_ {
+            FontType::Normal => 0.0,
+            FontType::Oblique => 6.0,
+            FontType::Italic => 7.0,
+        };
+
+        // note: exact values sit between the weights / widths they are rounded to
         glm::vec3(
-            (self.width as i32 - FontWidth::Normal as i32) as f32 * 11.0,
-            self.type_ as u8 as f32 * 7.0,
-            (self.weight as i32 - FontWeight::Normal as i32) as f32 / 100.0 * 5.0,
+            (FontWidth::class_from_percent(self.width_value()) - FontWidth::Normal as u16 as f32)
+                * 11.0,
+            slant,
+            (self.weight_value() - FontWeight::Normal as u16 as f32) / 100.0 * 5.0,
         )
     }
 }
 
+/// Where the data of a face comes from.
+#[derive(Debug, Clone)]
+pub enum FontSource {
+    /// a file on disk, read when the face is first used
+    File(Box<Path>),
+    /// face data kept in memory, ex. a font bundled with `include_bytes!`
+    Memory(&'static [u8]),
+    /// face data read into memory and owned by the fonts opened from it, ex. a registered font file
+    Owned(Arc<[u8]>),
+}
+
+impl FontSource {
+    fn is_woff_file(path: &Path) -> bool {
+        path.extension().is_some_and(|ext| {
+            ext.eq_ignore_ascii_case("woff") || ext.eq_ignore_ascii_case("woff2")
+        })
+    }
+
+    /// Reads the font data into memory, unpacking WOFF containers.
+    pub(crate) fn read(&self) -> Result<Vec<u8>, FontError> {
+        let data = match self {
+            FontSource::File(path) => std::fs::read(path)?,
+            FontSource::Memory(bytes) => return Self::unpack(bytes),
+            FontSource::Owned(bytes) => return Self::unpack(bytes),
+        };
+
+        if is_woff(&data) {
+            Self::unpack(&data)
+        } else {
+            Ok(data)
+        }
+    }
+
+    fn unpack(bytes: &[u8]) -> Result<Vec<u8>, FontError> {
+        if is_woff(bytes) {
+            decode_woff(bytes).map_err(FontError::InvalidData)
+        } else {
+            Ok(bytes.to_vec())
+        }
+    }
+
+    /// The font data if it is already in memory and needs no unpacking.
+    fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            FontSource::File(_) => None,
+            FontSource::Memory(bytes) => Some(*bytes),
+            FontSource::Owned(bytes) => Some(&bytes[..]),
+        }
+        .filter(|bytes| !is_woff(bytes))
+    }
+
+    /// Opens the face at `index`, plain files are opened by FreeType directly.
+    pub(crate) fn open_face(&self, index: u32) -> Result<FontFace, FontError> {
+        match self {
+            FontSource::File(path) if !Self::is_woff_file(path) => {
+                Self::open_file_face(path, index)
+            }
+            _ => Self::open_memory_face(self.read()?, index),
+        }
+    }
+
+    fn open_file_face(path: &Path, index: u32) -> Result<FontFace, FontError> {
+        let library = lock_freetype_library();
+        let face = library.new_face(path, index as isize)?;
+        Ok(FontFace(ManuallyDrop::new(face)))
+    }
+
+    fn open_memory_face(data: Vec<u8>, index: u32) -> Result<FontFace, FontError> {
+        let library = lock_freetype_library();
+        let face = library.new_memory_face(data, index as isize)?;
+        Ok(FontFace(ManuallyDrop::new(face)))
+    }
+
+    /// Opens every face of the source, collections (.ttc / .otc) hold more than one.
+    /// The named instances of variable faces are opened as faces of their own.
+    /// Faces of a collection that fail to open are left out.
+    pub(crate) fn open_faces(&self) -> Result<Vec<(u32, FontFace)>, FontError> {
+        // note: read the data once, each face gets its own copy so faces can move between threads
+        let data = match self {
+            FontSource::File(path) if !Self::is_woff_file(path) => None,
+            _ => Some(self.read()?),
+        };
+        let open = |index: u32| match &data {
+            Some(data) => Self::open_memory_face(data.clone(), index),
+            None => self.open_face(index),
+        };
+
+        let first = open(0)?;
+        let count = first.num_faces().max(1) as u32;
+
+        let mut faces = vec![(0, first)];
+        for index in 1..count {
+            match open(index) {
+                Ok(face) => faces.push((index, face)),
+                Err(err) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("Error loading face {} of font {}: {}", index, self, err);
+                }
+            }
+        }
+
+        let mut instances = Vec::new();
+        for (index, face) in &mut faces {
+            let axis_count = read_axes(face).len();
+            let default = design_coordinates(face, axis_count);
+            for instance in 1..=named_instance_count(face) {
+                // note: the face index is kept, the instance is restored from its coordinates
+                match open(named_instance_index(*index, instance)) {
+                    Ok(mut face) => {
+                        // note: one named instance usually sits at the default, ex. "Regular"
+                        if design_coordinates(&mut face, axis_count) != default {
+                            instances.push((*index, face));
+                        }
+                    }
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        eprintln!(
+                            "Error loading instance {} of face {} of font {}: {}",
+                            instance, index, self, err
+                        );
+                    }
+                }
+            }
+        }
+        faces.extend(instances);
+
+        Ok(faces)
+    }
+}
+
+impl std::fmt::Display for FontSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontSource::File(path) => write!(f, "{}", path.display()),
+            FontSource::Memory(bytes) => write!(f, "<{} bytes in memory>", bytes.len()),
+            FontSource::Owned(bytes) => write!(f, "<{} bytes in memory>", bytes.len()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Font {
-    path: Box<Path>,
-    face: RwLock<Option<ft::Face>>,
+    source: FontSource,
+    /// index of the face inside a collection, 0 for single face files
+    face_index: u32,
+    face: Mutex<Option<FontFace>>,
+    /// design axes, empty for static fonts
+    axes: Box<[VariationAxis]>,
+    /// position on `axes` this font is drawn at
+    coordinates: Box<[f32]>,
     style: FontStyle,
-    glyphs: UnsafeCell<HashMap<char, Pin<Box<Glyph>>>>,
+    category: Option<FontCatagory>,
+    /// the face has color glyphs, ex. CBDT, sbix or COLR tables
+    color: bool,
+    data: OnceLock<Option<Box<[u8]>>>,
+    unit_metrics: OnceLock<Option<UnitMetrics>>,
+    glyphs: RwLock<HashMap<GlyphKey, Pin<Box<Glyph>>>>,
     metrics: FT_Size_Metrics,
+    units_per_em: u16,
 }
 
 impl Font {
-    pub fn new(path: &Path, mut face: ft::Face) -> Self {
-        let os2 = ft::tt_os2::TrueTypeOS2Table::from_face(&mut face).unwrap();
+    pub(crate) fn new(
+        source: FontSource,
+        face_index: u32,
+        mut face: FontFace,
+    ) -> Result<Self, ft::Error> {
+        // note: some older faces ship without an OS/2 table, treat them as normal weight / width
+        let (weight, width, family_class) = match ft::tt_os2::TrueTypeOS2Table::from_face(&mut face)
+        {
+            Some(os2) => (
+                FontWeight::from(os2.us_weight_class()),
+                FontWidth::from(os2.us_width_class()),
+                os2.s_family_class(),
+            ),
+            None => (FontWeight::default(), FontWidth::default(), 0),
+        };
+
+        let category = if face.is_fixed_width() {
+            Some(FontCatagory::Monospace)
+        } else {
+            FontCatagory::from_family_class(family_class)
+        };
 
         let style = face.style_flags();
+        let oblique = face
+            .style_name()
+            .is_some_and(|name| name.to_lowercase().contains("oblique"));
 
-        face.set_char_size(
-            (RENDER_SIZE_PT * 64.0) as isize,
-            (RENDER_SIZE_PT * 64.0) as isize,
-            DPI,
-            DPI,
-        )
-        .unwrap();
+        // note: bitmap only faces, ex. CBDT / sbix emoji fonts, can only be set to one of their strikes
+        if face.is_scalable() {
+            face.set_char_size(
+                (render_size_pt() * 64.0) as isize,
+                (render_size_pt() * 64.0) as isize,
+                DPI,
+                DPI,
+            )?;
+        } else {
+            select_strike(&mut face, render_size_pt() * DPI as f32 / 72.0)?;
+        }
 
-        Self {
-            path: path.into(),
-            face: RwLock::new(None),
-            style: FontStyle {
-                weight: FontWeight::from(os2.us_weight_class()),
-                width: FontWidth::from(os2.us_width_class()),
-                type_: if style.contains(ft::face::StyleFlag::ITALIC) {
-                    FontType::Italic
-                } else {
-                    FontType::Normal
-                },
+        let metrics = face.size_metrics().ok_or(ft::Error::InvalidSizeHandle)?;
+
+        let style = FontStyle {
+            weight,
+            width,
+            type_: if oblique {
+                FontType::Oblique
+            } else if style.contains(ft::face::StyleFlag::ITALIC) {
+                FontType::Italic
+            } else {
+                FontType::Normal
             },
-            glyphs: UnsafeCell::new(HashMap::new()),
-            metrics: face.size_metrics().unwrap(),
+            variation: FontVariation::default(),
+        };
+
+        // note: the OS/2 table describes the default instance, variable faces take their style from the axes
+        let axes = read_axes(&mut face);
+        let coordinates = design_coordinates(&mut face, axes.len());
+        let style = style_at_coordinates(style, &axes, &coordinates);
+
+        Ok(Self {
+            source,
+            face_index,
+            face: Mutex::new(None),
+            axes: axes.into(),
+            coordinates: coordinates.into(),
+            style,
+            category,
+            color: face.has_color(),
+            data: OnceLock::new(),
+            unit_metrics: OnceLock::new(),
+            glyphs: RwLock::new(HashMap::new()),
+            metrics,
+            units_per_em: face.em_size() as u16,
+        })
+    }
+
+    /// Opens the same face moved to `coordinates` on its design axes.
+    fn instance(&self, coordinates: &[f32]) -> Result<Font, FontError> {
+        let mut face = self.source.open_face(self.face_index)?;
+        set_design_coordinates(&mut face, coordinates)?;
+        Ok(Font::new(self.source.clone(), self.face_index, face)?)
+    }
+
+    /// Returns true if both fonts are drawn from the same face of the same source.
+    fn same_face(&self, other: &Font) -> bool {
+        let same_source = match (&self.source, &other.source) {
+            (FontSource::File(a), FontSource::File(b)) => a == b,
+            (FontSource::Memory(a), FontSource::Memory(b)) => std::ptr::eq(*a, *b),
+            (FontSource::Owned(a), FontSource::Owned(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        };
+        same_source && self.face_index == other.face_index
+    }
+
+    pub fn category(&self) -> Option<FontCatagory> {
+        self.category
+    }
+
+    /// Returns true if the face has color glyphs, ex. an emoji font.
+    pub fn has_color(&self) -> bool {
+        self.color
+    }
+
+    pub fn style(&self) -> FontStyle {
+        self.style
+    }
+
+    /// Returns true if the face has design axes, ex. `wght` for weight.
+    pub fn is_variable(&self) -> bool {
+        !self.axes.is_empty()
+    }
+
+    pub fn variation_axes(&self) -> &[VariationAxis] {
+        &self.axes
+    }
+
+    /// Position on `variation_axes` this font is drawn at, one value per axis.
+    pub fn variation_coordinates(&self) -> &[f32] {
+        &self.coordinates
+    }
+
+    /// Parses the face for shaping, moved to this font's variation.
+    pub(crate) fn shaping_face(&self) -> Option<rustybuzz::Face<'_>> {
+        let mut face = rustybuzz::Face::from_slice(self.data()?, self.face_index)?;
+        if self.is_variable() {
+            face.set_variations(&shaping_variations(&self.axes, &self.coordinates));
+        }
+        Some(face)
+    }
+
+    /// Metrics scaled to `size` on a window with `scale_factor`,
+    /// ex. the x-height for baseline alignment or the underline position.
+    pub fn metrics(&self, size: FontSize, scale_factor: f32) -> Result<FontMetrics<'_>, FontError> {
+        let units = self
+            .unit_metrics
+            .get_or_init(|| self.shaping_face().map(|face| UnitMetrics::read(&face)));
+        match units {
+            Some(units) => Ok(FontMetrics::new(self, units, size, scale_factor)),
+            None => Err(FontError::InvalidData(format!(
+                "the tables of {} could not be parsed",
+                self.source
+            ))),
         }
     }
 
@@ -388,61 +853,188 @@ impl Font {
         self.metrics.height as f32 / 64.0
     }
 
-    pub fn get_glyph(&self, codepoint: char) -> Result<&Glyph, ()> {
-        let glyph = if let Some(glyph) = unsafe { &mut *self.glyphs.get() }.get(&codepoint) {
-            glyph
-        } else {
-            self.load();
+    pub fn units_per_em(&self) -> u16 {
+        self.units_per_em
+    }
+
+    /// Scale from font units to the pixel size glyphs are rendered at.
+    pub(crate) fn render_scale(&self) -> f32 {
+        distance_field_settings().resolution / self.units_per_em as f32
+    }
+
+    pub fn source(&self) -> &FontSource {
+        &self.source
+    }
+
+    pub fn face_index(&self) -> u32 {
+        self.face_index
+    }
 
-            let lock = self.face.write().unwrap();
-            let face = lock.as_ref().unwrap();
+    /// Raw font data, read on first use. WOFF containers are unpacked.
+    pub(crate) fn data(&self) -> Option<&[u8]> {
+        if let Some(bytes) = self.source.bytes() {
+            return Some(bytes);
+        }
+
+        self.data
+            .get_or_init(|| match self.source.read() {
+                Ok(data) => Some(data.into()),
+                Err(err) => {
+                    #[cfg(debug_assertions)]
+                    eprintln!("Error reading font {}: {}", self.source, err);
+                    None
+                }
+            })
+            .as_deref()
+    }
 
+    /// Returns true if the face's charmap has a glyph for `codepoint`.
+    pub fn has_glyph(&self, codepoint: char) -> bool {
+        let lock = self.load();
+        match lock.as_ref() {
+            Some(face) => face.get_char_index(codepoint as usize).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Returns the glyph index `codepoint` maps to in the face's charmap, or 0 (`.notdef`).
+    pub fn glyph_index(&self, codepoint: char) -> u32 {
+        let lock = self.load();
+        match lock.as_ref() {
+            Some(face) => face
+                .get_char_index(codepoint as usize)
+                .map_or(0, |index| index.get()),
+            None => 0,
+        }
+    }
+
+    pub fn get_glyph(&self, codepoint: char) -> Result<&Glyph, FontError> {
+        self.get_glyph_by_index(self.glyph_index(codepoint))
+    }
+
+    /// Returns the distance field glyph, drawn scaled to any size.
+    pub fn get_glyph_by_index(&self, glyph_index: u32) -> Result<&Glyph, FontError> {
+        self.get_or_render_glyph(GlyphKey::DistanceField(glyph_index), |face| {
             face.set_char_size(
-                (RENDER_SIZE_PT * 64.0) as isize,
-                (RENDER_SIZE_PT * 64.0) as isize,
+                (render_size_pt() * 64.0) as isize,
+                (render_size_pt() * 64.0) as isize,
                 DPI,
                 DPI,
-            )
-            .unwrap();
+            )?;
 
-            face.load_char(codepoint as usize, ft::face::LoadFlag::RENDER)
-                .unwrap();
+            // note: the outline is turned into a distance field on the cpu, hinting would distort it
+            face.load_glyph(
+                glyph_index,
+                ft::face::LoadFlag::NO_HINTING | ft::face::LoadFlag::NO_BITMAP,
+            )?;
 
-            let glyph_slot = face.glyph();
+            Ok(Glyph::new(face.glyph(), distance_field_settings()))
+        })
+    }
 
-            // note: this is a hack to get the glyph to render as an sdf
-            // todo: add 'SDF_RENDER_MODE_SDF' to freetype-rs and use that instead
-            if !codepoint.is_whitespace() {
-                glyph_slot
-                    .render_glyph(unsafe { std::mem::transmute(5) })
-                    .unwrap();
+    /// Returns the glyph as a hinted, antialiased bitmap rendered at `pixel_size` pixels per em.
+    pub fn get_bitmap_glyph(&self, glyph_index: u32, pixel_size: f32) -> Result<&Glyph, FontError> {
+        let key = GlyphKey::bitmap(glyph_index, pixel_size);
+        self.get_or_render_glyph(key, |face| {
+            // note: at 72 dpi one point is one pixel
+            let size = (pixel_size * 64.0).round() as isize;
+            face.set_char_size(size, size, 72, 72)?;
+
+            face.load_glyph(
+                glyph_index,
+                ft::face::LoadFlag::RENDER | ft::face::LoadFlag::TARGET_NORMAL,
+            )?;
+
+            Ok(Glyph::new_bitmap(face.glyph(), pixel_size))
+        })
+    }
+
+    /// Returns the glyph in color, from CBDT / sbix bitmaps or COLR layers, at `pixel_size` pixels per em.
+    /// Glyphs without color data come back as coverage bitmaps.
+    pub fn get_color_glyph(&self, glyph_index: u32, pixel_size: f32) -> Result<&Glyph, FontError> {
+        let key = GlyphKey::color(glyph_index, pixel_size);
+        self.get_or_render_glyph(key, |face| {
+            // note: bitmap fonts like Noto Color Emoji only come in fixed strikes, they are scaled when drawn
+            let rendered_size = if face.has_fixed_sizes() {
+                select_strike(face, pixel_size)?
+            } else {
+                let size = (pixel_size * 64.0).round() as isize;
+                face.set_char_size(size, size, 72, 72)?;
+                pixel_size
+            };
+
+            face.load_glyph(
+                glyph_index,
+                ft::face::LoadFlag::COLOR | ft::face::LoadFlag::RENDER,
+            )?;
+
+            match face.glyph().bitmap().pixel_mode() {
+                Ok(ft::bitmap::PixelMode::Bgra) => {
+                    Ok(Glyph::new_color(face.glyph(), rendered_size))
+                }
+                _ => Ok(Glyph::new_bitmap(face.glyph(), rendered_size)),
             }
+        })
+    }
 
-            let glyph = Box::pin(Glyph::new(glyph_slot));
-            let cache = unsafe { &mut *self.glyphs.get() };
-            cache.insert(codepoint, glyph);
-            cache.get(&codepoint).unwrap()
-        };
+    fn get_or_render_glyph<F>(&self, key: GlyphKey, render: F) -> Result<&Glyph, FontError>
+    where
+        F: FnOnce(&mut FontFace) -> Result<Glyph, FontError>,
+    {
+        if let Some(glyph) = self.glyphs.read().unwrap().get(&key) {
+            return Ok(Self::static_glyph(glyph));
+        }
+
+        let mut lock = self.load();
+        let face = lock.as_mut().ok_or_else(|| {
+            FontError::InvalidData(format!("{} could not be loaded", self.source))
+        })?;
 
-        Ok(glyph.deref())
+        // note: another thread may have rendered the glyph while this one waited for the face
+        if let Some(glyph) = self.glyphs.read().unwrap().get(&key) {
+            return Ok(Self::static_glyph(glyph));
+        }
+
+        let glyph = Box::pin(render(face)?);
+        let mut cache = self.glyphs.write().unwrap();
+        Ok(Self::static_glyph(cache.entry(key).or_insert(glyph)))
     }
 
-    fn load(&self) {
-        let mut lock = self.face.write().unwrap();
+    /// Extends the borrow of a cached glyph past the cache lock.
+    fn static_glyph<'a>(glyph: &Pin<Box<Glyph>>) -> &'a Glyph {
+        // note: glyphs are pinned and never removed from the cache while the font lives
+        unsafe { &*(glyph.deref() as *const Glyph) }
+    }
+
+    /// Locks the face, opening it first if it has not been used yet.
+    fn load(&self) -> MutexGuard<'_, Option<FontFace>> {
+        let mut lock = self.face.lock().unwrap();
         match lock.deref_mut() {
-            Some(_) => return,
+            Some(_) => lock,
             None => {
-                let mut face = get_freetype_library()
-                    .new_face(self.path.as_ref(), 0)
-                    .unwrap();
-                // face.set_char_size(0, 16 * 64, DPI, DPI).unwrap();
-                *lock.deref_mut() = Some(face);
+                // note: the face loaded fine during discovery, a failure here means the file changed on disk
+                match self.source.open_face(self.face_index) {
+                    Ok(mut face) => {
+                        if self.is_variable()
+                            && set_design_coordinates(&mut face, &self.coordinates).is_err()
+                        {
+                            #[cfg(debug_assertions)]
+                            eprintln!("Error setting variation of font {}", self.source);
+                        }
+                        *lock.deref_mut() = Some(face);
+                    }
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        eprintln!("Error loading font {}: {}", self.source, err);
+                    }
+                }
+                lock
             }
         }
     }
 
     fn unload(&self) {
-        let mut lock = self.face.write().unwrap();
+        let mut lock = self.face.lock().unwrap();
         match lock.deref_mut() {
             Some(_) => {
                 *lock.deref_mut() = None;
@@ -455,14 +1047,32 @@ impl Font {
 #[derive(Debug)]
 pub struct FontFamily {
     name: Box<str>,
-    fonts: Vec<Font>,
+    fonts: RwLock<Vec<Pin<Box<Font>>>>,
+    /// variable fonts moved to exact axis values, created when a style asks for them
+    instances: Mutex<Vec<Pin<Box<Font>>>>,
+}
+
+/// Adds `font` to its family in `cache`, creating the family if needed.
+/// Preferred fonts are placed first so they win ties when matching a style.
+pub(crate) fn insert_font(cache: &mut FontCache, family_name: &str, font: Font, preferred: bool) {
+    let family = cache
+        .entry(family_name.to_lowercase().into())
+        .or_insert_with(|| Box::pin(FontFamily::new(family_name)));
+
+    let mut fonts = family.fonts.write().unwrap();
+    if preferred {
+        fonts.insert(0, Box::pin(font));
+    } else {
+        fonts.push(Box::pin(font));
+    }
 }
 
 impl FontFamily {
     fn new(name: &str) -> Self {
         Self {
             name: name.into(),
-            fonts: Vec::new(),
+            fonts: RwLock::new(Vec::new()),
+            instances: Mutex::new(Vec::new()),
         }
     }
 
@@ -470,37 +1080,90 @@ impl FontFamily {
         &self.name
     }
 
-    pub fn fonts(&self) -> &[Font] {
-        &self.fonts
+    pub fn fonts(&self) -> impl Iterator<Item = &Font> {
+        // note: fonts are pinned and never removed, they stay valid after the lock is released
+        let fonts: Vec<&Font> = self
+            .fonts
+            .read()
+            .unwrap()
+            .iter()
+            .map(|font| unsafe { &*(font.deref() as *const Font) })
+            .collect();
+        fonts.into_iter()
+    }
+
+    pub fn category(&self) -> Option<FontCatagory> {
+        self.fonts().find_map(|font| font.category())
     }
 
-    fn find_best_match(&self, query: FontStyle) -> Result<&Font, ()> {
+    fn find_best_match(&self, query: FontStyle) -> Result<&Font, FontError> {
         let query_vector = query.vectorize();
 
         let mut best_font: Option<&Font> = None;
         let mut best_distance = 0.0f32;
 
-        for font in &self.fonts {
+        for font in self.fonts() {
             let delta = glm::builtin::distance(query_vector, font.style.vectorize());
-            if let Some(_) = best_font {
-                if delta < best_distance {
-                    best_font = Some(font);
-                    best_distance = delta;
+            if best_font.is_none() || delta < best_distance {
+                best_font = Some(font);
+                best_distance = delta;
+            }
+        }
+
+        // note: a variable font can usually hit the query exactly, ex. weight 450
+        let mut best_variable: Option<(&Font, Vec<f32>)> = None;
+        for font in self.fonts().filter(|font| font.is_variable()) {
+            let coordinates = coordinates_for_style(&font.axes, &query);
+            let style = style_at_coordinates(font.style, &font.axes, &coordinates);
+            let delta = glm::builtin::distance(query_vector, style.vectorize());
+            // note: on a tie prefer the variable font over its own named instances only
+            let wins = match best_font {
+                Some(best) => {
+                    delta < best_distance || (delta == best_distance && best.same_face(font))
                 }
-            } else {
+                None => true,
+            };
+            if wins {
                 best_font = Some(font);
                 best_distance = delta;
+                best_variable = Some((font, coordinates));
             }
         }
 
-        match best_font {
-            Some(font) => Ok(font),
-            None => Err(()),
+        match best_variable {
+            Some((font, coordinates)) => self.find_instance(font, &coordinates),
+            None => best_font.ok_or(FontError::FamilyNotFound),
+        }
+    }
+
+    /// Returns `font` moved to `coordinates`, reusing named and earlier instances.
+    fn find_instance(&self, font: &Font, coordinates: &[f32]) -> Result<&Font, FontError> {
+        if let Some(existing) = self
+            .fonts()
+            .find(|f| f.same_face(font) && f.coordinates.deref() == coordinates)
+        {
+            return Ok(existing);
         }
+
+        let mut instances = self.instances.lock().unwrap();
+        let index = match instances
+            .iter()
+            .position(|f| f.same_face(font) && f.coordinates.deref() == coordinates)
+        {
+            Some(index) => index,
+            None => {
+                instances.push(Box::pin(font.instance(coordinates)?));
+                instances.len() - 1
+            }
+        };
+
+        // note: instances are pinned and never removed, they live as long as the family
+        let instance: *const Font = instances[index].deref();
+        Ok(unsafe { &*instance })
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[repr(u8)]
 pub enum FontCatagory {
     Serif,
@@ -508,26 +1171,33 @@ pub enum FontCatagory {
     Monospace,
 }
 
-#[derive(Debug, Copy, Clone)]
+impl FontCatagory {
+    /// Classifies a face from the high byte of the OS/2 `sFamilyClass` field.
+    fn from_family_class(family_class: i16) -> Option<Self> {
+        match family_class >> 8 {
+            1..=5 | 7 => Some(FontCatagory::Serif),
+            8 => Some(FontCatagory::SansSerif),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
 #[repr(u8)]
 pub enum FontType {
+    #[default]
     Normal,
     Italic,
     Oblique,
 }
 
-impl Default for FontType {
-    fn default() -> Self {
-        FontType::Normal
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 #[repr(u16)]
 pub enum FontWeight {
     Thin = 100,
     ExtraLight = 200,
     Light = 300,
+    #[default]
     Normal = 400,
     Medium = 500,
     SemiBold = 600,
@@ -537,12 +1207,6 @@ pub enum FontWeight {
     ExtraBlack = 1000,
 }
 
-impl Default for FontWeight {
-    fn default() -> Self {
-        FontWeight::Normal
-    }
-}
-
 impl From<FT_UShort> for FontWeight {
     fn from(value: FT_UShort) -> Self {
         match value {
@@ -560,13 +1224,14 @@ impl From<FT_UShort> for FontWeight {
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 #[repr(u16)]
 pub enum FontWidth {
     UltraCondensed = 1,
     ExtraCondensed = 2,
     Condensed = 3,
     SemiCondensed = 4,
+    #[default]
     Normal = 5,
     SemiExpanded = 6,
     Expanded = 7,
@@ -574,9 +1239,30 @@ pub enum FontWidth {
     UltraExpanded = 9,
 }
 
-impl Default for FontWidth {
-    fn default() -> Self {
-        FontWidth::Normal
+impl FontWidth {
+    // note: percent of the normal width for each OS/2 width class
+    const PERCENT: [f32; 9] = [50.0, 62.5, 75.0, 87.5, 100.0, 112.5, 125.0, 150.0, 200.0];
+
+    /// Width in percent of normal, the scale of the `wdth` axis.
+    pub fn percent(self) -> f32 {
+        Self::PERCENT[self as usize - 1]
+    }
+
+    /// Nearest width class for a `wdth` value.
+    pub fn from_percent(percent: f32) -> Self {
+        FontWidth::from(Self::class_from_percent(percent).round() as FT_UShort)
+    }
+
+    /// Width class for a `wdth` value, interpolated between classes.
+    fn class_from_percent(percent: f32) -> f32 {
+        let upper = Self::PERCENT
+            .iter()
+            .position(|&p| percent <= p)
+            .unwrap_or(Self::PERCENT.len() - 1)
+            .max(1);
+        let (low, high) = (Self::PERCENT[upper - 1], Self::PERCENT[upper]);
+        let t = ((percent - low) / (high - low)).clamp(0.0, 1.0);
+        upper as f32 + t
     }
 }
 
@@ -597,5 +1283,69 @@ impl From<FT_UShort> for FontWidth {
 }
 
 pub(crate) fn initialize() {
-    get_font_cache();
+    // note: only scans the system fonts, the cache is not locked
+    FONT_CACHE.get_or_init(initialize_font_cache);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn fonts_resolve_and_rasterize_concurrently() {
+        const THREADS: usize = 8;
+        const CATEGORIES: [FontCatagory; 3] = [
+            FontCatagory::SansSerif,
+            FontCatagory::Serif,
+            FontCatagory::Monospace,
+        ];
+        let _lock = GENERIC_FAMILIES_TEST_LOCK
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let barrier = Arc::new(Barrier::new(THREADS));
+        let threads: Vec<_> = (0..THREADS)
+            .map(|index| {
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+
+                    let category = CATEGORIES[index % CATEGORIES.len()];
+                    let style = FontStyle::default();
+                    let fallback = FontFallback::new(&BestFontQuery {
+                        query: FontQuery::FontType(category),
+                        style,
+                    })
+                    .or_else(|_| FontFallback::last_resort(style))
+                    .unwrap();
+
+                    // note: pointers are compared after the threads join, glyphs never move
+                    let font = fallback.font_for('a');
+                    let glyphs: Vec<usize> = ('a'..='z')
+                        .map(|c| {
+                            let size = 12.0 + index as f32;
+                            font.get_bitmap_glyph(font.glyph_index(c), size).unwrap();
+                            font.get_glyph(c).unwrap() as *const Glyph as usize
+                        })
+                        .collect();
+
+                    (category, font as *const Font as usize, glyphs)
+                })
+            })
+            .collect();
+
+        let results: Vec<_> = threads
+            .into_iter()
+            .map(|thread| thread.join().unwrap())
+            .collect();
+
+        // note: threads that asked for the same family share its font and the glyphs cached on it
+        for a in &results {
+            for b in results.iter().filter(|b| b.0 == a.0) {
+                assert_eq!(a.1, b.1);
+                assert_eq!(a.2, b.2);
+            }
+        }
+    }
 }
@@ -0,0 +1,99 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+
+/// Adds the faces in `bytes` to the font cache under their family names.
+/// `bytes` may hold a TrueType / OpenType font, a collection or a WOFF / WOFF2 container.
+///
+/// Registered faces are preferred over installed faces of the same family and style,
+/// so a font bundled with `include_bytes!` renders the same on every machine.
+///
+/// Faces that fail to load are left out, an error is returned if none of them could be registered.
+pub fn register_font_bytes(bytes: &'static [u8]) -> Result<(), FontError> {
+    register_source(FontSource::Memory(bytes))
+}
+
+/// Reads the font at `path` into memory and registers it, see `register_font_bytes`.
+///
+/// The data is owned by the registered fonts and stays in memory for as long as the font cache.
+pub fn register_font_file<P: AsRef<Path>>(path: P) -> Result<(), FontError> {
+    let bytes = std::fs::read(path)?;
+    register_source(FontSource::Owned(bytes.into()))
+}
+
+fn register_source(source: FontSource) -> Result<(), FontError> {
+    let faces = source.open_faces()?;
+
+    let mut registered = 0;
+    let mut error = None;
+    for (index, face) in faces {
+        let family_name = match face.family_name() {
+            Some(family_name) => family_name,
+            None => {
+                error = Some(FontError::MissingFamilyName);
+                continue;
+            }
+        };
+
+        match Font::new(source.clone(), index, face) {
+            Ok(font) => {
+                insert_font(&mut get_font_cache_mut(), &family_name, font, true);
+                registered += 1;
+            }
+            Err(err) => error = Some(err.into()),
+        }
+    }
+
+    clear_last_resort_cache();
+
+    match error {
+        Some(err) if registered == 0 => Err(err),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fallback::LAST_RESORT_CACHE;
+    use super::*;
+
+    // note: demo.ttf has no name table, this is the bundled font with a family name
+    const COLOR_EMOJI: &[u8] = include_bytes!("../../tests/fonts/color-emoji.ttf");
+
+    #[test]
+    fn registered_faces_win_over_installed_ones() {
+        // note: stands in for an installed copy of the family, added the way discovery adds it
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fonts/color-emoji.ttf");
+        let source = FontSource::File(path.as_path().into());
+        let (index, face) = source.open_faces().unwrap().remove(0);
+        let family_name = face.family_name().unwrap();
+        let installed = Font::new(source, index, face).unwrap();
+        insert_font(&mut get_font_cache_mut(), &family_name, installed, false);
+
+        // note: a private use codepoint no other test looks up
+        LAST_RESORT_CACHE.write().unwrap().insert('\u{E000}', None);
+
+        register_font_bytes(COLOR_EMOJI).unwrap();
+        assert!(!LAST_RESORT_CACHE.read().unwrap().contains_key(&'\u{E000}'));
+
+        let font = find_family(&family_name)
+            .unwrap()
+            .find_best_match(FontStyle::default())
+            .unwrap();
+        assert!(matches!(font.source(), FontSource::Memory(_)));
+    }
+}
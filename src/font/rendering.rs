@@ -0,0 +1,70 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+
+/// Largest size in pixels that `GlyphRendering::Auto` draws with hinted bitmaps.
+pub const BITMAP_MAX_PIXEL_SIZE: f32 = 20.0;
+
+/// How glyphs are rasterized.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GlyphRendering {
+    /// bitmaps for small sizes and distance fields above `BITMAP_MAX_PIXEL_SIZE`
+    #[default]
+    Auto,
+    /// hinted, antialiased bitmaps rendered at the exact pixel size
+    Bitmap,
+    /// distance fields scaled to the requested size, see `DistanceFieldSettings`
+    DistanceField,
+}
+
+impl GlyphRendering {
+    /// Resolves `Auto` for text drawn at `size` on a window with `scale_factor`.
+    pub fn resolve(self, size: FontSize, scale_factor: f32) -> GlyphRendering {
+        match self {
+            GlyphRendering::Auto if pixel_size(size, scale_factor) <= BITMAP_MAX_PIXEL_SIZE => {
+                GlyphRendering::Bitmap
+            }
+            GlyphRendering::Auto => GlyphRendering::DistanceField,
+            rendering => rendering,
+        }
+    }
+}
+
+/// Size of an em in physical pixels.
+pub(crate) fn pixel_size(size: FontSize, scale_factor: f32) -> f32 {
+    size.as_px(scale_factor)
+}
+
+/// Key of a glyph in a font's cache, bitmaps are cached per pixel size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum GlyphKey {
+    DistanceField(u32),
+    /// glyph index and pixel size in 26.6 fixed point
+    Bitmap(u32, u32),
+    /// same as `Bitmap`, rendered with color
+    Color(u32, u32),
+}
+
+impl GlyphKey {
+    pub(crate) fn bitmap(glyph_index: u32, pixel_size: f32) -> Self {
+        GlyphKey::Bitmap(glyph_index, (pixel_size * 64.0).round() as u32)
+    }
+
+    pub(crate) fn color(glyph_index: u32, pixel_size: f32) -> Self {
+        GlyphKey::Color(glyph_index, (pixel_size * 64.0).round() as u32)
+    }
+}
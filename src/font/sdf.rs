@@ -0,0 +1,702 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::FontError;
+use Point;
+
+use std::sync::OnceLock;
+
+/// The kind of distance field glyphs are rendered to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DistanceFieldMode {
+    /// single channel signed distance field, corners are rounded when magnified
+    Sdf,
+    /// multi-channel signed distance field, keeps corners sharp
+    Msdf,
+}
+
+/// Controls how glyph distance fields are generated.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DistanceFieldSettings {
+    pub mode: DistanceFieldMode,
+    /// pixels per em the field is generated at
+    pub resolution: f32,
+    /// distance in field pixels covered on either side of an edge
+    pub spread: f32,
+}
+
+impl Default for DistanceFieldSettings {
+    fn default() -> Self {
+        Self {
+            mode: DistanceFieldMode::Msdf,
+            resolution: 64.0,
+            spread: 6.0,
+        }
+    }
+}
+
+impl DistanceFieldSettings {
+    pub fn with_mode(mut self, mode: DistanceFieldMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: f32) -> Self {
+        self.resolution = resolution.max(1.0);
+        self
+    }
+
+    pub fn with_spread(mut self, spread: f32) -> Self {
+        self.spread = spread.max(1.0);
+        self
+    }
+}
+
+static DISTANCE_FIELD_SETTINGS: OnceLock<DistanceFieldSettings> = OnceLock::new();
+
+/// Sets how glyphs are rendered.
+/// Must be called before `initialize`, fails once the first font has been loaded.
+pub fn set_distance_field_settings(settings: DistanceFieldSettings) -> Result<(), FontError> {
+    DISTANCE_FIELD_SETTINGS
+        .set(settings)
+        .map_err(|_| FontError::AlreadyInitialized)
+}
+
+pub fn distance_field_settings() -> &'static DistanceFieldSettings {
+    DISTANCE_FIELD_SETTINGS.get_or_init(Default::default)
+}
+
+const RED: u8 = 1;
+const GREEN: u8 = 2;
+const BLUE: u8 = 4;
+const WHITE: u8 = RED | GREEN | BLUE;
+const COLORS: [u8; 3] = [GREEN | BLUE, RED | BLUE, RED | GREEN];
+
+// note: sin of the smallest angle between two edges that is treated as a corner, ~8 degrees
+const CORNER_THRESHOLD: f32 = 0.14;
+
+// note: curves are flattened into pieces at most this long, in field pixels
+const FLATTEN_LENGTH: f32 = 2.0;
+
+fn dot(a: Point, b: Point) -> f32 {
+    a.x * b.x + a.y * b.y
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+fn length(a: Point) -> f32 {
+    dot(a, a).sqrt()
+}
+
+fn normalize(a: Point) -> Point {
+    let len = length(a);
+    if len > 0.0 {
+        a / len
+    } else {
+        a
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Segment {
+    Line(Point, Point),
+    Quad(Point, Point, Point),
+    Cubic(Point, Point, Point, Point),
+}
+
+impl Segment {
+    fn point(&self, t: f32) -> Point {
+        let s = 1.0 - t;
+        match *self {
+            Segment::Line(a, b) => a * s + b * t,
+            Segment::Quad(a, b, c) => a * (s * s) + b * (2.0 * s * t) + c * (t * t),
+            Segment::Cubic(a, b, c, d) => {
+                a * (s * s * s) + b * (3.0 * s * s * t) + c * (3.0 * s * t * t) + d * (t * t * t)
+            }
+        }
+    }
+
+    fn control_points(&self) -> Vec<Point> {
+        match *self {
+            Segment::Line(a, b) => vec![a, b],
+            Segment::Quad(a, b, c) => vec![a, b, c],
+            Segment::Cubic(a, b, c, d) => vec![a, b, c, d],
+        }
+    }
+
+    /// Direction the segment leaves its start point in.
+    fn start_direction(&self) -> Point {
+        let points = self.control_points();
+        points[1..]
+            .iter()
+            .map(|&p| p - points[0])
+            .find(|&d| length(d) > 0.0)
+            .map_or(Point::new(0.0, 0.0), normalize)
+    }
+
+    /// Direction the segment arrives at its end point in.
+    fn end_direction(&self) -> Point {
+        let points = self.control_points();
+        let end = points[points.len() - 1];
+        points[..points.len() - 1]
+            .iter()
+            .rev()
+            .map(|&p| end - p)
+            .find(|&d| length(d) > 0.0)
+            .map_or(Point::new(0.0, 0.0), normalize)
+    }
+
+    fn flatten(&self, points: &mut Vec<Point>) {
+        let control = self.control_points();
+        let hull: f32 = control.windows(2).map(|w| length(w[1] - w[0])).sum();
+        let steps = match self {
+            Segment::Line(..) => 1,
+            _ => ((hull / FLATTEN_LENGTH).ceil() as usize).clamp(2, 64),
+        };
+
+        for step in 1..=steps {
+            points.push(self.point(step as f32 / steps as f32));
+        }
+    }
+}
+
+/// A glyph outline made of closed contours, y increases upwards.
+#[derive(Debug, Clone, Default)]
+pub struct Outline {
+    contours: Vec<Vec<Segment>>,
+    current: Point,
+    start: Point,
+}
+
+impl Outline {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn move_to(&mut self, p: Point) {
+        self.close();
+        self.contours.push(Vec::new());
+        self.current = p;
+        self.start = p;
+    }
+
+    pub fn line_to(&mut self, p: Point) {
+        self.push(Segment::Line(self.current, p), p);
+    }
+
+    pub fn quad_to(&mut self, control: Point, p: Point) {
+        self.push(Segment::Quad(self.current, control, p), p);
+    }
+
+    pub fn cubic_to(&mut self, control1: Point, control2: Point, p: Point) {
+        self.push(Segment::Cubic(self.current, control1, control2, p), p);
+    }
+
+    /// Closes the current contour with a line back to its start if needed.
+    pub fn close(&mut self) {
+        if self.current != self.start {
+            self.push(Segment::Line(self.current, self.start), self.start);
+        }
+    }
+
+    fn push(&mut self, segment: Segment, end: Point) {
+        // note: drop degenerate segments, they have no direction to color or measure against
+        if self.current != end || !matches!(segment, Segment::Line(..)) {
+            match self.contours.last_mut() {
+                Some(contour) => contour.push(segment),
+                None => self.contours.push(vec![segment]),
+            }
+        }
+        self.current = end;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contours.iter().all(|contour| contour.is_empty())
+    }
+
+    /// Smallest and largest point of the control polygons.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self
+            .contours
+            .iter()
+            .flatten()
+            .flat_map(|segment| segment.control_points());
+        let first = points.next()?;
+
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+}
+
+/// A straight piece of a flattened edge.
+#[derive(Debug, Copy, Clone)]
+struct Piece {
+    a: Point,
+    b: Point,
+    color: u8,
+    /// the piece starts or ends a source edge, distances past those ends are measured perpendicular
+    first: bool,
+    last: bool,
+}
+
+impl Piece {
+    /// Returns the true distance to the piece and how orthogonal the direction to it is, lower is more orthogonal.
+    fn distance(&self, p: Point) -> (f32, f32) {
+        let ab = self.b - self.a;
+        let t = (dot(p - self.a, ab) / dot(ab, ab)).clamp(0.0, 1.0);
+        let to = p - (self.a + ab * t);
+        let distance = length(to);
+        let orthogonality = if distance > 0.0 {
+            (dot(normalize(ab), to) / distance).abs()
+        } else {
+            0.0
+        };
+        (distance, orthogonality)
+    }
+
+    /// Distance to the piece, extended past the ends of its edge.
+    fn pseudo_distance(&self, p: Point) -> f32 {
+        let ab = self.b - self.a;
+        let t = dot(p - self.a, ab) / dot(ab, ab);
+        if (t < 0.0 && self.first) || (t > 1.0 && self.last) {
+            (cross(ab, p - self.a) / length(ab)).abs()
+        } else {
+            self.distance(p).0
+        }
+    }
+
+    fn side(&self, p: Point) -> f32 {
+        cross(self.b - self.a, p - self.a)
+    }
+}
+
+fn is_corner(a: Point, b: Point) -> bool {
+    dot(a, b) <= 0.0 || cross(a, b).abs() > CORNER_THRESHOLD
+}
+
+/// Indices of the edges that start at a corner of the contour.
+fn find_corners(contour: &[Segment]) -> Vec<usize> {
+    let count = contour.len();
+    (0..count)
+        .filter(|&i| {
+            let previous = &contour[(i + count - 1) % count];
+            is_corner(previous.end_direction(), contour[i].start_direction())
+        })
+        .collect()
+}
+
+/// Assigns channels to the edges of a contour so no two edges meeting at a corner share all of them.
+fn color_contour(contour: &[Segment], corners: &[usize]) -> Vec<u8> {
+    let count = contour.len();
+    let mut colors = vec![WHITE; count];
+    if corners.len() < 2 {
+        // note: a single corner is split into thirds when flattening, see `flatten_contour`
+        return colors;
+    }
+
+    let mut spline = 0;
+    let mut color = COLORS[0];
+    for offset in 0..count {
+        let index = (corners[0] + offset) % count;
+        if spline + 1 < corners.len() && corners[spline + 1] == index {
+            spline += 1;
+            // note: the last run also meets the first one, it must differ from both neighbours
+            let last = spline + 1 == corners.len();
+            let previous = color;
+            color = COLORS
+                .iter()
+                .copied()
+                .find(|&c| c != previous && !(last && c == COLORS[0]))
+                .unwrap();
+        }
+        colors[index] = color;
+    }
+
+    colors
+}
+
+fn flatten_contour(contour: &[Segment], corners: &[usize], pieces: &mut Vec<Piece>) {
+    let start = pieces.len();
+    let colors = color_contour(contour, corners);
+
+    for (segment, &color) in contour.iter().zip(&colors) {
+        let mut points = vec![segment.point(0.0)];
+        segment.flatten(&mut points);
+
+        let count = points.len() - 1;
+        for (index, w) in points.windows(2).enumerate() {
+            if w[0] == w[1] {
+                continue;
+            }
+            pieces.push(Piece {
+                a: w[0],
+                b: w[1],
+                color,
+                first: index == 0,
+                last: index + 1 == count,
+            });
+        }
+    }
+
+    // note: a teardrop has a single corner, split its pieces into three runs starting at the corner
+    let contour_pieces = &mut pieces[start..];
+    let len = contour_pieces.len();
+    if corners.len() == 1 && len >= 3 {
+        let corner = contour[corners[0]].point(0.0);
+        let offset = contour_pieces
+            .iter()
+            .position(|piece| piece.a == corner)
+            .unwrap_or(0);
+
+        for i in 0..len {
+            contour_pieces[(offset + i) % len].color = match i * 3 / len {
+                0 => RED | BLUE,
+                1 => WHITE,
+                _ => RED | GREEN,
+            };
+        }
+    }
+}
+
+/// A generated distance field, rows are tightly packed from the top down.
+#[derive(Debug, Clone)]
+pub struct DistanceField {
+    pub width: u32,
+    pub height: u32,
+    /// 1 for an sdf, 4 for an msdf where alpha holds the true distance
+    pub channels: u32,
+    pub data: Vec<u8>,
+}
+
+/// Where the field is sampled, in the coordinate space of the outline.
+#[derive(Debug, Copy, Clone)]
+pub struct FieldFrame {
+    /// outline position of the top left corner of the field
+    pub origin: Point,
+    pub width: u32,
+    pub height: u32,
+    /// distance in outline units that maps to the edge of the encoded range
+    pub spread: f32,
+}
+
+impl FieldFrame {
+    /// A frame that covers `outline` with `spread` pixels of padding on every side.
+    pub fn covering(outline: &Outline, spread: f32) -> Option<Self> {
+        let (min, max) = outline.bounds()?;
+        let pad = spread.ceil();
+        let left = min.x.floor() - pad;
+        let top = max.y.ceil() + pad;
+
+        Some(Self {
+            origin: Point::new(left, top),
+            width: (max.x.ceil() + pad - left) as u32,
+            height: (top - (min.y.floor() - pad)) as u32,
+            spread,
+        })
+    }
+
+    fn sample_point(&self, x: u32, y: u32) -> Point {
+        Point::new(
+            self.origin.x + x as f32 + 0.5,
+            self.origin.y - y as f32 - 0.5,
+        )
+    }
+
+    fn encode(&self, distance: f32) -> u8 {
+        ((0.5 + distance / (2.0 * self.spread)).clamp(0.0, 1.0) * 255.0).round() as u8
+    }
+}
+
+struct Shape {
+    pieces: Vec<Piece>,
+    /// true if the filled side is to the left of the edges
+    inside_left: bool,
+}
+
+impl Shape {
+    fn new(outline: &Outline) -> Self {
+        let mut pieces = Vec::new();
+        for contour in outline.contours.iter().filter(|c| !c.is_empty()) {
+            flatten_contour(contour, &find_corners(contour), &mut pieces);
+        }
+
+        // note: truetype winds outer contours clockwise and cff counter clockwise, follow the shape
+        let area: f32 = pieces.iter().map(|piece| cross(piece.a, piece.b)).sum();
+
+        Self {
+            pieces,
+            inside_left: area > 0.0,
+        }
+    }
+
+    fn winding(&self, p: Point) -> i32 {
+        self.pieces
+            .iter()
+            .map(|piece| {
+                if piece.a.y <= p.y && piece.b.y > p.y && piece.side(p) > 0.0 {
+                    1
+                } else if piece.b.y <= p.y && piece.a.y > p.y && piece.side(p) < 0.0 {
+                    -1
+                } else {
+                    0
+                }
+            })
+            .sum()
+    }
+
+    /// Distance to the closest edge, positive inside the shape.
+    fn signed_distance(&self, p: Point) -> f32 {
+        let distance = self
+            .pieces
+            .iter()
+            .map(|piece| piece.distance(p).0)
+            .fold(f32::MAX, f32::min);
+
+        if self.winding(p) != 0 {
+            distance
+        } else {
+            -distance
+        }
+    }
+
+    /// Pseudo distances to the closest edge carrying each of the red, green and blue channels,
+    /// positive inside the shape. Channels without any edge are `None`.
+    fn channel_distances(&self, p: Point) -> [Option<f32>; 3] {
+        let mut closest: [Option<(&Piece, (f32, f32))>; 3] = [None; 3];
+
+        for piece in &self.pieces {
+            let distance = piece.distance(p);
+            for (channel, best) in [RED, GREEN, BLUE].iter().zip(closest.iter_mut()) {
+                if piece.color & channel == 0 {
+                    continue;
+                }
+
+                // note: ties happen at corners, prefer the edge the point is more orthogonal to
+                let closer = match best {
+                    Some((_, current)) if (distance.0 - current.0).abs() < 1e-4 => {
+                        distance.1 < current.1
+                    }
+                    Some((_, current)) => distance.0 < current.0,
+                    None => true,
+                };
+                if closer {
+                    *best = Some((piece, distance));
+                }
+            }
+        }
+
+        closest.map(|best| {
+            best.map(|(piece, _)| {
+                let distance = piece.pseudo_distance(p);
+                if (piece.side(p) > 0.0) == self.inside_left {
+                    distance
+                } else {
+                    -distance
+                }
+            })
+        })
+    }
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+    a.max(b).min(a.min(b).max(c))
+}
+
+/// Generates a single channel signed distance field of `outline`.
+pub fn generate_sdf(outline: &Outline, frame: FieldFrame) -> DistanceField {
+    let shape = Shape::new(outline);
+
+    let mut data = Vec::with_capacity((frame.width * frame.height) as usize);
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            data.push(frame.encode(shape.signed_distance(frame.sample_point(x, y))));
+        }
+    }
+
+    DistanceField {
+        width: frame.width,
+        height: frame.height,
+        channels: 1,
+        data,
+    }
+}
+
+/// Generates a multi-channel signed distance field of `outline`.
+/// The median of the color channels reconstructs the shape with sharp corners,
+/// alpha holds the true signed distance.
+pub fn generate_msdf(outline: &Outline, frame: FieldFrame) -> DistanceField {
+    let shape = Shape::new(outline);
+
+    let mut data = Vec::with_capacity((frame.width * frame.height * 4) as usize);
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            let p = frame.sample_point(x, y);
+            let true_distance = shape.signed_distance(p);
+
+            let mut channels = shape
+                .channel_distances(p)
+                .map(|distance| distance.unwrap_or(true_distance));
+
+            // note: where the channels disagree with the real inside / outside test they
+            // would produce artifacts, fall back to the plain distance for that texel
+            let inside = true_distance > 0.0;
+            if (median(channels[0], channels[1], channels[2]) > 0.0) != inside {
+                channels = [true_distance; 3];
+            }
+
+            data.extend(channels.iter().map(|&d| frame.encode(d)));
+            data.push(frame.encode(true_distance));
+        }
+    }
+
+    DistanceField {
+        width: frame.width,
+        height: frame.height,
+        channels: 4,
+        data,
+    }
+}
+
+/// Generates a distance field of the kind selected by `mode`.
+pub fn generate_distance_field(
+    outline: &Outline,
+    frame: FieldFrame,
+    mode: DistanceFieldMode,
+) -> DistanceField {
+    match mode {
+        DistanceFieldMode::Sdf => generate_sdf(outline, frame),
+        DistanceFieldMode::Msdf => generate_msdf(outline, frame),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = 16.0;
+    const SPREAD: f32 = 4.0;
+
+    /// A 16 by 16 square with its bottom left corner at the origin.
+    fn square() -> Outline {
+        let mut outline = Outline::new();
+        outline.move_to(Point::new(0.0, 0.0));
+        outline.line_to(Point::new(0.0, SIZE));
+        outline.line_to(Point::new(SIZE, SIZE));
+        outline.line_to(Point::new(SIZE, 0.0));
+        outline.close();
+        outline
+    }
+
+    /// A frame reaching 10 pixels past every side of the square, further than the spread.
+    fn frame() -> FieldFrame {
+        FieldFrame {
+            origin: Point::new(-10.0, SIZE + 10.0),
+            width: 36,
+            height: 36,
+            spread: SPREAD,
+        }
+    }
+
+    /// Value of `channel` at the texel whose center is at (x + 0.5, y + 0.5) in outline space.
+    fn texel(field: &DistanceField, x: i32, y: i32, channel: u32) -> u8 {
+        let frame = frame();
+        let column = (x as f32 - frame.origin.x) as u32;
+        let row = (frame.origin.y - y as f32 - 1.0) as u32;
+        field.data[((row * field.width + column) * field.channels + channel) as usize]
+    }
+
+    fn median_texel(field: &DistanceField, x: i32, y: i32) -> u8 {
+        let [r, g, b] = [0, 1, 2].map(|channel| texel(field, x, y, channel) as f32);
+        median(r, g, b) as u8
+    }
+
+    #[test]
+    fn sdf_is_positive_inside_and_negative_outside() {
+        let field = generate_sdf(&square(), frame());
+        assert_eq!(field.channels, 1);
+        assert!(texel(&field, 7, 7, 0) > 128);
+        assert!(texel(&field, 1, 14, 0) > 128);
+        assert!(texel(&field, -2, 7, 0) < 128);
+        assert!(texel(&field, 17, 17, 0) < 128);
+    }
+
+    #[test]
+    fn sdf_crosses_zero_at_the_edge() {
+        let field = generate_sdf(&square(), frame());
+
+        // note: the texels either side of the left edge are half a pixel away from it
+        let (outside, inside) = (texel(&field, -1, 7, 0), texel(&field, 0, 7, 0));
+        assert!(outside < 128 && inside > 128);
+        assert_eq!(outside as u32 + inside as u32, 255);
+        assert_eq!(inside, frame().encode(0.5));
+
+        let (inside, outside) = (texel(&field, 7, 15, 0), texel(&field, 7, 16, 0));
+        assert!(outside < 128 && inside > 128);
+        assert_eq!(outside as u32 + inside as u32, 255);
+    }
+
+    #[test]
+    fn sdf_clamps_at_spread() {
+        let field = generate_sdf(&square(), frame());
+        assert_eq!(texel(&field, -10, 7, 0), 0);
+        assert_eq!(texel(&field, -5, 7, 0), 0);
+        assert_eq!(texel(&field, 7, 7, 0), 255);
+        assert!(texel(&field, -3, 7, 0) > 0);
+        assert!(texel(&field, 3, 7, 0) < 255);
+    }
+
+    #[test]
+    fn msdf_median_matches_the_shape() {
+        let field = generate_msdf(&square(), frame());
+        assert_eq!(field.channels, 4);
+
+        assert!(median_texel(&field, 7, 7) > 128);
+        assert!(median_texel(&field, -2, 7) < 128);
+
+        let (outside, inside) = (median_texel(&field, -1, 7), median_texel(&field, 0, 7));
+        assert!(outside < 128 && inside > 128);
+        assert_eq!(outside as u32 + inside as u32, 255);
+
+        assert_eq!(median_texel(&field, -10, 7), 0);
+        assert_eq!(median_texel(&field, 7, 7), 255);
+    }
+
+    #[test]
+    fn msdf_keeps_corners_sharp() {
+        let field = generate_msdf(&square(), frame());
+
+        // note: just inside and outside the top right corner along the diagonal
+        assert!(median_texel(&field, 15, 15) > 128);
+        assert!(median_texel(&field, 16, 16) < 128);
+        // note: alpha holds the true distance, which is rounded at the corner
+        assert_eq!(texel(&field, 16, 16, 3), frame().encode(-0.5f32.hypot(0.5)));
+    }
+
+    #[test]
+    fn settings_can_not_change_after_use() {
+        distance_field_settings();
+        assert!(matches!(
+            set_distance_field_settings(DistanceFieldSettings::default()),
+            Err(FontError::AlreadyInitialized)
+        ));
+    }
+}
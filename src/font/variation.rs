@@ -0,0 +1,230 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use super::*;
+use freetype::ffi;
+
+/// Exact axis values for variable fonts, unset axes follow the rest of the `FontStyle`.
+/// Static faces are matched as if the values were rounded to the nearest weight / width.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FontVariation {
+    /// `wght` axis, 1 to 1000
+    pub weight: Option<f32>,
+    /// `wdth` axis, percent of the normal width
+    pub width: Option<f32>,
+    /// `slnt` axis, in degrees, negative values lean to the right
+    pub slant: Option<f32>,
+    /// `opsz` axis, the size in points the design is tuned for
+    pub optical_size: Option<f32>,
+}
+
+impl FontVariation {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn with_slant(mut self, slant: f32) -> Self {
+        self.slant = Some(slant);
+        self
+    }
+
+    pub fn with_optical_size(mut self, optical_size: f32) -> Self {
+        self.optical_size = Some(optical_size);
+        self
+    }
+}
+
+/// A design axis of a variable font, ex. `wght` from 100 to 900.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct VariationAxis {
+    pub tag: [u8; 4],
+    pub minimum: f32,
+    pub default: f32,
+    pub maximum: f32,
+}
+
+impl VariationAxis {
+    fn clamp(&self, value: f32) -> f32 {
+        value.clamp(self.minimum, self.maximum)
+    }
+}
+
+fn to_fixed(value: f32) -> ffi::FT_Fixed {
+    (value * 65536.0).round() as ffi::FT_Fixed
+}
+
+fn from_fixed(value: ffi::FT_Fixed) -> f32 {
+    value as f32 / 65536.0
+}
+
+/// Reads the design axes of a variable face, static faces have none.
+pub(crate) fn read_axes(face: &mut FontFace) -> Vec<VariationAxis> {
+    // note: the axis description is allocated from the library's memory
+    let library = lock_freetype_library();
+    let raw = face.raw_mut();
+    if !ffi::FT_HAS_MULTIPLE_MASTERS(raw) {
+        return Vec::new();
+    }
+
+    unsafe {
+        let mut master: *mut ffi::FT_MM_Var = std::ptr::null_mut();
+        if ffi::FT_Get_MM_Var(raw, &mut master) != 0 || master.is_null() {
+            return Vec::new();
+        }
+
+        let axes = std::slice::from_raw_parts((*master).axis, (*master).num_axis as usize)
+            .iter()
+            .map(|axis| VariationAxis {
+                tag: (axis.tag as u32).to_be_bytes(),
+                minimum: from_fixed(axis.minimum),
+                default: from_fixed(axis.def),
+                maximum: from_fixed(axis.maximum),
+            })
+            .collect();
+
+        ffi::FT_Done_MM_Var(library.raw(), master);
+        axes
+    }
+}
+
+/// Number of named instances, ex. "Bold" or "Condensed Light", defined by a variable face.
+pub(crate) fn named_instance_count(face: &ft::Face) -> u32 {
+    (face.raw().style_flags >> 16) as u32
+}
+
+/// Face index FreeType opens the named instance `instance` (1 based) of the face at `index` with.
+pub(crate) fn named_instance_index(index: u32, instance: u32) -> u32 {
+    (instance << 16) | index
+}
+
+/// Reads the current design coordinates of a face, one per axis.
+pub(crate) fn design_coordinates(face: &mut FontFace, axis_count: usize) -> Vec<f32> {
+    let _library = lock_freetype_library();
+    let mut coords: Vec<ffi::FT_Fixed> = vec![0; axis_count];
+    let error = unsafe {
+        ffi::FT_Get_Var_Design_Coordinates(
+            face.raw_mut(),
+            axis_count as ffi::FT_UInt,
+            coords.as_mut_ptr(),
+        )
+    };
+
+    if error != 0 {
+        return Vec::new();
+    }
+    coords.into_iter().map(from_fixed).collect()
+}
+
+/// Moves a variable face to `coords`, one value per axis.
+pub(crate) fn set_design_coordinates(face: &mut FontFace, coords: &[f32]) -> Result<(), ft::Error> {
+    let _library = lock_freetype_library();
+    let fixed: Vec<ffi::FT_Fixed> = coords.iter().copied().map(to_fixed).collect();
+    let error = unsafe {
+        ffi::FT_Set_Var_Design_Coordinates(
+            face.raw_mut(),
+            fixed.len() as ffi::FT_UInt,
+            fixed.as_ptr(),
+        )
+    };
+
+    match error {
+        0 => Ok(()),
+        _ => Err(error.into()),
+    }
+}
+
+/// Picks the coordinates on `axes` that best satisfy `style`.
+/// Axes the style says nothing about are left at their default.
+pub(crate) fn coordinates_for_style(axes: &[VariationAxis], style: &FontStyle) -> Vec<f32> {
+    axes.iter()
+        .map(|axis| {
+            let value = match &axis.tag {
+                b"wght" => style.weight_value(),
+                b"wdth" => style.width_value(),
+                b"slnt" => match (style.variation.slant, style.type_) {
+                    (Some(slant), _) => slant,
+                    (None, FontType::Normal) => 0.0,
+                    // note: slnt runs from upright (0) to the most slanted (minimum)
+                    (None, _) => axis.minimum,
+                },
+                b"ital" => match style.type_ {
+                    FontType::Italic => 1.0,
+                    _ => 0.0,
+                },
+                b"opsz" => style.variation.optical_size.unwrap_or(axis.default),
+                _ => axis.default,
+            };
+            axis.clamp(value)
+        })
+        .collect()
+}
+
+/// Style of a variable face at `coords`, `base` is the style read from the OS/2 table.
+pub(crate) fn style_at_coordinates(
+    base: FontStyle,
+    axes: &[VariationAxis],
+    coords: &[f32],
+) -> FontStyle {
+    let mut style = base;
+    for (axis, &value) in axes.iter().zip(coords) {
+        match &axis.tag {
+            b"wght" => {
+                style.weight = FontWeight::from(value.round() as FT_UShort);
+                style.variation.weight = Some(value);
+            }
+            b"wdth" => {
+                style.width = FontWidth::from_percent(value);
+                style.variation.width = Some(value);
+            }
+            b"slnt" => {
+                if value != 0.0 {
+                    if let FontType::Normal = style.type_ {
+                        style.type_ = FontType::Oblique;
+                    }
+                }
+                style.variation.slant = Some(value);
+            }
+            b"ital" if value >= 0.5 => style.type_ = FontType::Italic,
+            b"opsz" => style.variation.optical_size = Some(value),
+            _ => {}
+        }
+    }
+    style
+}
+
+/// Variations passed to the shaper so advances match the outlines.
+pub(crate) fn shaping_variations(
+    axes: &[VariationAxis],
+    coords: &[f32],
+) -> Vec<rustybuzz::Variation> {
+    axes.iter()
+        .zip(coords)
+        .map(|(axis, &value)| rustybuzz::Variation {
+            tag: rustybuzz::ttf_parser::Tag::from_bytes(&axis.tag),
+            value,
+        })
+        .collect()
+}
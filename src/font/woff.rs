@@ -0,0 +1,853 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use brotli_decompressor;
+use miniz_oxide;
+
+const WOFF_SIGNATURE: &[u8] = b"wOFF";
+const WOFF2_SIGNATURE: &[u8] = b"wOF2";
+
+const TAG_GLYF: u32 = u32::from_be_bytes(*b"glyf");
+const TAG_LOCA: u32 = u32::from_be_bytes(*b"loca");
+const TAG_HMTX: u32 = u32::from_be_bytes(*b"hmtx");
+const TAG_HHEA: u32 = u32::from_be_bytes(*b"hhea");
+const TAG_HEAD: u32 = u32::from_be_bytes(*b"head");
+const TAG_TTCF: u32 = u32::from_be_bytes(*b"ttcf");
+
+/// Tags a WOFF2 table directory can refer to by index.
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Returns true if `data` starts with a WOFF or WOFF2 signature.
+pub(crate) fn is_woff(data: &[u8]) -> bool {
+    data.starts_with(WOFF_SIGNATURE) || data.starts_with(WOFF2_SIGNATURE)
+}
+
+/// Unpacks a WOFF or WOFF2 container into a regular TrueType / OpenType font.
+/// WOFF2 collections are unpacked into a font collection (.ttc).
+pub(crate) fn decode_woff(data: &[u8]) -> Result<Vec<u8>, String> {
+    if data.starts_with(WOFF_SIGNATURE) {
+        decode_woff1(data)
+    } else if data.starts_with(WOFF2_SIGNATURE) {
+        decode_woff2(data)
+    } else {
+        Err("not a woff file".into())
+    }
+}
+
+/// Big endian reader over a byte slice.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or("unexpected end of font data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn i16(&mut self) -> Result<i16, String> {
+        Ok(self.u16()? as i16)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Variable length `UIntBase128` used by the WOFF2 table directory.
+    fn base128(&mut self) -> Result<u32, String> {
+        let mut value: u32 = 0;
+        for index in 0..5 {
+            let byte = self.u8()?;
+            if index == 0 && byte == 0x80 {
+                return Err("invalid base128 value".into());
+            }
+            if value & 0xFE00_0000 != 0 {
+                return Err("base128 value overflows".into());
+            }
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("base128 value is too long".into())
+    }
+
+    /// Variable length `255UInt16` used by the WOFF2 glyf transform.
+    fn u255(&mut self) -> Result<u16, String> {
+        match self.u8()? {
+            253 => self.u16(),
+            255 => Ok(self.u8()? as u16 + 253),
+            254 => Ok(self.u8()? as u16 + 253 * 2),
+            code => Ok(code as u16),
+        }
+    }
+}
+
+fn table_checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// A font of an sfnt file, a collection holds several that can share tables.
+#[derive(Debug)]
+struct SfntFont {
+    flavor: u32,
+    /// indices of the font's tables
+    tables: Vec<usize>,
+}
+
+/// Writes an sfnt file with the tables sorted by tag and padded to four bytes.
+fn build_sfnt(flavor: u32, mut tables: Vec<(u32, Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+    let font = SfntFont {
+        flavor,
+        tables: (0..tables.len()).collect(),
+    };
+    build_fonts(&[font], &tables, false)
+}
+
+/// Writes the offset tables of `fonts` followed by every table once, padded to four bytes.
+/// A collection starts with a `ttcf` header pointing at each font's offset table.
+fn build_fonts(fonts: &[SfntFont], tables: &[(u32, Vec<u8>)], collection: bool) -> Vec<u8> {
+    let header_length = if collection { 12 + 4 * fonts.len() } else { 0 };
+    let directory_lengths: Vec<usize> = fonts
+        .iter()
+        .map(|font| 12 + 16 * font.tables.len())
+        .collect();
+
+    let mut offsets = Vec::with_capacity(tables.len());
+    let mut offset = header_length + directory_lengths.iter().sum::<usize>();
+    for (_, data) in tables {
+        offsets.push(offset);
+        offset += (data.len() + 3) & !3;
+    }
+
+    let mut output = Vec::with_capacity(offset);
+    if collection {
+        // note: version 1 has no DSIG fields, a signature would not survive the round trip anyway
+        output.extend_from_slice(&TAG_TTCF.to_be_bytes());
+        output.extend_from_slice(&0x0001_0000u32.to_be_bytes());
+        output.extend_from_slice(&(fonts.len() as u32).to_be_bytes());
+        let mut directory = header_length;
+        for length in &directory_lengths {
+            output.extend_from_slice(&(directory as u32).to_be_bytes());
+            directory += length;
+        }
+    }
+
+    for font in fonts {
+        let mut indices = font.tables.clone();
+        indices.sort_by_key(|&index| tables[index].0);
+
+        let count = indices.len() as u16;
+        let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+        let search_range = (1u16 << entry_selector) * 16;
+
+        output.extend_from_slice(&font.flavor.to_be_bytes());
+        output.extend_from_slice(&count.to_be_bytes());
+        output.extend_from_slice(&search_range.to_be_bytes());
+        output.extend_from_slice(&entry_selector.to_be_bytes());
+        output.extend_from_slice(&(count * 16 - search_range).to_be_bytes());
+
+        for index in indices {
+            let (tag, data) = &tables[index];
+            output.extend_from_slice(&tag.to_be_bytes());
+            output.extend_from_slice(&table_checksum(data).to_be_bytes());
+            output.extend_from_slice(&(offsets[index] as u32).to_be_bytes());
+            output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        }
+    }
+
+    for (_, data) in tables {
+        output.extend_from_slice(data);
+        output.resize((output.len() + 3) & !3, 0);
+    }
+
+    output
+}
+
+fn decode_woff1(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.bytes(4)?;
+    let flavor = header.u32()?;
+    header.u32()?;
+    let num_tables = header.u16()?;
+    // note: skip the reserved field, the total size, version and metadata / private block offsets
+    header.bytes(2 + 4 + 4 + 4 * 5)?;
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let tag = header.u32()?;
+        let offset = header.u32()? as usize;
+        let compressed_length = header.u32()? as usize;
+        let length = header.u32()? as usize;
+        header.u32()?;
+
+        let compressed = Reader { data, pos: offset }.bytes(compressed_length)?;
+
+        let table = if compressed_length < length {
+            miniz_oxide::inflate::decompress_to_vec_zlib(compressed)
+                .map_err(|err| format!("failed to inflate table: {:?}", err))?
+        } else {
+            compressed.to_vec()
+        };
+        if table.len() != length {
+            return Err("table length does not match the directory".into());
+        }
+
+        tables.push((tag, table));
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+#[derive(Debug)]
+struct Woff2Table {
+    tag: u32,
+    length: usize,
+    transformed: bool,
+    /// length of the table in the decompressed stream
+    stream_length: usize,
+}
+
+fn decode_woff2(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut header = Reader::new(data);
+    header.bytes(4)?;
+    let flavor = header.u32()?;
+    header.u32()?;
+    let num_tables = header.u16()?;
+    header.u16()?;
+    header.u32()?;
+    let compressed_length = header.u32()? as usize;
+    // note: skip the version and metadata / private block offsets
+    header.bytes(2 * 2 + 4 * 5)?;
+
+    let mut directory = Vec::with_capacity(num_tables as usize);
+    for _ in 0..num_tables {
+        let flags = header.u8()?;
+        let tag = match (flags & 0x3F) as usize {
+            63 => header.u32()?,
+            index => u32::from_be_bytes(*KNOWN_TAGS[index]),
+        };
+        let version = flags >> 6;
+        let length = header.base128()? as usize;
+
+        // note: glyf and loca are transformed by default, version 3 is their null transform
+        let transformed = if tag == TAG_GLYF || tag == TAG_LOCA {
+            version == 0
+        } else {
+            version != 0
+        };
+        let stream_length = if transformed {
+            header.base128()? as usize
+        } else {
+            length
+        };
+
+        directory.push(Woff2Table {
+            tag,
+            length,
+            transformed,
+            stream_length,
+        });
+    }
+
+    let fonts = if flavor == TAG_TTCF {
+        read_collection_directory(&mut header, directory.len())?
+    } else {
+        vec![SfntFont {
+            flavor,
+            tables: (0..directory.len()).collect(),
+        }]
+    };
+
+    let mut stream = Vec::new();
+    let mut compressed = header.bytes(compressed_length)?;
+    brotli_decompressor::BrotliDecompress(&mut compressed, &mut stream)
+        .map_err(|err| format!("failed to decompress font data: {}", err))?;
+
+    let mut offset = 0;
+    let mut raw: Vec<&[u8]> = Vec::with_capacity(directory.len());
+    for table in &directory {
+        let bytes = Reader {
+            data: &stream,
+            pos: offset,
+        }
+        .bytes(table.stream_length)?;
+        offset += table.stream_length;
+        raw.push(bytes);
+    }
+
+    // note: tables shared by the fonts of a collection are rebuilt once
+    let mut tables: Vec<Option<Vec<u8>>> = vec![None; directory.len()];
+    let mut x_mins: Vec<Option<Vec<i16>>> = vec![None; directory.len()];
+
+    for font in &fonts {
+        let find = |tag: u32| {
+            font.tables
+                .iter()
+                .copied()
+                .find(|&index| directory[index].tag == tag)
+        };
+
+        let glyf = find(TAG_GLYF).filter(|&index| directory[index].transformed);
+        if let Some(glyf) = glyf.filter(|&index| tables[index].is_none()) {
+            let loca = find(TAG_LOCA).ok_or("transformed glyf table has no loca table")?;
+            let glyphs = reconstruct_glyf(raw[glyf])?;
+            if glyphs.loca.len() != directory[loca].length {
+                return Err("reconstructed loca table has the wrong length".into());
+            }
+
+            tables[glyf] = Some(glyphs.glyf);
+            tables[loca] = Some(glyphs.loca);
+            x_mins[glyf] = Some(glyphs.x_mins);
+        }
+
+        for &index in &font.tables {
+            if tables[index].is_some() {
+                continue;
+            }
+
+            let table = &directory[index];
+            let table_data = if !table.transformed {
+                raw[index].to_vec()
+            } else if table.tag == TAG_HMTX {
+                let hhea = find(TAG_HHEA).ok_or("font has no hhea table")?;
+                let mins = glyf
+                    .and_then(|glyf| x_mins[glyf].as_ref())
+                    .ok_or("transformed hmtx requires a transformed glyf table")?;
+                reconstruct_hmtx(raw[index], raw[hhea], mins)?
+            } else {
+                return Err("unsupported woff2 table transform".into());
+            };
+
+            tables[index] = Some(table_data);
+        }
+    }
+
+    let mut tables: Vec<(u32, Vec<u8>)> = directory
+        .iter()
+        .zip(tables)
+        .map(|(table, data)| (table.tag, data.unwrap_or_default()))
+        .collect();
+
+    // note: the font checksum is not verified by any reader we use, clear it like the encoder does
+    for (_, head) in tables.iter_mut().filter(|(tag, _)| *tag == TAG_HEAD) {
+        if head.len() >= 12 {
+            head[8..12].fill(0);
+        }
+    }
+
+    if flavor == TAG_TTCF {
+        Ok(build_fonts(&fonts, &tables, true))
+    } else {
+        Ok(build_sfnt(flavor, tables))
+    }
+}
+
+/// Reads the collection directory that follows the table directory of a WOFF2 collection.
+fn read_collection_directory(
+    header: &mut Reader,
+    table_count: usize,
+) -> Result<Vec<SfntFont>, String> {
+    // note: versions 1 and 2 only differ in the DSIG fields of the unpacked header
+    header.u32()?;
+    let font_count = header.u255()?;
+
+    let mut fonts = Vec::with_capacity(font_count as usize);
+    for _ in 0..font_count {
+        let count = header.u255()?;
+        let flavor = header.u32()?;
+
+        let mut tables = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let index = header.u255()? as usize;
+            if index >= table_count {
+                return Err("collection font refers to a missing table".into());
+            }
+            tables.push(index);
+        }
+
+        fonts.push(SfntFont { flavor, tables });
+    }
+
+    Ok(fonts)
+}
+
+fn with_sign(flag: u8, value: i32) -> i32 {
+    if flag & 1 != 0 {
+        value
+    } else {
+        -value
+    }
+}
+
+/// Decodes the delta coordinates of a simple glyph's points.
+fn decode_triplets(
+    flags: &mut Reader,
+    glyphs: &mut Reader,
+    count: usize,
+) -> Result<Vec<(i32, i32, bool)>, String> {
+    let mut points = Vec::with_capacity(count);
+    let (mut x, mut y) = (0i32, 0i32);
+
+    for _ in 0..count {
+        let flag = flags.u8()?;
+        let on_curve = flag >> 7 == 0;
+        let flag = flag & 0x7F;
+
+        let (dx, dy) = if flag < 10 {
+            let b0 = glyphs.u8()? as i32;
+            (0, with_sign(flag, (((flag & 14) as i32) << 7) + b0))
+        } else if flag < 20 {
+            let b0 = glyphs.u8()? as i32;
+            (with_sign(flag, ((((flag - 10) & 14) as i32) << 7) + b0), 0)
+        } else if flag < 84 {
+            let b0 = (flag - 20) as i32;
+            let b1 = glyphs.u8()? as i32;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+            )
+        } else if flag < 120 {
+            let b0 = (flag - 84) as i32;
+            let b = glyphs.bytes(2)?;
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + b[0] as i32),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b[1] as i32),
+            )
+        } else if flag < 124 {
+            let b = glyphs.bytes(3)?;
+            (
+                with_sign(flag, ((b[0] as i32) << 4) + (b[1] as i32 >> 4)),
+                with_sign(flag >> 1, (((b[1] & 0x0F) as i32) << 8) + b[2] as i32),
+            )
+        } else {
+            let b = glyphs.bytes(4)?;
+            (
+                with_sign(flag, ((b[0] as i32) << 8) + b[1] as i32),
+                with_sign(flag >> 1, ((b[2] as i32) << 8) + b[3] as i32),
+            )
+        };
+
+        x += dx;
+        y += dy;
+        points.push((x, y, on_curve));
+    }
+
+    Ok(points)
+}
+
+/// Tables rebuilt from the WOFF2 glyf transform.
+struct Glyphs {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    /// xMin of every glyph, needed to rebuild a transformed hmtx table
+    x_mins: Vec<i16>,
+}
+
+/// Rebuilds the glyf and loca tables from the WOFF2 glyf transform.
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyphs, String> {
+    let mut header = Reader::new(data);
+    header.u16()?;
+    let options = header.u16()?;
+    let num_glyphs = header.u16()? as usize;
+    let index_format = header.u16()?;
+
+    let mut sizes = [0usize; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+
+    let mut streams: Vec<Reader> = Vec::with_capacity(7);
+    for &size in &sizes {
+        streams.push(Reader::new(header.bytes(size)?));
+    }
+    let overlap_bitmap = if options & 1 != 0 {
+        Some(header.bytes(num_glyphs.div_ceil(8))?)
+    } else {
+        None
+    };
+
+    let mut streams = streams.into_iter();
+    let mut contours = streams.next().unwrap();
+    let mut point_counts = streams.next().unwrap();
+    let mut flags = streams.next().unwrap();
+    let mut glyphs = streams.next().unwrap();
+    let mut composites = streams.next().unwrap();
+    let mut bboxes = streams.next().unwrap();
+    let mut instructions = streams.next().unwrap();
+
+    let bbox_bitmap = bboxes.bytes(((num_glyphs + 31) >> 5) << 2)?;
+    let has_bit = |bitmap: &[u8], index: usize| bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(num_glyphs + 1);
+    let mut x_mins = Vec::with_capacity(num_glyphs);
+
+    for index in 0..num_glyphs {
+        offsets.push(glyf.len());
+        let contour_count = contours.i16()?;
+        let has_bbox = has_bit(bbox_bitmap, index);
+
+        let mut bbox = [0i16; 4];
+        if has_bbox {
+            for value in &mut bbox {
+                *value = bboxes.i16()?;
+            }
+        }
+
+        if contour_count == 0 {
+            if has_bbox {
+                return Err("empty glyph has a bounding box".into());
+            }
+            x_mins.push(0);
+            continue;
+        }
+
+        if contour_count < 0 {
+            if !has_bbox {
+                return Err("composite glyph has no bounding box".into());
+            }
+
+            // note: copy components until one no longer sets MORE_COMPONENTS
+            let start = composites.pos;
+            let mut has_instructions = false;
+            loop {
+                let component_flags = composites.u16()?;
+                composites.u16()?;
+                let args = if component_flags & 0x0001 != 0 { 4 } else { 2 };
+                let scale = if component_flags & 0x0008 != 0 {
+                    2
+                } else if component_flags & 0x0040 != 0 {
+                    4
+                } else if component_flags & 0x0080 != 0 {
+                    8
+                } else {
+                    0
+                };
+                composites.bytes(args + scale)?;
+                has_instructions |= component_flags & 0x0100 != 0;
+                if component_flags & 0x0020 == 0 {
+                    break;
+                }
+            }
+            let components = &composites.data[start..composites.pos];
+
+            glyf.extend_from_slice(&(-1i16).to_be_bytes());
+            bbox.iter()
+                .for_each(|value| glyf.extend_from_slice(&value.to_be_bytes()));
+            glyf.extend_from_slice(components);
+            if has_instructions {
+                let length = glyphs.u255()?;
+                glyf.extend_from_slice(&length.to_be_bytes());
+                glyf.extend_from_slice(instructions.bytes(length as usize)?);
+            }
+        } else {
+            let mut end_points = Vec::with_capacity(contour_count as usize);
+            let mut total = 0usize;
+            for _ in 0..contour_count {
+                total += point_counts.u255()? as usize;
+                end_points.push(total as u16 - 1);
+            }
+
+            let points = decode_triplets(&mut flags, &mut glyphs, total)?;
+            let instruction_length = glyphs.u255()?;
+
+            if !has_bbox {
+                let xs = points.iter().map(|p| p.0);
+                let ys = points.iter().map(|p| p.1);
+                bbox = [
+                    xs.clone().min().unwrap_or(0) as i16,
+                    ys.clone().min().unwrap_or(0) as i16,
+                    xs.max().unwrap_or(0) as i16,
+                    ys.max().unwrap_or(0) as i16,
+                ];
+            }
+
+            glyf.extend_from_slice(&contour_count.to_be_bytes());
+            bbox.iter()
+                .for_each(|value| glyf.extend_from_slice(&value.to_be_bytes()));
+            end_points
+                .iter()
+                .for_each(|value| glyf.extend_from_slice(&value.to_be_bytes()));
+            glyf.extend_from_slice(&instruction_length.to_be_bytes());
+            glyf.extend_from_slice(instructions.bytes(instruction_length as usize)?);
+
+            // note: write every point with its own flag and 16 bit deltas, simple and always valid
+            let overlap = overlap_bitmap.is_some_and(|bitmap| has_bit(bitmap, index));
+            for (point, &(_, _, on_curve)) in points.iter().enumerate() {
+                let mut flag = on_curve as u8;
+                if point == 0 && overlap {
+                    flag |= 0x40;
+                }
+                glyf.push(flag);
+            }
+            let mut previous = (0, 0);
+            for &(x, _, _) in &points {
+                glyf.extend_from_slice(&((x - previous.0) as i16).to_be_bytes());
+                previous.0 = x;
+            }
+            for &(_, y, _) in &points {
+                glyf.extend_from_slice(&((y - previous.1) as i16).to_be_bytes());
+                previous.1 = y;
+            }
+        }
+
+        x_mins.push(bbox[0]);
+        glyf.resize((glyf.len() + 3) & !3, 0);
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::with_capacity(offsets.len() * 4);
+    for offset in offsets {
+        if index_format == 0 {
+            loca.extend_from_slice(&((offset / 2) as u16).to_be_bytes());
+        } else {
+            loca.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+    }
+
+    Ok(Glyphs { glyf, loca, x_mins })
+}
+
+/// Rebuilds an hmtx table whose left side bearings were dropped in favour of the glyph xMin.
+fn reconstruct_hmtx(data: &[u8], hhea: &[u8], x_mins: &[i16]) -> Result<Vec<u8>, String> {
+    let num_metrics = Reader {
+        data: hhea,
+        pos: 34,
+    }
+    .u16()? as usize;
+    let num_glyphs = x_mins.len();
+    if num_metrics == 0 || num_metrics > num_glyphs {
+        return Err("invalid number of horizontal metrics".into());
+    }
+
+    let mut reader = Reader::new(data);
+    let flags = reader.u8()?;
+
+    let mut advances = Vec::with_capacity(num_metrics);
+    for _ in 0..num_metrics {
+        advances.push(reader.u16()?);
+    }
+
+    let mut bearings = Vec::with_capacity(num_glyphs);
+    for (index, &x_min) in x_mins.iter().enumerate() {
+        let stored = if index < num_metrics {
+            flags & 1 == 0
+        } else {
+            flags & 2 == 0
+        };
+        bearings.push(if stored { reader.i16()? } else { x_min });
+    }
+
+    let mut hmtx = Vec::with_capacity(num_metrics * 4 + (num_glyphs - num_metrics) * 2);
+    for (index, bearing) in bearings.iter().enumerate() {
+        if index < num_metrics {
+            hmtx.extend_from_slice(&advances[index].to_be_bytes());
+        }
+        hmtx.extend_from_slice(&bearing.to_be_bytes());
+    }
+
+    Ok(hmtx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustybuzz::ttf_parser;
+
+    // note: tests/fonts/make_woff_fixtures.py builds the woff fixtures from demo.ttf
+    const DEMO_TTF: &[u8] = include_bytes!("../../tests/fonts/demo.ttf");
+    const DEMO_WOFF: &[u8] = include_bytes!("../../tests/fonts/demo.woff");
+    const DEMO_WOFF2: &[u8] = include_bytes!("../../tests/fonts/demo.woff2");
+    const DEMO_COLLECTION_WOFF2: &[u8] = include_bytes!("../../tests/fonts/demo-collection.woff2");
+
+    /// Tables of the font whose offset table starts at `offset`, by tag.
+    fn read_tables(font: &[u8], offset: usize) -> Vec<(u32, &[u8])> {
+        let mut reader = Reader {
+            data: font,
+            pos: offset + 4,
+        };
+        let count = reader.u16().unwrap();
+        reader.bytes(6).unwrap();
+
+        (0..count)
+            .map(|_| {
+                let tag = reader.u32().unwrap();
+                reader.u32().unwrap();
+                let start = reader.u32().unwrap() as usize;
+                let length = reader.u32().unwrap() as usize;
+                (tag, &font[start..start + length])
+            })
+            .collect()
+    }
+
+    #[derive(Default)]
+    struct Path(Vec<(char, f32, f32)>);
+
+    impl ttf_parser::OutlineBuilder for Path {
+        fn move_to(&mut self, x: f32, y: f32) {
+            self.0.push(('M', x, y));
+        }
+
+        fn line_to(&mut self, x: f32, y: f32) {
+            self.0.push(('L', x, y));
+        }
+
+        fn quad_to(&mut self, _: f32, _: f32, x: f32, y: f32) {
+            self.0.push(('Q', x, y));
+        }
+
+        fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, x: f32, y: f32) {
+            self.0.push(('C', x, y));
+        }
+
+        fn close(&mut self) {
+            self.0.push(('Z', 0.0, 0.0));
+        }
+    }
+
+    /// Checks the face at `index` of `decoded` matches demo.ttf.
+    /// The glyf and loca tables are rebuilt rather than copied, their glyphs are compared instead.
+    fn assert_same_face(decoded: &[u8], index: u32) {
+        let offset = if decoded.starts_with(b"ttcf") {
+            Reader {
+                data: decoded,
+                pos: 12 + 4 * index as usize,
+            }
+            .u32()
+            .unwrap() as usize
+        } else {
+            0
+        };
+
+        let expected = read_tables(DEMO_TTF, 0);
+        let actual = read_tables(decoded, offset);
+        let tags = |tables: &[(u32, &[u8])]| tables.iter().map(|t| t.0).collect::<Vec<_>>();
+        assert_eq!(tags(&actual), tags(&expected));
+
+        for ((tag, actual), (_, expected)) in actual.iter().zip(&expected) {
+            match *tag {
+                TAG_GLYF | TAG_LOCA => {}
+                TAG_HEAD => {
+                    assert_eq!(actual[..8], expected[..8]);
+                    assert_eq!(actual[12..], expected[12..]);
+                }
+                _ => assert_eq!(actual, expected, "table {:?}", tag.to_be_bytes()),
+            }
+        }
+
+        let expected = ttf_parser::Face::parse(DEMO_TTF, 0).unwrap();
+        let actual = ttf_parser::Face::parse(decoded, index).unwrap();
+        assert_eq!(actual.number_of_glyphs(), expected.number_of_glyphs());
+        for glyph in 0..expected.number_of_glyphs() {
+            let glyph = ttf_parser::GlyphId(glyph);
+            let (mut actual_path, mut expected_path) = (Path::default(), Path::default());
+            let actual_bbox = actual.outline_glyph(glyph, &mut actual_path);
+            let expected_bbox = expected.outline_glyph(glyph, &mut expected_path);
+            assert_eq!(actual_bbox, expected_bbox);
+            assert_eq!(actual_path.0, expected_path.0);
+            assert_eq!(
+                actual.glyph_hor_side_bearing(glyph),
+                expected.glyph_hor_side_bearing(glyph)
+            );
+        }
+    }
+
+    #[test]
+    fn detects_containers() {
+        assert!(is_woff(DEMO_WOFF));
+        assert!(is_woff(DEMO_WOFF2));
+        assert!(!is_woff(DEMO_TTF));
+        assert!(decode_woff(DEMO_TTF).is_err());
+    }
+
+    #[test]
+    fn decodes_woff() {
+        let decoded = decode_woff(DEMO_WOFF).unwrap();
+        assert_same_face(&decoded, 0);
+    }
+
+    #[test]
+    fn decodes_transformed_woff2() {
+        let decoded = decode_woff(DEMO_WOFF2).unwrap();
+        assert_eq!(decoded[..4], DEMO_TTF[..4]);
+        assert_same_face(&decoded, 0);
+    }
+
+    #[test]
+    fn decodes_woff2_collection() {
+        let decoded = decode_woff(DEMO_COLLECTION_WOFF2).unwrap();
+        assert!(decoded.starts_with(b"ttcf"));
+        assert_eq!(ttf_parser::fonts_in_collection(&decoded), Some(2));
+        assert_same_face(&decoded, 0);
+        assert_same_face(&decoded, 1);
+
+        // note: both fonts point at the same copy of their shared tables
+        let offset = |index: usize| {
+            Reader {
+                data: &decoded,
+                pos: 12 + 4 * index,
+            }
+            .u32()
+            .unwrap() as usize
+        };
+        let first = read_tables(&decoded, offset(0));
+        let second = read_tables(&decoded, offset(1));
+        for (a, b) in first.iter().zip(&second) {
+            assert_eq!(a.1.as_ptr(), b.1.as_ptr());
+        }
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        assert!(decode_woff(&DEMO_WOFF[..DEMO_WOFF.len() / 2]).is_err());
+        assert!(decode_woff(&DEMO_WOFF2[..DEMO_WOFF2.len() / 2]).is_err());
+    }
+}
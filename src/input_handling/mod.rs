@@ -23,15 +23,29 @@ type KeyState = winit::event::ElementState;
 type MouseButton = winit::event::MouseButton;
 type ButtonState = winit::event::ElementState;
 
+pub type Modifiers = winit::keyboard::ModifiersState;
+
 #[derive(Debug)]
 pub struct KeyEvent {
     key: Key,
     state: KeyState,
+    modifiers: Modifiers,
+    text: Option<String>,
 }
 
 impl KeyEvent {
-    pub(crate) fn new(key: Key, state: KeyState) -> Self {
-        Self { key, state }
+    pub(crate) fn new(
+        key: Key,
+        state: KeyState,
+        modifiers: Modifiers,
+        text: Option<String>,
+    ) -> Self {
+        Self {
+            key,
+            state,
+            modifiers,
+            text,
+        }
     }
 
     pub fn key(&self) -> Key {
@@ -41,17 +55,64 @@ impl KeyEvent {
     pub fn state(&self) -> KeyState {
         self.state
     }
+
+    /// Modifier keys held when the key was pressed or released.
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Text typed by a key press with the current keyboard layout, ex. "A" while shift is held.
+    pub fn text(&self) -> Option<&str> {
+        self.text.as_deref()
+    }
+
+    /// True if the platform's shortcut modifier is held, command on macOS and control elsewhere.
+    pub fn shortcut(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.modifiers.super_key()
+        } else {
+            self.modifiers.control_key()
+        }
+    }
+
+    /// True if the modifier that moves the caret by words is held, option on macOS and control elsewhere.
+    pub fn word_modifier(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.modifiers.alt_key()
+        } else {
+            self.modifiers.control_key()
+        }
+    }
+
+    /// True for a press of `code` with the shortcut modifier, ex. `KeyCode::KeyC` for copy.
+    pub fn is_shortcut(&self, code: winit::keyboard::KeyCode) -> bool {
+        self.state == KeyState::Pressed
+            && self.shortcut()
+            && self.key == winit::keyboard::PhysicalKey::Code(code)
+    }
 }
 
 #[derive(Debug)]
 pub struct MouseButtonEvent {
     button: MouseButton,
     state: ButtonState,
+    pos: Point,
+    click_count: u32,
 }
 
 impl MouseButtonEvent {
-    pub(crate) fn new(button: MouseButton, state: ButtonState) -> Self {
-        Self { button, state }
+    pub(crate) fn new(
+        button: MouseButton,
+        state: ButtonState,
+        pos: Point,
+        click_count: u32,
+    ) -> Self {
+        Self {
+            button,
+            state,
+            pos,
+            click_count,
+        }
     }
 
     pub fn button(&self) -> MouseButton {
@@ -61,6 +122,16 @@ impl MouseButtonEvent {
     pub fn state(&self) -> ButtonState {
         self.state
     }
+
+    /// Position of the cursor in the window.
+    pub fn pos(&self) -> Point {
+        self.pos
+    }
+
+    /// 1 for a single click, 2 for a double click and so on, counted on presses.
+    pub fn click_count(&self) -> u32 {
+        self.click_count
+    }
 }
 
 #[derive(Debug)]
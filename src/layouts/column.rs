@@ -42,14 +42,14 @@ impl Column {
 }
 
 impl Layout for Column {
-    fn measure(&mut self, available_size: Size) -> Size {
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size {
         let available = self.sizing.calc_available_size(available_size);
         let required = {
             let mut remaining_size = available_size;
             let mut max_width = 0.0f32;
 
             for child in &mut self.children {
-                let child_size = child.component.measure(remaining_size);
+                let child_size = child.component.measure(remaining_size, scale_factor);
                 child.final_size.set(child_size);
                 remaining_size.height -= child_size.height;
                 max_width = max_width.max(child_size.width);
@@ -61,11 +61,13 @@ impl Layout for Column {
         self.sizing.calc_final_size(available, required)
     }
 
-    fn arrange(&mut self, final_rect: Rect) -> Rect {
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
         let final_rect = match self.horiz_align {
-            HorizontalAlignment::Left => self.arrange_left(final_rect),
-            HorizontalAlignment::Center => self.arrange_center(final_rect),
-            HorizontalAlignment::Right => self.arrange_right(final_rect),
+            HorizontalAlignment::Left | HorizontalAlignment::Justify => {
+                self.arrange_left(final_rect, scale_factor)
+            }
+            HorizontalAlignment::Center => self.arrange_center(final_rect, scale_factor),
+            HorizontalAlignment::Right => self.arrange_right(final_rect, scale_factor),
         };
         self.final_rect.set(final_rect);
         final_rect
@@ -74,30 +76,38 @@ impl Layout for Column {
 
 impl Column {
     #[inline(always)]
-    fn arrange_generic(&mut self, final_rect: Rect, func: impl Fn(Rect, Size) -> Point) -> Rect {
+    fn arrange_generic(
+        &mut self,
+        final_rect: Rect,
+        scale_factor: f32,
+        func: impl Fn(Rect, Size) -> Point,
+    ) -> Rect {
         let mut y = final_rect.pos.y;
         for child in &mut self.children {
             let child_size = child.final_size.get();
-            let child_rect = child.component.arrange(Rect::new(
-                func(
-                    Rect::new(Point::new(final_rect.pos.x, y), final_rect.size),
+            let child_rect = child.component.arrange(
+                Rect::new(
+                    func(
+                        Rect::new(Point::new(final_rect.pos.x, y), final_rect.size),
+                        child_size,
+                    ),
                     child_size,
                 ),
-                child_size,
-            ));
+                scale_factor,
+            );
             y += child_rect.size.height;
         }
         final_rect
     }
 
     #[inline(always)]
-    fn arrange_left(&mut self, final_rect: Rect) -> Rect {
-        self.arrange_generic(final_rect, |rect, _child_size| rect.pos)
+    fn arrange_left(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
+        self.arrange_generic(final_rect, scale_factor, |rect, _child_size| rect.pos)
     }
 
     #[inline(always)]
-    fn arrange_center(&mut self, final_rect: Rect) -> Rect {
-        self.arrange_generic(final_rect, |rect, child_size| {
+    fn arrange_center(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
+        self.arrange_generic(final_rect, scale_factor, |rect, child_size| {
             Point::new(
                 rect.pos.x + (rect.size.width - child_size.width) / 2.0,
                 rect.pos.y,
@@ -106,8 +116,8 @@ impl Column {
     }
 
     #[inline(always)]
-    fn arrange_right(&mut self, final_rect: Rect) -> Rect {
-        self.arrange_generic(final_rect, |rect, child_size| {
+    fn arrange_right(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
+        self.arrange_generic(final_rect, scale_factor, |rect, child_size| {
             Point::new(rect.pos.x + rect.size.width - child_size.width, rect.pos.y)
         })
     }
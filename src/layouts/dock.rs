@@ -114,7 +114,7 @@ impl Dock {
 }
 
 impl Layout for Dock {
-    fn measure(&mut self, available_size: Size) -> Size {
+    fn measure(&mut self, available_size: Size, scale_factor: f32) -> Size {
         let available = self.sizing.calc_available_size(available_size);
         let required = {
             let mut remaining = available;
@@ -123,7 +123,7 @@ impl Layout for Dock {
                 .top
                 .as_mut()
                 .map(|c| {
-                    let s = c.component.measure(remaining);
+                    let s = c.component.measure(remaining, scale_factor);
                     c.final_size.set(s);
                     s
                 })
@@ -134,7 +134,7 @@ impl Layout for Dock {
                 .bottom
                 .as_mut()
                 .map(|c| {
-                    let s = c.component.measure(remaining);
+                    let s = c.component.measure(remaining, scale_factor);
                     c.final_size.set(s);
                     s
                 })
@@ -145,7 +145,7 @@ impl Layout for Dock {
                 .left
                 .as_mut()
                 .map(|c| {
-                    let s = c.component.measure(remaining);
+                    let s = c.component.measure(remaining, scale_factor);
                     c.final_size.set(s);
                     s
                 })
@@ -156,7 +156,7 @@ impl Layout for Dock {
                 .right
                 .as_mut()
                 .map(|c| {
-                    let s = c.component.measure(remaining);
+                    let s = c.component.measure(remaining, scale_factor);
                     c.final_size.set(s);
                     s
                 })
@@ -167,7 +167,7 @@ impl Layout for Dock {
                 .center
                 .as_mut()
                 .map(|c| {
-                    let s = c.component.measure(remaining);
+                    let s = c.component.measure(remaining, scale_factor);
                     c.final_size.set(s);
                     s
                 })
@@ -192,7 +192,7 @@ impl Layout for Dock {
         final_size
     }
 
-    fn arrange(&mut self, final_rect: Rect) -> Rect {
+    fn arrange(&mut self, final_rect: Rect, scale_factor: f32) -> Rect {
         let t = self
             .top
             .as_ref()
@@ -223,10 +223,10 @@ impl Layout for Dock {
             .top
             .as_mut()
             .map(|c| {
-                c.component.arrange(Rect::new(
-                    final_rect.pos,
-                    Size::new(final_rect.size.width, t.height),
-                ))
+                c.component.arrange(
+                    Rect::new(final_rect.pos, Size::new(final_rect.size.width, t.height)),
+                    scale_factor,
+                )
             })
             .unwrap_or_default();
 
@@ -234,13 +234,16 @@ impl Layout for Dock {
             .bottom
             .as_mut()
             .map(|c| {
-                c.component.arrange(Rect::new(
-                    Point::new(
-                        final_rect.pos.x,
-                        final_rect.pos.y + final_rect.size.height - b.height,
+                c.component.arrange(
+                    Rect::new(
+                        Point::new(
+                            final_rect.pos.x,
+                            final_rect.pos.y + final_rect.size.height - b.height,
+                        ),
+                        Size::new(final_rect.size.width, b.height),
                     ),
-                    Size::new(final_rect.size.width, b.height),
-                ))
+                    scale_factor,
+                )
             })
             .unwrap_or_default();
 
@@ -248,10 +251,13 @@ impl Layout for Dock {
             .left
             .as_mut()
             .map(|c| {
-                c.component.arrange(Rect::new(
-                    Point::new(final_rect.pos.x, final_rect.pos.y + t.height),
-                    Size::new(l.width, final_rect.size.height - t.height - b.height),
-                ))
+                c.component.arrange(
+                    Rect::new(
+                        Point::new(final_rect.pos.x, final_rect.pos.y + t.height),
+                        Size::new(l.width, final_rect.size.height - t.height - b.height),
+                    ),
+                    scale_factor,
+                )
             })
             .unwrap_or_default();
 
@@ -259,13 +265,16 @@ impl Layout for Dock {
             .right
             .as_mut()
             .map(|c| {
-                c.component.arrange(Rect::new(
-                    Point::new(
-                        final_rect.pos.x + final_rect.size.width - r.width,
-                        final_rect.pos.y + t.height,
+                c.component.arrange(
+                    Rect::new(
+                        Point::new(
+                            final_rect.pos.x + final_rect.size.width - r.width,
+                            final_rect.pos.y + t.height,
+                        ),
+                        Size::new(r.width, final_rect.size.height - t.height - b.height),
                     ),
-                    Size::new(r.width, final_rect.size.height - t.height - b.height),
-                ))
+                    scale_factor,
+                )
             })
             .unwrap_or_default();
 
@@ -273,13 +282,16 @@ impl Layout for Dock {
             .center
             .as_mut()
             .map(|c| {
-                c.component.arrange(Rect::new(
-                    Point::new(final_rect.pos.x + l.width, final_rect.pos.y + t.height),
-                    Size::new(
-                        final_rect.size.width - l.width - r.width,
-                        final_rect.size.height - t.height - b.height,
+                c.component.arrange(
+                    Rect::new(
+                        Point::new(final_rect.pos.x + l.width, final_rect.pos.y + t.height),
+                        Size::new(
+                            final_rect.size.width - l.width - r.width,
+                            final_rect.size.height - t.height - b.height,
+                        ),
                     ),
-                ))
+                    scale_factor,
+                )
             })
             .unwrap_or_default();
 
@@ -14,15 +14,29 @@
   limitations under the License.
 */
 
+extern crate brotli_decompressor;
 extern crate freetype;
 extern crate glm;
+#[cfg(target_os = "macos")]
+extern crate icrate;
 extern crate image;
 extern crate lazy_static;
+extern crate miniz_oxide;
 extern crate num_traits;
+extern crate rustybuzz;
+extern crate unicode_bidi;
+extern crate unicode_linebreak;
+extern crate unicode_segmentation;
 extern crate wgpu;
 extern crate winit;
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "ios", target_os = "android"))
+))]
+extern crate x11rb;
 
 mod application;
+pub mod clipboard;
 mod component;
 pub mod controls;
 mod drawing;
@@ -31,6 +45,7 @@ mod gfx;
 pub mod input_handling;
 pub mod layouts;
 mod scene;
+pub mod text;
 mod window;
 mod datatypes;
 pub use datatypes::*;
@@ -40,19 +55,28 @@ pub use self::scene::*;
 pub use self::window::*;
 
 use std::ops::*;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 pub struct StringProperty {
-    value: Arc<String>,
+    value: Arc<RwLock<String>>,
 }
 
 impl StringProperty {
     pub fn new() -> Self {
         Self {
-            value: Arc::new(String::new()),
+            value: Arc::new(RwLock::new(String::new())),
         }
     }
 
+    pub fn get(&self) -> String {
+        self.value.read().unwrap().clone()
+    }
+
+    /// Sets the value, bound components pick it up on their next layout.
+    pub fn set(&self, value: &str) {
+        *self.value.write().unwrap() = value.into();
+    }
+
     pub fn create_binding(&self) -> StringPropertyBinding {
         StringPropertyBinding {
             value: self.value.clone(),
@@ -63,13 +87,24 @@ impl StringProperty {
 impl From<&str> for StringProperty {
     fn from(value: &str) -> Self {
         Self {
-            value: Arc::new(value.into()),
+            value: Arc::new(RwLock::new(value.into())),
         }
     }
 }
 
+#[derive(Debug)]
 pub struct StringPropertyBinding {
-    value: Arc<String>,
+    value: Arc<RwLock<String>>,
+}
+
+impl StringPropertyBinding {
+    pub fn get(&self) -> String {
+        self.value.read().unwrap().clone()
+    }
+
+    pub fn set(&self, value: &str) {
+        *self.value.write().unwrap() = value.into();
+    }
 }
 
 #[derive(Debug)]
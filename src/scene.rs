@@ -74,11 +74,14 @@ impl Scene {
         self.background_color = color;
     }
 
-    pub(crate) fn update_layout(&mut self, canvas_size: Size) {
+    pub(crate) fn update_layout(&mut self, canvas_size: Size, scale_factor: f32) {
         if let Some(root) = unsafe { &mut *self.root.get() } {
             // if self.layout_dirty || root.is_layout_dirty() {
-            let measured_size = root.measure(canvas_size);
-            root.arrange(Rect::new(Point::zero(), canvas_size).align_center(measured_size));
+            let measured_size = root.measure(canvas_size, scale_factor);
+            root.arrange(
+                Rect::new(Point::zero(), canvas_size).align_center(measured_size),
+                scale_factor,
+            );
             //}
         }
         self.layout_dirty = false;
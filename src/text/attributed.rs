@@ -0,0 +1,182 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use font::{FamilyName, FontSize, FontType, FontVariation, FontWeight, FontWidth};
+use text::{TextDecoration, TextError};
+use Color;
+
+use std::ops::Range;
+
+/// Overrides applied to a span of an `AttributedString`.
+/// Unset values are inherited from the control the text is displayed in.
+#[derive(Debug, Clone, Default)]
+pub struct SpanStyle {
+    pub families: Option<Vec<FamilyName>>,
+    pub weight: Option<FontWeight>,
+    pub width: Option<FontWidth>,
+    pub type_: Option<FontType>,
+    pub variation: Option<FontVariation>,
+    pub size: Option<FontSize>,
+    pub color: Option<Color>,
+    pub letter_spacing: Option<f32>,
+    pub decoration: Option<TextDecoration>,
+}
+
+impl SpanStyle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_font_families(mut self, families: Vec<FamilyName>) -> Self {
+        self.families = Some(families);
+        self
+    }
+
+    pub fn with_font_weight(mut self, weight: FontWeight) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+
+    pub fn with_font_width(mut self, width: FontWidth) -> Self {
+        self.width = Some(width);
+        self
+    }
+
+    pub fn with_font_type(mut self, type_: FontType) -> Self {
+        self.type_ = Some(type_);
+        self
+    }
+
+    pub fn with_font_variation(mut self, variation: FontVariation) -> Self {
+        self.variation = Some(variation);
+        self
+    }
+
+    pub fn with_font_size(mut self, size: FontSize) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn with_foreground(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = Some(letter_spacing);
+        self
+    }
+
+    pub fn with_decoration(mut self, decoration: TextDecoration) -> Self {
+        self.decoration = Some(decoration);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TextSpan {
+    /// byte range into the text
+    pub range: Range<usize>,
+    pub style: SpanStyle,
+}
+
+/// Text with styled spans, ex. "Signed in as **alice** (admin)".
+/// When spans overlap, the values of the later span win.
+#[derive(Debug, Clone, Default)]
+pub struct AttributedString {
+    text: String,
+    spans: Vec<TextSpan>,
+}
+
+impl AttributedString {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.into(),
+            spans: Vec::new(),
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Appends unstyled text.
+    pub fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+    }
+
+    /// Appends text drawn with `style`.
+    pub fn push_styled(&mut self, text: &str, style: SpanStyle) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.spans.push(TextSpan {
+            range: start..self.text.len(),
+            style,
+        });
+    }
+
+    /// Styles an existing byte range of the text.
+    /// Fails if the range is out of bounds or not on character boundaries.
+    pub fn set_style(&mut self, range: Range<usize>, style: SpanStyle) -> Result<(), TextError> {
+        if range.start > range.end || range.end > self.text.len() {
+            return Err(TextError::OutOfBounds(range));
+        }
+        if let Some(&index) = [range.start, range.end]
+            .iter()
+            .find(|&&index| !self.text.is_char_boundary(index))
+        {
+            return Err(TextError::NotCharBoundary(index));
+        }
+
+        self.spans.push(TextSpan { range, style });
+        Ok(())
+    }
+}
+
+impl From<&str> for AttributedString {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_style_checks_the_range() {
+        let mut text = AttributedString::new("naïve");
+        assert_eq!(text.set_style(0..2, SpanStyle::new()), Ok(()));
+        let reversed = Range { start: 4, end: 2 };
+        assert_eq!(
+            text.set_style(reversed.clone(), SpanStyle::new()),
+            Err(TextError::OutOfBounds(reversed))
+        );
+        assert_eq!(
+            text.set_style(0..7, SpanStyle::new()),
+            Err(TextError::OutOfBounds(0..7))
+        );
+        assert_eq!(
+            text.set_style(3..6, SpanStyle::new()),
+            Err(TextError::NotCharBoundary(3))
+        );
+        assert_eq!(text.spans().len(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use std::ops::Range;
+
+use unicode_bidi::{BidiInfo, Level};
+
+/// Base direction of a paragraph.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    /// detect the direction from the first strong character, defaulting to left to right
+    #[default]
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl TextDirection {
+    pub fn is_rtl(&self) -> bool {
+        *self == TextDirection::RightToLeft
+    }
+
+    fn level(&self) -> Option<Level> {
+        match self {
+            TextDirection::Auto => None,
+            TextDirection::LeftToRight => Some(Level::ltr()),
+            TextDirection::RightToLeft => Some(Level::rtl()),
+        }
+    }
+}
+
+/// Returns the direction of the first strong character in `text`, or left to right if there is none.
+pub fn detect_direction(text: &str) -> TextDirection {
+    BidiParagraph::new(text, TextDirection::Auto).direction()
+}
+
+/// A run of text with a single embedding level.
+#[derive(Debug, Clone)]
+pub struct BidiRun {
+    /// byte range into the paragraph text
+    pub range: Range<usize>,
+    pub rtl: bool,
+}
+
+/// The Unicode Bidi Algorithm resolved over a single paragraph.
+#[derive(Debug)]
+pub struct BidiParagraph<'t> {
+    info: BidiInfo<'t>,
+    direction: TextDirection,
+}
+
+impl<'t> BidiParagraph<'t> {
+    pub fn new(text: &'t str, direction: TextDirection) -> Self {
+        let info = BidiInfo::new(text, direction.level());
+
+        let direction = match info.paragraphs.first() {
+            Some(paragraph) if paragraph.level.is_rtl() => TextDirection::RightToLeft,
+            Some(_) => TextDirection::LeftToRight,
+            None => match direction {
+                TextDirection::Auto => TextDirection::LeftToRight,
+                direction => direction,
+            },
+        };
+
+        Self { info, direction }
+    }
+
+    pub fn text(&self) -> &'t str {
+        self.info.text
+    }
+
+    /// The resolved base direction, never `Auto`.
+    pub fn direction(&self) -> TextDirection {
+        self.direction
+    }
+
+    /// Returns the runs of `line` in visual (left to right) order.
+    pub fn visual_runs(&self, line: Range<usize>) -> Vec<BidiRun> {
+        if line.start >= line.end {
+            return Vec::new();
+        }
+
+        // note: callers split text on newlines, so every line belongs to a single paragraph
+        let paragraph = match self.info.paragraphs.iter().find(|paragraph| {
+            paragraph.range.start <= line.start && line.start < paragraph.range.end
+        }) {
+            Some(paragraph) => paragraph,
+            None => return Vec::new(),
+        };
+
+        let line = line.start..line.end.min(paragraph.range.end);
+        let (levels, runs) = self.info.visual_runs(paragraph, line);
+
+        runs.into_iter()
+            .map(|range| BidiRun {
+                rtl: levels[range.start].is_rtl(),
+                range,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,41 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use std::ops::Range;
+
+/// Error returned when a byte range does not fit the text it refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextError {
+    /// the range is reversed or ends past the end of the text
+    OutOfBounds(Range<usize>),
+    /// the byte index is inside a character
+    NotCharBoundary(usize),
+}
+
+impl std::fmt::Display for TextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextError::OutOfBounds(range) => {
+                write!(f, "range {:?} is out of bounds of the text", range)
+            }
+            TextError::NotCharBoundary(index) => {
+                write!(f, "byte index {} is not a char boundary", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextError {}
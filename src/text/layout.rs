@@ -0,0 +1,823 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use font::{FontFallback, FontSize};
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+use {Alignment, HorizontalAlignment, Size, VerticalAlignment};
+
+use std::ops::Range;
+
+use super::bidi::*;
+use super::shaping::*;
+use super::style::*;
+
+const ELLIPSIS: &str = "\u{2026}";
+
+// note: tolerance so a layout re-run with its own measured width produces the same lines
+const WIDTH_EPSILON: f32 = 0.01;
+
+/// Where lines may be broken when they do not fit the available width.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// only break at explicit line breaks
+    None,
+    /// break between words at unicode line break opportunities
+    #[default]
+    Word,
+    /// break between any two clusters
+    Character,
+}
+
+/// Controls how text is broken into lines and truncated.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct TextWrapping {
+    pub mode: WrapMode,
+    /// maximum number of lines, the remaining text is dropped
+    pub max_lines: Option<usize>,
+    /// end truncated lines with an ellipsis
+    pub ellipsis: bool,
+}
+
+/// Height of every line of a paragraph.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum LineHeight {
+    /// the largest line height of the fonts on the line
+    #[default]
+    Normal,
+    /// multiple of the normal line height
+    Multiple(f32),
+    /// in logical pixels
+    Absolute(f32),
+}
+
+impl LineHeight {
+    /// Returns the ascent and height of a line with the normal `ascent` and `height`.
+    /// The difference to the normal height is split evenly above and below the text.
+    fn apply(self, ascent: f32, height: f32, scale_factor: f32) -> (f32, f32) {
+        let target = match self {
+            LineHeight::Normal => height,
+            LineHeight::Multiple(multiple) => height * multiple,
+            LineHeight::Absolute(height) => height * scale_factor,
+        };
+        (ascent + (target - height) / 2.0, target)
+    }
+}
+
+/// Positions of the tab stops of a paragraph.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TabStops {
+    /// in logical pixels from the left edge of the line, in ascending order
+    pub stops: Vec<f32>,
+    /// distance between the stops after the last one in logical pixels, eight spaces if unset
+    pub interval: Option<f32>,
+}
+
+impl TabStops {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_stop(mut self, stop: f32) -> Self {
+        self.stops.push(stop);
+        self
+    }
+
+    pub fn with_interval(mut self, interval: f32) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Returns the stops and interval in pixels, `style` measures the default interval.
+    fn resolve(&self, style: &TextStyle, scale_factor: f32) -> (Vec<f32>, f32) {
+        let interval = match self.interval {
+            Some(interval) => interval * scale_factor,
+            None => {
+                let font = style.fonts.primary();
+                font.metrics(style.size, scale_factor)
+                    .map_or(0.0, |metrics| metrics.advance(font.glyph_index(' ')))
+                    * 8.0
+            }
+        };
+        let stops = self.stops.iter().map(|stop| stop * scale_factor).collect();
+        (stops, interval)
+    }
+}
+
+/// Spacing between lines and paragraphs, and the tab stops of every paragraph.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParagraphStyle {
+    pub line_height: LineHeight,
+    /// extra space between paragraphs in logical pixels
+    pub paragraph_spacing: f32,
+    pub tab_stops: TabStops,
+}
+
+impl ParagraphStyle {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_line_height(mut self, line_height: LineHeight) -> Self {
+        self.line_height = line_height;
+        self
+    }
+
+    pub fn with_paragraph_spacing(mut self, paragraph_spacing: f32) -> Self {
+        self.paragraph_spacing = paragraph_spacing;
+        self
+    }
+
+    pub fn with_tab_stops(mut self, tab_stops: TabStops) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+}
+
+/// A single line of laid out text.
+#[derive(Debug, Clone)]
+pub struct LayoutLine {
+    /// byte range into the source text of the paragraph the line belongs to
+    pub paragraph: Range<usize>,
+    /// byte range into the source text, including trailing whitespace
+    pub range: Range<usize>,
+    /// shaped glyphs, cluster offsets are relative to the paragraph
+    pub shaped: ShapedLine,
+    /// offset of the left edge of the line from the left of the layout box
+    pub x: f32,
+    /// offset of the top of the line from the top of the layout box
+    pub y: f32,
+    /// distance from the top of the line to its baseline, the largest ascent of its styles
+    pub ascent: f32,
+    pub height: f32,
+}
+
+impl LayoutLine {
+    pub fn width(&self) -> f32 {
+        self.shaped.width
+    }
+}
+
+/// Text broken into lines that fit within a maximum width.
+#[derive(Debug, Clone, Default)]
+pub struct TextLayout {
+    pub lines: Vec<LayoutLine>,
+    /// width of the widest line and the total height of all lines
+    pub size: Size,
+    /// styles referenced by `ShapedGlyph::style`
+    pub styles: Vec<TextStyle>,
+    /// true if lines were dropped because of `TextWrapping::max_lines`
+    pub truncated: bool,
+    /// true if any line was shortened to fit an ellipsis
+    pub ellipsized: bool,
+    pub direction: TextDirection,
+    pub wrapping: TextWrapping,
+    pub paragraph: ParagraphStyle,
+    /// width the text was broken to fit
+    pub max_width: f32,
+    /// alignment last applied by `align`
+    pub alignment: Alignment,
+    /// scale factor the font sizes were converted to pixels with
+    pub scale_factor: f32,
+}
+
+impl TextLayout {
+    /// Positions the lines within a box of `size`.
+    /// Justified lines are stretched to the box width, except the last line of each paragraph.
+    pub fn align(&mut self, text: &str, alignment: Alignment, size: Size) {
+        self.alignment = alignment;
+
+        let top = match alignment.vertical {
+            VerticalAlignment::Top => 0.0,
+            VerticalAlignment::Center => (size.height - self.size.height) / 2.0,
+            VerticalAlignment::Bottom => size.height - self.size.height,
+        };
+
+        // note: keep the offsets between lines, they include the paragraph spacing
+        let first = self.lines.first().map_or(0.0, |line| line.y);
+        for line in &mut self.lines {
+            if let HorizontalAlignment::Justify = alignment.horizontal {
+                if line.range.end < line.paragraph.end {
+                    line.shaped
+                        .justify(&text[line.paragraph.clone()], size.width);
+                }
+            }
+
+            line.x = match alignment.horizontal {
+                HorizontalAlignment::Left | HorizontalAlignment::Justify => 0.0,
+                HorizontalAlignment::Center => (size.width - line.width()) / 2.0,
+                HorizontalAlignment::Right => size.width - line.width(),
+            };
+            line.y = top + line.y - first;
+        }
+    }
+
+    /// Returns true if laying out the same text at `max_width` and `scale_factor` would produce the same lines,
+    /// so the layout can be reused instead of shaping the text again.
+    pub fn is_valid_for(&self, max_width: f32, scale_factor: f32) -> bool {
+        // note: glyphs are shaped and rasterized in physical pixels
+        if self.scale_factor != scale_factor {
+            return false;
+        }
+        if max_width == self.max_width {
+            return true;
+        }
+        if self.ellipsized || self.size.width > max_width + WIDTH_EPSILON {
+            return false;
+        }
+
+        // note: narrowing down to the widest line keeps every break, widening may undo them
+        let wrapped = self
+            .lines
+            .iter()
+            .any(|line| line.range.end < line.paragraph.end);
+        max_width < self.max_width || !(wrapped || self.truncated)
+    }
+}
+
+/// Logical widths of the clusters of a shaped paragraph, used to measure candidate lines.
+struct ClusterWidths {
+    starts: Vec<usize>,
+    ends: Vec<usize>,
+    prefix: Vec<f32>,
+}
+
+impl ClusterWidths {
+    fn new(line: &ShapedLine) -> Self {
+        let mut clusters: Vec<(Range<usize>, f32)> = line
+            .clusters()
+            .iter()
+            .map(|cluster| (cluster.range.clone(), cluster.width))
+            .collect();
+        clusters.sort_by_key(|(range, _)| range.start);
+
+        let mut prefix = vec![0.0];
+        let mut total = 0.0;
+        for (_, width) in &clusters {
+            total += width;
+            prefix.push(total);
+        }
+
+        Self {
+            starts: clusters.iter().map(|(range, _)| range.start).collect(),
+            ends: clusters.iter().map(|(range, _)| range.end).collect(),
+            prefix,
+        }
+    }
+
+    fn first_at(&self, index: usize) -> usize {
+        match self.starts.binary_search(&index) {
+            Ok(i) | Err(i) => i,
+        }
+    }
+
+    fn width(&self, range: Range<usize>) -> f32 {
+        self.prefix[self.first_at(range.end)] - self.prefix[self.first_at(range.start)]
+    }
+
+    /// Returns the end of the longest run of clusters starting at `start` that fits in `max_width`.
+    /// At least one cluster is always taken so the line makes progress.
+    fn fit(&self, start: usize, end: usize, max_width: f32) -> usize {
+        let first = self.first_at(start);
+        let mut fit = match self.ends.get(first) {
+            Some(&cluster_end) => cluster_end.min(end),
+            None => return end,
+        };
+
+        for i in first + 1..self.starts.len() {
+            if self.ends[i] > end || self.prefix[i + 1] - self.prefix[first] > max_width {
+                break;
+            }
+            fit = self.ends[i];
+        }
+
+        fit
+    }
+}
+
+fn trim_end(text: &str, range: Range<usize>) -> Range<usize> {
+    let trimmed = text[range.clone()].trim_end();
+    range.start..range.start + trimmed.len()
+}
+
+/// Splits `text` at '\n', returning the byte range of every paragraph without its line ending.
+fn paragraphs(text: &str) -> Vec<Range<usize>> {
+    let mut paragraphs = Vec::new();
+    let mut offset = 0;
+
+    for part in text.split('\n') {
+        let len = part.strip_suffix('\r').unwrap_or(part).len();
+        paragraphs.push(offset..offset + len);
+        offset += part.len() + 1;
+    }
+
+    paragraphs
+}
+
+/// Candidate break positions of a paragraph, each paired with whether the break is mandatory.
+fn break_opportunities(text: &str, mode: WrapMode, widths: &ClusterWidths) -> Vec<(usize, bool)> {
+    let mut breaks: Vec<(usize, bool)> = linebreaks(text)
+        .filter(|&(index, opportunity)| {
+            index > 0 && (mode == WrapMode::Word || opportunity == BreakOpportunity::Mandatory)
+        })
+        .map(|(index, opportunity)| (index, opportunity == BreakOpportunity::Mandatory))
+        .collect();
+
+    if mode == WrapMode::Character {
+        breaks.extend(widths.ends.iter().map(|&end| (end, false)));
+        breaks.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        breaks.dedup_by_key(|(index, _)| *index);
+    }
+
+    breaks
+}
+
+/// Greedily breaks a paragraph into line ranges that fit in `max_width`.
+fn break_paragraph(
+    text: &str,
+    widths: &ClusterWidths,
+    mode: WrapMode,
+    max_width: f32,
+) -> Vec<Range<usize>> {
+    let breaks = break_opportunities(text, mode, widths);
+
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut last_fit: Option<usize> = None;
+
+    let mut i = 0;
+    while i < breaks.len() {
+        let (index, mandatory) = breaks[i];
+        let end = trim_end(text, start..index).end;
+
+        let fits = mode == WrapMode::None || widths.width(start..end) <= max_width + WIDTH_EPSILON;
+        if !fits {
+            if let Some(fit) = last_fit.take() {
+                lines.push(start..fit);
+                start = fit;
+                continue;
+            }
+
+            // note: a single word is wider than the line, break it between clusters
+            let fit = widths.fit(start, end, max_width + WIDTH_EPSILON);
+            if fit < end {
+                lines.push(start..fit);
+                start = fit;
+                continue;
+            }
+            // note: the word is a single cluster, let it overflow
+        }
+
+        if mandatory {
+            lines.push(start..index);
+            start = index;
+            last_fit = None;
+        } else {
+            last_fit = Some(index);
+        }
+        i += 1;
+    }
+
+    if start < text.len() || lines.is_empty() {
+        lines.push(start..text.len());
+    }
+
+    lines
+}
+
+/// Returns the ascent and height of a line, from the styles of its glyphs.
+/// Empty lines use the style at the start of the line.
+fn line_metrics(
+    line: &ShapedLine,
+    styles: &TextStyles,
+    empty_style: usize,
+    scale_factor: f32,
+) -> (f32, f32) {
+    let mut used: Vec<usize> = line.glyphs.iter().map(|glyph| glyph.style).collect();
+    used.sort();
+    used.dedup();
+    if used.is_empty() {
+        used.push(empty_style);
+    }
+
+    let ascent = used
+        .iter()
+        .map(|&style| styles.get(style).ascent(scale_factor))
+        .fold(0.0, f32::max);
+    let descent = used
+        .iter()
+        .map(|&style| {
+            let style = styles.get(style);
+            style.line_height(scale_factor) - style.ascent(scale_factor)
+        })
+        .fold(0.0, f32::max);
+
+    (ascent, ascent + descent)
+}
+
+/// Shapes the `range` of a paragraph as a single line and moves its tabs to the tab stops.
+fn shape_tabbed_range(
+    paragraph: &BidiParagraph,
+    range: Range<usize>,
+    styles: &TextStyles,
+    runs: &[StyleRun],
+    tabs: &(Vec<f32>, f32),
+    scale_factor: f32,
+) -> ShapedLine {
+    let mut line = shape_line_range(paragraph, range, styles, runs, scale_factor);
+    line.expand_tabs(paragraph.text(), &tabs.0, tabs.1);
+    line
+}
+
+/// Shortens `line` until it fits in `max_width` along with a trailing ellipsis.
+fn truncate_line(
+    text: &str,
+    line: &mut LayoutLine,
+    styles: &TextStyles,
+    direction: TextDirection,
+    tabs: &(Vec<f32>, f32),
+    max_width: f32,
+    scale_factor: f32,
+) {
+    let paragraph_text = &text[line.paragraph.clone()];
+    let paragraph = BidiParagraph::new(paragraph_text, direction);
+    let runs = styles.runs_in(line.paragraph.clone());
+
+    // note: the ellipsis takes the style of the last character on the line
+    let ellipsis_style = styles.style_at(line.range.end.max(line.range.start + 1) - 1);
+    let ellipsis = shape_line_range(
+        &BidiParagraph::new(ELLIPSIS, paragraph.direction()),
+        0..ELLIPSIS.len(),
+        styles,
+        &[StyleRun {
+            range: 0..ELLIPSIS.len(),
+            style: ellipsis_style,
+        }],
+        scale_factor,
+    );
+
+    let offset = line.paragraph.start;
+    let range = line.range.start - offset..line.range.end - offset;
+
+    let widths = ClusterWidths::new(&line.shaped);
+    let available = max_width - ellipsis.width + WIDTH_EPSILON;
+
+    let end = if widths.width(range.clone()) <= available {
+        range.end
+    } else {
+        let fit = widths.fit(range.start, range.end, available);
+        // note: fit always takes one cluster, drop it if even that does not leave room
+        if widths.width(range.start..fit) > available {
+            range.start
+        } else {
+            fit
+        }
+    };
+
+    let end = trim_end(paragraph_text, range.start..end).end;
+    line.shaped = shape_tabbed_range(
+        &paragraph,
+        range.start..end,
+        styles,
+        &runs,
+        tabs,
+        scale_factor,
+    );
+    line.shaped.append_ellipsis(ellipsis);
+}
+
+/// Breaks `text` drawn with a single font and size into lines no wider than `max_width`.
+#[allow(clippy::too_many_arguments)]
+pub fn layout_text(
+    text: &str,
+    fonts: &FontFallback,
+    size: FontSize,
+    direction: TextDirection,
+    wrapping: &TextWrapping,
+    paragraph: &ParagraphStyle,
+    max_width: f32,
+    scale_factor: f32,
+) -> TextLayout {
+    let styles = TextStyles::uniform(TextStyle::new(fonts.clone(), size));
+    layout_styled_text(
+        text,
+        &styles,
+        direction,
+        wrapping,
+        paragraph,
+        max_width,
+        scale_factor,
+    )
+}
+
+/// Breaks `text` into lines no wider than `max_width`.
+///
+/// Each paragraph is shaped once to measure candidate lines, the chosen lines are then
+/// shaped again on their own so bidi reordering is applied per line.
+/// Sizes are in physical pixels of a window with `scale_factor`.
+pub fn layout_styled_text(
+    text: &str,
+    styles: &TextStyles,
+    direction: TextDirection,
+    wrapping: &TextWrapping,
+    paragraph_style: &ParagraphStyle,
+    max_width: f32,
+    scale_factor: f32,
+) -> TextLayout {
+    let mut layout = TextLayout {
+        styles: styles.styles.clone(),
+        direction,
+        wrapping: *wrapping,
+        paragraph: paragraph_style.clone(),
+        max_width,
+        scale_factor,
+        ..Default::default()
+    };
+
+    let max_lines = wrapping.max_lines.unwrap_or(usize::MAX);
+    let tabs = paragraph_style
+        .tab_stops
+        .resolve(styles.base(), scale_factor);
+    let paragraph_spacing = paragraph_style.paragraph_spacing * scale_factor;
+
+    'paragraphs: for (index, paragraph_range) in paragraphs(text).into_iter().enumerate() {
+        let paragraph_text = &text[paragraph_range.clone()];
+        let paragraph = BidiParagraph::new(paragraph_text, direction);
+        let runs = styles.runs_in(paragraph_range.clone());
+
+        if index > 0 && layout.lines.len() < max_lines {
+            layout.size.height += paragraph_spacing;
+        }
+
+        // note: tabs are measured as if the paragraph was a single line, and placed again once broken
+        let full = shape_tabbed_range(
+            &paragraph,
+            0..paragraph_text.len(),
+            styles,
+            &runs,
+            &tabs,
+            scale_factor,
+        );
+        let widths = ClusterWidths::new(&full);
+
+        for range in break_paragraph(paragraph_text, &widths, wrapping.mode, max_width) {
+            if layout.lines.len() == max_lines {
+                layout.truncated = true;
+                break 'paragraphs;
+            }
+
+            let trimmed = trim_end(paragraph_text, range.clone());
+            let shaped = if trimmed == full.range {
+                full.clone()
+            } else {
+                shape_tabbed_range(&paragraph, trimmed, styles, &runs, &tabs, scale_factor)
+            };
+
+            let range = paragraph_range.start + range.start..paragraph_range.start + range.end;
+            let empty_style = styles.style_at(range.start);
+            let (ascent, height) = line_metrics(&shaped, styles, empty_style, scale_factor);
+            let (ascent, height) = paragraph_style
+                .line_height
+                .apply(ascent, height, scale_factor);
+
+            layout.lines.push(LayoutLine {
+                paragraph: paragraph_range.clone(),
+                range,
+                shaped,
+                x: 0.0,
+                y: layout.size.height,
+                ascent,
+                height,
+            });
+            layout.size.height += height;
+        }
+    }
+
+    if wrapping.ellipsis {
+        let count = layout.lines.len();
+        for (i, line) in layout.lines.iter_mut().enumerate() {
+            let overflows = line.width() > max_width + WIDTH_EPSILON;
+            if overflows || (layout.truncated && i + 1 == count) {
+                truncate_line(
+                    text,
+                    line,
+                    styles,
+                    direction,
+                    &tabs,
+                    max_width,
+                    scale_factor,
+                );
+                layout.ellipsized = true;
+            }
+        }
+    }
+
+    layout.size.width = layout
+        .lines
+        .iter()
+        .map(|line| line.width())
+        .fold(0.0, f32::max);
+
+    layout
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::FontStyle;
+
+    fn wrap_text(text: &str, wrapping: TextWrapping, max_width: f32) -> TextLayout {
+        let fonts = FontFallback::last_resort(FontStyle::default()).unwrap();
+        layout_text(
+            text,
+            &fonts,
+            FontSize::px(16.0),
+            TextDirection::LeftToRight,
+            &wrapping,
+            &ParagraphStyle::default(),
+            max_width,
+            1.0,
+        )
+    }
+
+    fn width(text: &str) -> f32 {
+        wrap_text(text, TextWrapping::default(), f32::INFINITY)
+            .size
+            .width
+    }
+
+    fn mode(mode: WrapMode) -> TextWrapping {
+        TextWrapping {
+            mode,
+            ..Default::default()
+        }
+    }
+
+    /// Text of each line without its trailing spaces.
+    fn lines<'a>(text: &'a str, layout: &TextLayout) -> Vec<&'a str> {
+        layout
+            .lines
+            .iter()
+            .map(|line| text[line.range.clone()].trim_end())
+            .collect()
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let text = "hello world again";
+        let max_width = width("hello world");
+        let layout = wrap_text(text, mode(WrapMode::Word), max_width);
+        assert_eq!(lines(text, &layout), ["hello world", "again"]);
+        assert!(layout.size.width <= max_width);
+        assert!(!layout.truncated && !layout.ellipsized);
+
+        // note: a word wider than the line is broken between clusters
+        let layout = wrap_text("hello", mode(WrapMode::Word), width("hel"));
+        assert_eq!(lines("hello", &layout), ["hel", "lo"]);
+
+        let layout = wrap_text(text, mode(WrapMode::None), max_width);
+        assert_eq!(lines(text, &layout), [text]);
+    }
+
+    #[test]
+    fn wraps_between_characters() {
+        let text = "hello world";
+        let max_width = width("hello w");
+        let layout = wrap_text(text, mode(WrapMode::Character), max_width);
+        assert_eq!(lines(text, &layout), ["hello w", "orld"]);
+        assert!(layout.size.width <= max_width);
+    }
+
+    #[test]
+    fn truncates_with_an_ellipsis() {
+        let max_width = width("hello\u{2026}");
+        let wrapping = TextWrapping {
+            mode: WrapMode::None,
+            ellipsis: true,
+            ..Default::default()
+        };
+        let layout = wrap_text("hello world", wrapping, max_width);
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.ellipsized);
+        assert_eq!(layout.lines[0].shaped.range, 0..5);
+        assert!(layout.size.width <= max_width + WIDTH_EPSILON);
+        assert!(layout.size.width > width("hello"));
+
+        // note: the last line kept by `max_lines` ends with an ellipsis even when it fits
+        let wrapping = TextWrapping {
+            mode: WrapMode::Word,
+            max_lines: Some(1),
+            ellipsis: true,
+        };
+        let layout = wrap_text("one two", wrapping, width("one\u{2026}"));
+        assert_eq!(layout.lines.len(), 1);
+        assert!(layout.truncated && layout.ellipsized);
+        assert_eq!(layout.lines[0].shaped.range, 0..3);
+    }
+
+    fn layout_paragraph(text: &str, paragraph: &ParagraphStyle, scale_factor: f32) -> TextLayout {
+        let fonts = FontFallback::last_resort(FontStyle::default()).unwrap();
+        layout_text(
+            text,
+            &fonts,
+            FontSize::px(16.0),
+            TextDirection::LeftToRight,
+            &TextWrapping::default(),
+            paragraph,
+            f32::INFINITY,
+            scale_factor,
+        )
+    }
+
+    #[test]
+    fn tabs_land_on_the_stops() {
+        let text = "a\tb\tc\nd\te";
+        let tabs = TabStops::new().with_stop(50.0).with_stop(120.0);
+        let paragraph = ParagraphStyle::default().with_tab_stops(tabs);
+
+        // note: stops are in logical pixels
+        let layout = layout_paragraph(text, &paragraph, 2.0);
+        assert_eq!(layout.lines[0].shaped.caret_x(2), 100.0);
+        assert_eq!(layout.lines[0].shaped.caret_x(4), 240.0);
+        // note: every paragraph starts over from the first stop
+        assert_eq!(layout.lines[1].shaped.caret_x(2), 100.0);
+
+        // note: without stops tabs are eight spaces apart
+        let layout = layout_paragraph("a\tb", &ParagraphStyle::default(), 1.0);
+        let space = width(" x") - width("x");
+        assert!((layout.lines[0].shaped.caret_x(2) - space * 8.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn line_height_spreads_the_difference() {
+        let text = "one\ntwo";
+        let normal = layout_paragraph(text, &ParagraphStyle::default(), 2.0);
+        let (ascent, height) = (normal.lines[0].ascent, normal.lines[0].height);
+
+        let paragraph = ParagraphStyle::default().with_line_height(LineHeight::Multiple(1.5));
+        let layout = layout_paragraph(text, &paragraph, 2.0);
+        assert_eq!(layout.lines[0].height, height * 1.5);
+        assert_eq!(layout.lines[0].ascent, ascent + height * 0.25);
+        assert_eq!(layout.lines[1].y, height * 1.5);
+
+        // note: absolute heights are in logical pixels
+        let paragraph = ParagraphStyle::default().with_line_height(LineHeight::Absolute(40.0));
+        let layout = layout_paragraph(text, &paragraph, 2.0);
+        assert_eq!(layout.lines[0].height, 80.0);
+        assert_eq!(layout.lines[0].ascent, ascent + (80.0 - height) / 2.0);
+        assert_eq!(layout.size.height, 160.0);
+    }
+
+    #[test]
+    fn align_keeps_paragraph_spacing() {
+        let text = "first\nsecond";
+        let fonts = FontFallback::last_resort(FontStyle::default()).unwrap();
+        let spacing = 10.0;
+        let mut layout = layout_text(
+            text,
+            &fonts,
+            FontSize::px(16.0),
+            TextDirection::LeftToRight,
+            &TextWrapping::default(),
+            &ParagraphStyle::default().with_paragraph_spacing(spacing),
+            f32::INFINITY,
+            1.0,
+        );
+        assert_eq!(layout.lines.len(), 2);
+
+        let first = &layout.lines[0];
+        let gap = first.height + spacing;
+        assert_eq!(layout.lines[1].y - first.y, gap);
+        assert_eq!(layout.size.height, gap + layout.lines[1].height);
+
+        let size = Size::new(200.0, layout.size.height + 40.0);
+        let alignment = Alignment {
+            horizontal: HorizontalAlignment::Left,
+            vertical: VerticalAlignment::Center,
+        };
+        layout.align(text, alignment, size);
+        assert_eq!(layout.lines[0].y, 20.0);
+        assert_eq!(layout.lines[1].y, 20.0 + gap);
+
+        // note: aligning again within another box moves the lines, it does not add up
+        let alignment = Alignment {
+            vertical: VerticalAlignment::Bottom,
+            ..alignment
+        };
+        layout.align(text, alignment, size);
+        assert_eq!(layout.lines[0].y, 40.0);
+        assert_eq!(layout.lines[1].y, 40.0 + gap);
+    }
+}
@@ -0,0 +1,31 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+mod attributed;
+mod bidi;
+mod error;
+mod layout;
+mod paragraph;
+mod shaping;
+mod style;
+
+pub use self::attributed::*;
+pub use self::bidi::*;
+pub use self::error::TextError;
+pub use self::layout::*;
+pub use self::paragraph::*;
+pub use self::shaping::*;
+pub use self::style::*;
@@ -0,0 +1,422 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use unicode_segmentation::{GraphemeCursor, UnicodeSegmentation};
+use {Point, Rect, Size};
+
+use std::ops::Range;
+
+use super::layout::*;
+
+/// Result of hit testing a point against a `Paragraph`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TextHit {
+    /// byte index of the caret position closest to the point
+    pub index: usize,
+    /// line the point is on, the closest line for points above or below the text
+    pub line: usize,
+    /// true if the point is over a glyph cluster
+    pub inside: bool,
+}
+
+/// A glyph cluster placed in the layout box, ex. a ligature or a base character with its marks.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterBox {
+    /// byte range into the text
+    pub range: Range<usize>,
+    pub rect: Rect,
+    pub rtl: bool,
+}
+
+/// Geometry queries on laid out text, for carets, selections and hit testing.
+/// Positions are relative to the top left of the layout box, see `TextLayout::align`.
+/// Caret positions always fall on grapheme cluster boundaries.
+#[derive(Debug, Copy, Clone)]
+pub struct Paragraph<'a> {
+    text: &'a str,
+    layout: &'a TextLayout,
+}
+
+impl<'a> Paragraph<'a> {
+    /// `text` must be the text `layout` was made from.
+    pub fn new(text: &'a str, layout: &'a TextLayout) -> Self {
+        Self { text, layout }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn layout(&self) -> &'a TextLayout {
+        self.layout
+    }
+
+    pub fn lines(&self) -> &'a [LayoutLine] {
+        &self.layout.lines
+    }
+
+    /// Box of a line, as wide as its glyphs and as tall as the line height.
+    pub fn line_box(&self, line: usize) -> Rect {
+        let line = &self.layout.lines[line];
+        Rect::new(
+            Point::new(line.x, line.y),
+            Size::new(line.width(), line.height),
+        )
+    }
+
+    /// Clusters of a line in visual order.
+    pub fn clusters(&self, line: usize) -> Vec<ClusterBox> {
+        let line = &self.layout.lines[line];
+        let offset = line.paragraph.start;
+        line.shaped
+            .clusters()
+            .iter()
+            .map(|cluster| ClusterBox {
+                range: offset + cluster.range.start..offset + cluster.range.end,
+                rect: Rect::new(
+                    Point::new(line.x + cluster.x, line.y),
+                    Size::new(cluster.width, line.height),
+                ),
+                rtl: cluster.rtl,
+            })
+            .collect()
+    }
+
+    /// Returns the line a caret at `index` is drawn on.
+    /// An index where a wrapped line ends belongs to the start of the next line.
+    pub fn line_at_index(&self, index: usize) -> usize {
+        self.layout
+            .lines
+            .iter()
+            .rposition(|line| line.range.start <= index)
+            .unwrap_or(0)
+    }
+
+    /// Moves `index` back to the start of the grapheme cluster it is in.
+    pub fn grapheme_start(&self, index: usize) -> usize {
+        let mut index = index.min(self.text.len());
+        while !self.text.is_char_boundary(index) {
+            index -= 1;
+        }
+
+        let mut cursor = GraphemeCursor::new(index, self.text.len(), true);
+        match cursor.is_boundary(self.text, 0) {
+            Ok(false) => cursor
+                .prev_boundary(self.text, 0)
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+            _ => index,
+        }
+    }
+
+    /// Returns the caret position after the grapheme cluster at `index`.
+    pub fn next_grapheme(&self, index: usize) -> usize {
+        let index = self.grapheme_start(index);
+        let mut cursor = GraphemeCursor::new(index, self.text.len(), true);
+        cursor
+            .next_boundary(self.text, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(self.text.len())
+    }
+
+    /// Returns the caret position before the grapheme cluster that ends at `index`.
+    pub fn previous_grapheme(&self, index: usize) -> usize {
+        let index = self.grapheme_start(index);
+        let mut cursor = GraphemeCursor::new(index, self.text.len(), true);
+        cursor
+            .prev_boundary(self.text, 0)
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
+
+    /// Returns the word at `index`, or the spaces or punctuation between words.
+    /// An index at the end of a word belongs to that word.
+    pub fn word_at(&self, index: usize) -> Range<usize> {
+        let index = self.grapheme_start(index);
+        let mut words = self
+            .text
+            .split_word_bound_indices()
+            .map(|(start, word)| start..start + word.len());
+
+        let mut previous: Option<Range<usize>> = None;
+        for word in &mut words {
+            if word.contains(&index) {
+                // note: prefer the word before the caret over the space or punctuation after it
+                let is_word = |range: &Range<usize>| {
+                    self.text[range.clone()].chars().any(char::is_alphanumeric)
+                };
+                return match previous {
+                    Some(previous)
+                        if word.start == index && !is_word(&word) && is_word(&previous) =>
+                    {
+                        previous
+                    }
+                    _ => word,
+                };
+            }
+            previous = Some(word);
+        }
+
+        previous.unwrap_or(index..index)
+    }
+
+    /// Returns the end of the word after `index`, for moving the caret a word at a time.
+    pub fn next_word(&self, index: usize) -> usize {
+        self.text
+            .split_word_bound_indices()
+            .map(|(start, word)| (start + word.len(), word))
+            .find(|&(end, word)| end > index && word.chars().any(char::is_alphanumeric))
+            .map_or(self.text.len(), |(end, _)| end)
+    }
+
+    /// Returns the start of the word before `index`, for moving the caret a word at a time.
+    pub fn previous_word(&self, index: usize) -> usize {
+        self.text
+            .split_word_bound_indices()
+            .rfind(|&(start, word)| start < index && word.chars().any(char::is_alphanumeric))
+            .map_or(0, |(start, _)| start)
+    }
+
+    /// Returns the caret position closest to `point`.
+    pub fn hit_test(&self, point: Point) -> TextHit {
+        let lines = &self.layout.lines;
+        if lines.is_empty() {
+            return TextHit {
+                index: 0,
+                line: 0,
+                inside: false,
+            };
+        }
+
+        let line_index = lines
+            .iter()
+            .position(|line| point.y < line.y + line.height)
+            .unwrap_or(lines.len() - 1);
+        let line = &lines[line_index];
+
+        let x = point.x - line.x;
+        let index = line.paragraph.start + line.shaped.index_at_x(x);
+        let index = self.grapheme_start(index.clamp(line.range.start, line.range.end));
+
+        let inside =
+            point.y >= line.y && point.y < line.y + line.height && x >= 0.0 && x < line.width();
+
+        TextHit {
+            index,
+            line: line_index,
+            inside,
+        }
+    }
+
+    /// Rectangle of a caret placed before the byte `index`, one logical pixel wide.
+    pub fn caret_rect(&self, index: usize) -> Rect {
+        let width = self.layout.scale_factor;
+        let line = match self.layout.lines.get(self.line_at_index(index)) {
+            Some(line) => line,
+            None => return Rect::new(Point::new(0.0, 0.0), Size::new(width, 0.0)),
+        };
+
+        let index = self
+            .grapheme_start(index)
+            .clamp(line.range.start, line.range.end);
+        let x = line.x + line.shaped.caret_x(index - line.paragraph.start);
+
+        // note: keep the caret inside the line at its right edge
+        let x = if x >= line.x + line.width() && line.width() > 0.0 {
+            x - width
+        } else {
+            x
+        };
+        Rect::new(Point::new(x, line.y), Size::new(width, line.height))
+    }
+
+    /// Rectangles covering the clusters in `range`, at most one per run of adjacent clusters on a line.
+    /// Bidirectional text can produce several rectangles on the same line.
+    pub fn selection_rects(&self, range: Range<usize>) -> Vec<Rect> {
+        let mut rects: Vec<Rect> = Vec::new();
+        if range.start >= range.end {
+            return rects;
+        }
+
+        for (index, line) in self.layout.lines.iter().enumerate() {
+            if line.range.end < range.start || line.range.start >= range.end {
+                continue;
+            }
+
+            let first = rects.len();
+            for cluster in self.clusters(index) {
+                if cluster.range.start >= range.end || cluster.range.end <= range.start {
+                    continue;
+                }
+
+                match rects[first..].last_mut() {
+                    Some(rect)
+                        if (rect.pos.x + rect.size.width - cluster.rect.pos.x).abs() < 0.01 =>
+                    {
+                        rect.size.width += cluster.rect.size.width;
+                    }
+                    _ => rects.push(cluster.rect),
+                }
+            }
+        }
+
+        rects
+    }
+}
+
+impl TextLayout {
+    /// Geometry queries on the layout, `text` must be the text the layout was made from.
+    pub fn paragraph<'a>(&'a self, text: &'a str) -> Paragraph<'a> {
+        Paragraph::new(text, self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::{FontFallback, FontSize, FontStyle};
+    use text::{layout_text, ParagraphStyle, TextDirection, TextWrapping};
+
+    fn layout(text: &str, max_width: f32) -> TextLayout {
+        let fonts = FontFallback::last_resort(FontStyle::default()).unwrap();
+        layout_text(
+            text,
+            &fonts,
+            FontSize::px(16.0),
+            TextDirection::LeftToRight,
+            &TextWrapping::default(),
+            &ParagraphStyle::default(),
+            max_width,
+            1.0,
+        )
+    }
+
+    fn cluster(paragraph: &Paragraph, line: usize, start: usize) -> ClusterBox {
+        paragraph
+            .clusters(line)
+            .into_iter()
+            .find(|cluster| cluster.range.start == start)
+            .unwrap()
+    }
+
+    fn middle_y(paragraph: &Paragraph, line: usize) -> f32 {
+        let line = &paragraph.lines()[line];
+        line.y + line.height / 2.0
+    }
+
+    #[test]
+    fn single_line() {
+        let text = "hello world";
+        let layout = layout(text, f32::INFINITY);
+        let paragraph = layout.paragraph(text);
+        assert_eq!(paragraph.lines().len(), 1);
+
+        let w = cluster(&paragraph, 0, 6).rect;
+        let y = middle_y(&paragraph, 0);
+        let hit = paragraph.hit_test(Point::new(w.pos.x + 1.0, y));
+        assert_eq!(
+            hit,
+            TextHit {
+                index: 6,
+                line: 0,
+                inside: true
+            }
+        );
+        let hit = paragraph.hit_test(Point::new(w.pos.x + w.size.width - 1.0, y));
+        assert_eq!(hit.index, 7);
+        let hit = paragraph.hit_test(Point::new(layout.size.width + 10.0, y));
+        assert_eq!((hit.index, hit.inside), (text.len(), false));
+
+        let caret = paragraph.caret_rect(6);
+        assert_eq!(caret.pos.x, w.pos.x);
+        assert_eq!(caret.size.height, paragraph.lines()[0].height);
+
+        let hello = cluster(&paragraph, 0, 4).rect;
+        let rects = paragraph.selection_rects(0..5);
+        assert_eq!(rects.len(), 1);
+        assert_eq!(rects[0].pos.x, 0.0);
+        assert!((rects[0].size.width - (hello.pos.x + hello.size.width)).abs() < 0.01);
+        assert!(paragraph.selection_rects(3..3).is_empty());
+
+        assert_eq!(paragraph.previous_word(8), 6);
+        assert_eq!(paragraph.previous_word(6), 0);
+        assert_eq!(paragraph.next_word(5), text.len());
+    }
+
+    #[test]
+    fn wrapped_line() {
+        let text = "hello world";
+        let width = layout(text, f32::INFINITY).size.width;
+        let layout = layout(text, width * 0.75);
+        let paragraph = layout.paragraph(text);
+        assert_eq!(paragraph.lines().len(), 2);
+        assert_eq!(paragraph.lines()[1].range.start, 6);
+
+        let hit = paragraph.hit_test(Point::new(1.0, middle_y(&paragraph, 1)));
+        assert_eq!(
+            hit,
+            TextHit {
+                index: 6,
+                line: 1,
+                inside: true
+            }
+        );
+        // note: points below the text hit the last line
+        let hit = paragraph.hit_test(Point::new(1.0, layout.size.height + 10.0));
+        assert_eq!((hit.index, hit.line, hit.inside), (6, 1, false));
+
+        // note: the index the first line wraps at belongs to the start of the second line
+        let caret = paragraph.caret_rect(6);
+        assert_eq!(caret.pos, Point::new(0.0, paragraph.lines()[1].y));
+
+        let rects = paragraph.selection_rects(2..9);
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[0].pos.y, paragraph.lines()[0].y);
+        assert_eq!(rects[1].pos, Point::new(0.0, paragraph.lines()[1].y));
+    }
+
+    #[test]
+    fn bidi_text() {
+        // note: "abc " is 0..4, the hebrew letters are 4..6, 6..8 and 8..10, " def" is 10..14
+        let text = "abc \u{5d0}\u{5d1}\u{5d2} def";
+        let layout = layout(text, f32::INFINITY);
+        let paragraph = layout.paragraph(text);
+        let y = middle_y(&paragraph, 0);
+
+        let alef = cluster(&paragraph, 0, 4);
+        let gimel = cluster(&paragraph, 0, 8);
+        assert!(alef.rtl);
+        assert!(gimel.rect.pos.x < alef.rect.pos.x);
+
+        // note: the caret before a right to left letter is at its right edge
+        let caret = paragraph.caret_rect(4);
+        assert!((caret.pos.x - (alef.rect.pos.x + alef.rect.size.width)).abs() < 0.01);
+
+        let hit = paragraph.hit_test(Point::new(alef.rect.pos.x + alef.rect.size.width - 1.0, y));
+        assert_eq!(hit.index, 4);
+        let hit = paragraph.hit_test(Point::new(alef.rect.pos.x + 1.0, y));
+        assert_eq!(hit.index, 6);
+
+        // note: the hebrew run is contiguous on screen, "c " and alef are not
+        assert_eq!(paragraph.selection_rects(4..10).len(), 1);
+        let rects = paragraph.selection_rects(2..6);
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[1].pos.x, alef.rect.pos.x);
+    }
+}
@@ -0,0 +1,462 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use font::{calculate_font_scale, Font, FontFallback, FontRun, FontSize};
+use rustybuzz;
+use Point;
+
+use std::ops::Range;
+
+use super::bidi::*;
+use super::style::*;
+
+/// A glyph positioned by the shaper.
+/// All values are in pixels relative to the pen position at the start of the line.
+#[derive(Debug, Clone)]
+pub struct ShapedGlyph {
+    pub font: &'static Font,
+    pub glyph_index: u32,
+    /// index of the `TextStyle` the glyph was shaped with
+    pub style: usize,
+    /// byte offset into the paragraph of the cluster this glyph belongs to
+    pub cluster: usize,
+    pub advance: f32,
+    /// offset from the pen position, y increases downwards
+    pub offset: Point,
+    /// true if the glyph belongs to a right to left run
+    pub rtl: bool,
+}
+
+/// The glyphs that make up one indivisible piece of text, ex. a ligature or a base character with marks.
+#[derive(Debug, Clone)]
+pub struct GlyphCluster {
+    /// byte range into the paragraph
+    pub range: Range<usize>,
+    /// left edge relative to the start of the line
+    pub x: f32,
+    pub width: f32,
+    pub rtl: bool,
+}
+
+impl GlyphCluster {
+    /// x position of the edge the cluster's text starts at in reading order
+    pub fn leading_edge(&self) -> f32 {
+        if self.rtl {
+            self.x + self.width
+        } else {
+            self.x
+        }
+    }
+
+    /// x position of the edge the cluster's text ends at in reading order
+    pub fn trailing_edge(&self) -> f32 {
+        if self.rtl {
+            self.x
+        } else {
+            self.x + self.width
+        }
+    }
+}
+
+/// A single line of shaped glyphs in visual order.
+#[derive(Debug, Clone, Default)]
+pub struct ShapedLine {
+    pub glyphs: Vec<ShapedGlyph>,
+    pub width: f32,
+    /// byte range into the paragraph
+    pub range: Range<usize>,
+    /// resolved base direction of the paragraph
+    pub direction: TextDirection,
+    clusters: Vec<GlyphCluster>,
+}
+
+impl ShapedLine {
+    fn push(&mut self, glyph: ShapedGlyph) {
+        self.width += glyph.advance;
+        self.glyphs.push(glyph);
+    }
+
+    fn build_clusters(&mut self) {
+        let mut starts: Vec<usize> = self.glyphs.iter().map(|glyph| glyph.cluster).collect();
+        starts.sort();
+        starts.dedup();
+
+        let cluster_end = |start: usize| match starts.binary_search(&start) {
+            Ok(index) if index + 1 < starts.len() => starts[index + 1],
+            _ => self.range.end,
+        };
+
+        let mut clusters: Vec<GlyphCluster> = Vec::new();
+        let mut x = 0.0;
+        for glyph in &self.glyphs {
+            match clusters.last_mut() {
+                Some(cluster) if cluster.range.start == glyph.cluster => {
+                    cluster.width += glyph.advance;
+                }
+                _ => clusters.push(GlyphCluster {
+                    range: glyph.cluster..cluster_end(glyph.cluster),
+                    x,
+                    width: glyph.advance,
+                    rtl: glyph.rtl,
+                }),
+            }
+            x += glyph.advance;
+        }
+
+        self.clusters = clusters;
+    }
+
+    /// Appends `ellipsis` at the visual end of the line, the right edge for left to right
+    /// paragraphs and the left edge for right to left ones.
+    /// The ellipsis glyphs are attributed to the cluster at the end of the line.
+    pub(crate) fn append_ellipsis(&mut self, ellipsis: ShapedLine) {
+        let cluster = self.range.end;
+        let rtl = self.direction.is_rtl();
+        let glyphs = ellipsis.glyphs.into_iter().map(|glyph| ShapedGlyph {
+            cluster,
+            rtl,
+            ..glyph
+        });
+
+        if rtl {
+            let mut line: Vec<ShapedGlyph> = glyphs.collect();
+            line.append(&mut self.glyphs);
+            self.glyphs = line;
+        } else {
+            self.glyphs.extend(glyphs);
+        }
+
+        self.width += ellipsis.width;
+        self.build_clusters();
+    }
+
+    /// Widens the whitespace glyphs of the line so it fills `width`.
+    /// `text` is the paragraph the line was shaped from.
+    pub(crate) fn justify(&mut self, text: &str, width: f32) {
+        let is_space = |glyph: &ShapedGlyph| {
+            text[glyph.cluster..]
+                .chars()
+                .next()
+                .is_some_and(char::is_whitespace)
+        };
+
+        let spaces = self.glyphs.iter().filter(|glyph| is_space(glyph)).count();
+        if spaces == 0 || width <= self.width {
+            return;
+        }
+
+        let extra = (width - self.width) / spaces as f32;
+        for glyph in &mut self.glyphs {
+            if is_space(glyph) {
+                glyph.advance += extra;
+            }
+        }
+
+        self.width = width;
+        self.build_clusters();
+    }
+
+    /// Widens every tab so the text after it starts at the next tab stop.
+    /// `text` is the paragraph the line was shaped from, `stops` and `interval` are in pixels
+    /// from the left edge of the line, stops past the last one are `interval` apart.
+    pub(crate) fn expand_tabs(&mut self, text: &str, stops: &[f32], interval: f32) {
+        let mut x = 0.0;
+        let mut expanded = false;
+
+        for glyph in &mut self.glyphs {
+            if text[glyph.cluster..].starts_with('\t') {
+                let next = stops
+                    .iter()
+                    .copied()
+                    .find(|&stop| stop > x)
+                    .unwrap_or_else(|| {
+                        if interval > 0.0 {
+                            ((x / interval).floor() + 1.0) * interval
+                        } else {
+                            x
+                        }
+                    });
+
+                // note: fonts rarely map the tab character, draw it as a space
+                glyph.glyph_index = glyph.font.glyph_index(' ');
+                glyph.advance = next - x;
+                glyph.offset = Point::new(0.0, 0.0);
+                expanded = true;
+            }
+            x += glyph.advance;
+        }
+
+        if expanded {
+            self.width = x;
+            self.build_clusters();
+        }
+    }
+
+    /// Clusters in visual order.
+    pub fn clusters(&self) -> &[GlyphCluster] {
+        &self.clusters
+    }
+
+    /// Returns the x position of a caret placed before the byte `index`.
+    /// Indices inside a cluster snap to the start of that cluster.
+    pub fn caret_x(&self, index: usize) -> f32 {
+        if let Some(cluster) = self
+            .clusters
+            .iter()
+            .find(|cluster| cluster.range.contains(&index))
+        {
+            return cluster.leading_edge();
+        }
+
+        // note: the caret is past the last character, place it after the last logical cluster
+        match self
+            .clusters
+            .iter()
+            .filter(|cluster| cluster.range.end <= index)
+            .max_by_key(|cluster| cluster.range.end)
+        {
+            Some(cluster) => cluster.trailing_edge(),
+            None if self.direction.is_rtl() => self.width,
+            None => 0.0,
+        }
+    }
+
+    /// Returns the byte index of the cluster boundary closest to `x`.
+    pub fn index_at_x(&self, x: f32) -> usize {
+        let cluster = match self
+            .clusters
+            .iter()
+            .find(|cluster| x < cluster.x + cluster.width)
+            .or(self.clusters.last())
+        {
+            Some(cluster) => cluster,
+            None => return self.range.start,
+        };
+
+        let before_middle = x < cluster.x + cluster.width / 2.0;
+        if before_middle != cluster.rtl {
+            cluster.range.start
+        } else {
+            cluster.range.end
+        }
+    }
+}
+
+/// Shapes a single line of text, splitting it into bidi runs and per-font runs.
+/// Advances are in physical pixels of a window with `scale_factor`.
+pub fn shape_line(
+    text: &str,
+    fonts: &FontFallback,
+    size: FontSize,
+    direction: TextDirection,
+    scale_factor: f32,
+) -> ShapedLine {
+    let styles = TextStyles::uniform(TextStyle::new(fonts.clone(), size));
+    let paragraph = BidiParagraph::new(text, direction);
+    shape_line_range(
+        &paragraph,
+        0..text.len(),
+        &styles,
+        &styles.runs_in(0..text.len()),
+        scale_factor,
+    )
+}
+
+/// Shapes the `range` of a paragraph as a single visual line.
+/// `runs` assign the styles to the paragraph, see `TextStyles::runs_in`.
+pub fn shape_line_range(
+    paragraph: &BidiParagraph,
+    range: Range<usize>,
+    styles: &TextStyles,
+    runs: &[StyleRun],
+    scale_factor: f32,
+) -> ShapedLine {
+    let text = paragraph.text();
+
+    let mut line = ShapedLine {
+        range: range.clone(),
+        direction: paragraph.direction(),
+        ..Default::default()
+    };
+
+    for bidi_run in paragraph.visual_runs(range) {
+        let mut pieces: Vec<(usize, FontRun)> = Vec::new();
+
+        for style_run in runs {
+            let start = style_run.range.start.max(bidi_run.range.start);
+            let end = style_run.range.end.min(bidi_run.range.end);
+            if start >= end {
+                continue;
+            }
+
+            let style = styles.get(style_run.style);
+            pieces.extend(style.fonts.runs(&text[start..end]).into_iter().map(|run| {
+                (
+                    style_run.style,
+                    FontRun {
+                        font: run.font,
+                        range: start + run.range.start..start + run.range.end,
+                    },
+                )
+            }));
+        }
+
+        if bidi_run.rtl {
+            pieces.reverse();
+        }
+
+        for (style, run) in pieces {
+            shape_run(
+                &mut line,
+                text,
+                run,
+                styles.get(style),
+                style,
+                bidi_run.rtl,
+                scale_factor,
+            );
+        }
+    }
+
+    line.build_clusters();
+    line
+}
+
+fn shape_run(
+    line: &mut ShapedLine,
+    text: &str,
+    run: FontRun,
+    text_style: &TextStyle,
+    style: usize,
+    rtl: bool,
+    scale_factor: f32,
+) {
+    let font = run.font;
+    let font_scale = calculate_font_scale(text_style.size, scale_factor);
+    let letter_spacing = text_style.letter_spacing * scale_factor;
+
+    match font.shaping_face() {
+        Some(face) => {
+            let scale = font.render_scale() * font_scale;
+
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(&text[run.range.clone()]);
+            buffer.guess_segment_properties();
+            buffer.set_direction(if rtl {
+                rustybuzz::Direction::RightToLeft
+            } else {
+                rustybuzz::Direction::LeftToRight
+            });
+
+            let output = rustybuzz::shape(&face, &[], buffer);
+
+            let infos = output.glyph_infos();
+            for (index, (info, pos)) in infos.iter().zip(output.glyph_positions()).enumerate() {
+                // note: spacing goes after the last glyph of a cluster so ligatures and marks stay together
+                let spacing = match infos.get(index + 1) {
+                    Some(next) if next.cluster == info.cluster => 0.0,
+                    _ => letter_spacing,
+                };
+
+                line.push(ShapedGlyph {
+                    font,
+                    glyph_index: info.glyph_id,
+                    style,
+                    cluster: run.range.start + info.cluster as usize,
+                    advance: pos.x_advance as f32 * scale + spacing,
+                    offset: Point::new(pos.x_offset as f32 * scale, -pos.y_offset as f32 * scale),
+                    rtl,
+                });
+            }
+        }
+        None => {
+            // note: the shaper could not parse the font, fall back to the charmap and hinted advances
+            let mut chars: Vec<(usize, char)> = text[run.range.clone()].char_indices().collect();
+            if rtl {
+                chars.reverse();
+            }
+
+            for (index, c) in chars {
+                if c.is_control() {
+                    continue;
+                }
+
+                let glyph_index = font.glyph_index(c);
+                let advance = font
+                    .get_glyph_by_index(glyph_index)
+                    .map_or(0.0, |glyph| glyph.advance());
+
+                line.push(ShapedGlyph {
+                    font,
+                    glyph_index,
+                    style,
+                    cluster: run.range.start + index,
+                    advance: advance * font_scale + letter_spacing,
+                    offset: Point::new(0.0, 0.0),
+                    rtl,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use font::FontStyle;
+
+    fn shape(text: &str, style: TextStyle, scale_factor: f32) -> ShapedLine {
+        let styles = TextStyles::uniform(style);
+        shape_line_range(
+            &BidiParagraph::new(text, TextDirection::LeftToRight),
+            0..text.len(),
+            &styles,
+            &styles.runs_in(0..text.len()),
+            scale_factor,
+        )
+    }
+
+    fn style() -> TextStyle {
+        let fonts = FontFallback::last_resort(FontStyle::default()).unwrap();
+        TextStyle::new(fonts, FontSize::px(16.0))
+    }
+
+    #[test]
+    fn moves_tabs_to_the_stops() {
+        let text = "a\tb\tc\td";
+        let mut line = shape(text, style(), 1.0);
+        line.expand_tabs(text, &[50.0, 120.0], 40.0);
+
+        assert_eq!(line.caret_x(2), 50.0);
+        assert_eq!(line.caret_x(4), 120.0);
+        // note: past the last stop tabs land on multiples of the interval
+        assert_eq!(line.caret_x(6), 160.0);
+        assert_eq!(line.width, 160.0 + line.clusters().last().unwrap().width);
+    }
+
+    #[test]
+    fn letter_spacing_widens_every_cluster() {
+        let text = "abc";
+        let plain = shape(text, style(), 2.0);
+        let spaced = shape(text, style().with_letter_spacing(1.5), 2.0);
+
+        // note: spacing is in logical pixels, 3 physical pixels per cluster at a scale factor of 2
+        for (plain, spaced) in plain.clusters().iter().zip(spaced.clusters()) {
+            assert!((spaced.width - plain.width - 3.0).abs() < 1e-3);
+        }
+        assert!((spaced.width - plain.width - 9.0).abs() < 1e-3);
+    }
+}
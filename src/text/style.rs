@@ -0,0 +1,261 @@
+/*
+  Copyright 2023 Jacob Green
+
+  Licensed under the Apache License, Version 2.0 (the "License");
+  you may not use this file except in compliance with the License.
+  You may obtain a copy of the License at
+
+      http://www.apache.org/licenses/LICENSE-2.0
+
+  Unless required by applicable law or agreed to in writing, software
+  distributed under the License is distributed on an "AS IS" BASIS,
+  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+  See the License for the specific language governing permissions and
+  limitations under the License.
+*/
+
+use font::{calculate_font_scale, FontFallback, FontSize, GlyphRendering};
+use {Color, Point};
+
+use std::ops::Range;
+
+/// Lines drawn along a run of text, positioned from the font's metrics.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// color of the lines, the text color if unset
+    pub color: Option<Color>,
+}
+
+impl TextDecoration {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.underline && !self.strikethrough
+    }
+}
+
+/// A stroke around the outside of every glyph.
+#[derive(Debug, Copy, Clone)]
+pub struct TextOutline {
+    /// in logical pixels, limited by `DistanceFieldSettings::spread`
+    pub width: f32,
+    pub color: Color,
+}
+
+impl TextOutline {
+    pub fn new(width: f32, color: Color) -> Self {
+        Self { width, color }
+    }
+}
+
+/// A copy of the text drawn behind it, a shadow without an offset makes a glow.
+#[derive(Debug, Copy, Clone)]
+pub struct TextShadow {
+    /// in logical pixels
+    pub offset: Point,
+    /// distance the edge fades out over in logical pixels, limited by `DistanceFieldSettings::spread`
+    pub blur: f32,
+    pub color: Color,
+}
+
+impl TextShadow {
+    pub fn new(offset: Point, color: Color) -> Self {
+        Self {
+            offset,
+            blur: 0.0,
+            color,
+        }
+    }
+
+    pub fn glow(radius: f32, color: Color) -> Self {
+        Self::new(Point::new(0.0, 0.0), color).with_blur(radius)
+    }
+
+    pub fn with_blur(mut self, blur: f32) -> Self {
+        self.blur = blur.max(0.0);
+        self
+    }
+}
+
+/// Resolved appearance of a run of text.
+#[derive(Debug, Clone)]
+pub struct TextStyle {
+    pub fonts: FontFallback,
+    pub size: FontSize,
+    pub color: Color,
+    pub rendering: GlyphRendering,
+    /// extra space after every cluster in logical pixels, negative values tighten the text
+    pub letter_spacing: f32,
+    pub decoration: TextDecoration,
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+}
+
+impl TextStyle {
+    pub fn new(fonts: FontFallback, size: FontSize) -> Self {
+        Self {
+            fonts,
+            size,
+            color: Color::BLACK,
+            rendering: GlyphRendering::Auto,
+            letter_spacing: 0.0,
+            decoration: TextDecoration::default(),
+            outline: None,
+            shadow: None,
+        }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_rendering(mut self, rendering: GlyphRendering) -> Self {
+        self.rendering = rendering;
+        self
+    }
+
+    pub fn with_letter_spacing(mut self, letter_spacing: f32) -> Self {
+        self.letter_spacing = letter_spacing;
+        self
+    }
+
+    pub fn with_decoration(mut self, decoration: TextDecoration) -> Self {
+        self.decoration = decoration;
+        self
+    }
+
+    pub fn with_outline(mut self, outline: TextOutline) -> Self {
+        self.outline = Some(outline);
+        self
+    }
+
+    pub fn with_shadow(mut self, shadow: TextShadow) -> Self {
+        self.shadow = Some(shadow);
+        self
+    }
+
+    /// Resolves `GlyphRendering::Auto`.
+    /// Outlines and blurred shadows are drawn from distance fields, so they are used at any size.
+    pub(crate) fn glyph_rendering(&self, scale_factor: f32) -> GlyphRendering {
+        let blurred = self.shadow.is_some_and(|shadow| shadow.blur > 0.0);
+        match self.rendering {
+            GlyphRendering::Auto if self.outline.is_some() || blurred => {
+                GlyphRendering::DistanceField
+            }
+            rendering => rendering.resolve(self.size, scale_factor),
+        }
+    }
+
+    pub(crate) fn font_scale(&self, scale_factor: f32) -> f32 {
+        calculate_font_scale(self.size, scale_factor)
+    }
+
+    /// Distance from the top of a line to its baseline, in physical pixels of a window with `scale_factor`.
+    pub fn ascent(&self, scale_factor: f32) -> f32 {
+        self.fonts.primary().ascent() * self.font_scale(scale_factor)
+    }
+
+    pub fn line_height(&self, scale_factor: f32) -> f32 {
+        self.fonts.primary().line_height() * self.font_scale(scale_factor)
+    }
+}
+
+/// A byte range of text drawn with a single style.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StyleRun {
+    pub range: Range<usize>,
+    /// index into `TextStyles::styles`
+    pub style: usize,
+}
+
+/// The styles used by a text and the ranges they apply to.
+/// Text that is not covered by a run uses the first, base style.
+#[derive(Debug, Clone)]
+pub struct TextStyles {
+    pub styles: Vec<TextStyle>,
+    pub runs: Vec<StyleRun>,
+}
+
+impl TextStyles {
+    /// A single style applied to the whole text.
+    pub fn uniform(style: TextStyle) -> Self {
+        Self {
+            styles: vec![style],
+            runs: Vec::new(),
+        }
+    }
+
+    pub fn base(&self) -> &TextStyle {
+        &self.styles[0]
+    }
+
+    pub fn get(&self, style: usize) -> &TextStyle {
+        &self.styles[style]
+    }
+
+    /// Returns the index of the style used at byte `index`.
+    pub fn style_at(&self, index: usize) -> usize {
+        self.runs
+            .iter()
+            .find(|run| run.range.contains(&index))
+            .map_or(0, |run| run.style)
+    }
+
+    /// Returns runs covering all of `range`, relative to the start of `range`.
+    /// Gaps between runs are filled with the base style.
+    pub fn runs_in(&self, range: Range<usize>) -> Vec<StyleRun> {
+        let mut runs: Vec<StyleRun> = Vec::new();
+        let mut push = |start: usize, end: usize, style: usize| {
+            if start >= end {
+                return;
+            }
+            match runs.last_mut() {
+                Some(last) if last.style == style && last.range.end == start => {
+                    last.range.end = end
+                }
+                _ => runs.push(StyleRun {
+                    range: start..end,
+                    style,
+                }),
+            }
+        };
+
+        let mut sorted: Vec<&StyleRun> = self.runs.iter().collect();
+        sorted.sort_by_key(|run| run.range.start);
+
+        let mut pos = range.start;
+        for run in sorted {
+            let start = run.range.start.max(pos);
+            let end = run.range.end.min(range.end);
+            if start >= end {
+                continue;
+            }
+            push(pos - range.start, start - range.start, 0);
+            push(start - range.start, end - range.start, run.style);
+            pos = end;
+        }
+        push(pos - range.start, range.end - range.start, 0);
+
+        runs
+    }
+}
@@ -21,6 +21,7 @@ use crate::scene::*;
 use crate::*;
 
 use std::cell::{Ref, RefCell};
+use std::time::{Duration, Instant};
 
 use glm::ext::*;
 use glm::*;
@@ -32,6 +33,20 @@ use winit::window::*;
 
 use wgpu::*;
 
+/// Longest time between the presses of a double click.
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+/// Farthest the cursor may move between the presses of a double click, in logical pixels.
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+
+/// The last press of a mouse button, used to count multi clicks.
+struct LastClick {
+    time: Instant,
+    pos: Point,
+    button: winit::event::MouseButton,
+    count: u32,
+}
+
 pub trait WindowController {
     fn on_create(&mut self, _window: &Window) {}
 
@@ -184,6 +199,10 @@ pub struct Window {
     controller: Box<RefCell<dyn WindowController>>,
     scene: RefCell<Option<Scene>>,
     client_area: Size,
+    scale_factor: f32,
+    modifiers: Modifiers,
+    cursor_pos: Point,
+    last_click: Option<LastClick>,
 }
 
 impl Window {
@@ -194,6 +213,7 @@ impl Window {
     ) -> Self {
         let window = config.to_builder().build(app.event_loop).unwrap();
         let surface = unsafe { get_instance().create_surface(&window).unwrap() };
+        let scale_factor = window.scale_factor() as f32;
 
         let mut this = Self {
             window: Some(window),
@@ -202,6 +222,10 @@ impl Window {
             controller: Box::new(RefCell::new(controller)),
             scene: RefCell::new(None),
             client_area: Size::default(),
+            scale_factor,
+            modifiers: Modifiers::empty(),
+            cursor_pos: Point::default(),
+            last_click: None,
         };
         this.redraw_requested();
         this.controller.borrow_mut().on_create(&this);
@@ -254,6 +278,11 @@ impl Window {
         self.surface_dirty = false;
     }
 
+    /// Physical pixels per logical pixel of the monitor the window is on.
+    pub fn scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+
     pub(crate) fn id(&self) -> WindowId {
         self.window.as_ref().unwrap().id()
     }
@@ -271,6 +300,61 @@ impl Window {
         self.window.as_ref().unwrap().request_redraw();
     }
 
+    pub(crate) fn scale_factor_changed(&mut self, scale_factor: f32) {
+        self.scale_factor = scale_factor;
+
+        // note: text layouts made at the old scale factor are no longer valid, see `TextLayout::is_valid_for`
+        self.window.as_ref().unwrap().request_redraw();
+    }
+
+    pub(crate) fn modifiers_changed(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    pub(crate) fn key_event(
+        &self,
+        key: winit::keyboard::PhysicalKey,
+        state: winit::event::ElementState,
+        text: Option<&str>,
+    ) -> KeyEvent {
+        KeyEvent::new(key, state, self.modifiers, text.map(String::from))
+    }
+
+    /// Creates a mouse button event at the cursor, presses close together in time and space
+    /// count as a multi click.
+    pub(crate) fn mouse_button_event(
+        &mut self,
+        button: winit::event::MouseButton,
+        state: winit::event::ElementState,
+    ) -> MouseButtonEvent {
+        let pos = self.cursor_pos;
+        let max_distance = DOUBLE_CLICK_DISTANCE * self.scale_factor;
+
+        let count = match &self.last_click {
+            Some(last) if state == winit::event::ElementState::Released => last.count,
+            Some(last)
+                if last.button == button
+                    && last.time.elapsed() <= DOUBLE_CLICK_TIME
+                    && (last.pos.x - pos.x).abs() <= max_distance
+                    && (last.pos.y - pos.y).abs() <= max_distance =>
+            {
+                last.count + 1
+            }
+            _ => 1,
+        };
+
+        if state == winit::event::ElementState::Pressed {
+            self.last_click = Some(LastClick {
+                time: Instant::now(),
+                pos,
+                button,
+                count,
+            });
+        }
+
+        MouseButtonEvent::new(button, state, pos, count)
+    }
+
     pub(crate) fn moved(&mut self, pos: Point) {
         self.controller.borrow_mut().on_moved(self, pos);
     }
@@ -284,9 +368,14 @@ impl Window {
             self.update_surface();
         }
 
+        crate::font::begin_atlas_frame();
+
         let mut scene = self.scene.borrow_mut();
         if let Some(scene) = scene.as_mut() {
-            scene.update_layout(self.window.as_ref().unwrap().inner_size().into());
+            scene.update_layout(
+                self.window.as_ref().unwrap().inner_size().into(),
+                self.scale_factor,
+            );
         }
 
         let surface = self.surface.as_ref().unwrap();
@@ -396,6 +485,7 @@ impl InputHandler for Window {
     }
 
     fn on_cursor_moved(&mut self, event: &CursorMovedEvent) -> bool {
+        self.cursor_pos = event.pos();
         match self.scene.borrow_mut().as_mut() {
             Some(scene) => scene.on_cursor_moved(event),
             None => false,
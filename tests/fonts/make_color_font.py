@@ -0,0 +1,132 @@
+#!/usr/bin/env python3
+# Builds color-emoji.ttf, a bitmap only color font using only the standard library.
+#
+# The font has no outlines, a single 32 ppem CBDT / CBLC strike holds a PNG
+# smiley for U+1F600, like the strikes of Noto Color Emoji.
+
+import os
+import struct
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+FAMILY = "Uxui Test Emoji"
+CODEPOINT = 0x1F600
+PPEM = 32
+UNITS_PER_EM = 2048
+
+
+def png(size):
+    rows = bytearray()
+    center = (size - 1) / 2
+    for y in range(size):
+        rows.append(0)
+        for x in range(size):
+            dx, dy = x - center, y - center
+            inside = dx * dx + dy * dy <= (size / 2 - 1) ** 2
+            eye = abs(abs(dx) - size / 6) < 2 and abs(dy + size / 6) < 3
+            mouth = abs(dx * dx + dy * dy - (size / 4) ** 2) < size / 2 and dy > size / 10
+            if not inside:
+                rows += bytes(4)
+            elif eye or mouth:
+                rows += bytes([0x40, 0x20, 0x00, 0xFF])
+            else:
+                rows += bytes([0xFF, 0xC8, 0x20, 0xFF])
+
+    def chunk(tag, data):
+        return struct.pack(">I", len(data)) + tag + data + struct.pack(">I", zlib.crc32(tag + data))
+
+    header = struct.pack(">IIBBBBB", size, size, 8, 6, 0, 0, 0)
+    return (
+        b"\x89PNG\r\n\x1a\n"
+        + chunk(b"IHDR", header)
+        + chunk(b"IDAT", zlib.compress(bytes(rows), 9))
+        + chunk(b"IEND", b"")
+    )
+
+
+def name_table():
+    names = [(1, FAMILY), (2, "Regular"), (4, FAMILY + " Regular"), (6, FAMILY.replace(" ", ""))]
+    strings = bytearray()
+    records = bytearray()
+    for name_id, text in names:
+        data = text.encode("utf-16-be")
+        records += struct.pack(">HHHHHH", 3, 1, 0x409, name_id, len(data), len(strings))
+        strings += data
+    return struct.pack(">HHH", 0, len(names), 6 + len(records)) + records + strings
+
+
+def cmap_table():
+    subtable = struct.pack(">HHIII", 12, 0, 28, 0, 1) + struct.pack(">III", CODEPOINT, CODEPOINT, 1)
+    return struct.pack(">HHHHI", 0, 1, 3, 10, 12) + subtable
+
+
+def bitmap_tables():
+    image = png(PPEM)
+    # note: format 17 is small metrics followed by the PNG data
+    metrics = struct.pack(">BBbbB", PPEM, PPEM, 0, PPEM - 6, PPEM)
+    glyph = metrics + struct.pack(">I", len(image)) + image
+    cbdt = struct.pack(">HH", 3, 0) + glyph
+
+    index_subtable = struct.pack(">HHI", 1, 17, 4) + struct.pack(">II", 0, len(glyph))
+    array = struct.pack(">HHI", 1, 1, 8) + index_subtable
+
+    line_metrics = struct.pack(">bbBbbbbbbbbb", PPEM - 6, -6, PPEM, 1, 0, 0, 0, 0, 0, 0, 0, 0)
+    size = (
+        struct.pack(">IIII", 8 + 48, len(array), 1, 0)
+        + line_metrics
+        + line_metrics
+        + struct.pack(">HHBBBb", 1, 1, PPEM, PPEM, 32, 1)
+    )
+    cblc = struct.pack(">HHI", 3, 0, 1) + size + array
+    return cblc, cbdt
+
+
+def checksum(data):
+    data = data + b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def main():
+    ascender, descender = UNITS_PER_EM * 26 // 32, -UNITS_PER_EM * 6 // 32
+    cblc, cbdt = bitmap_tables()
+    tables = {
+        b"CBDT": cbdt,
+        b"CBLC": cblc,
+        b"cmap": cmap_table(),
+        b"head": struct.pack(
+            ">IIIIHHqqhhhhHHhhh",
+            0x00010000, 0x00010000, 0, 0x5F0F3CF5, 0x000B, UNITS_PER_EM, 0, 0,
+            0, descender, UNITS_PER_EM, ascender, 0, 8, 2, 0, 0,
+        ),
+        b"hhea": struct.pack(
+            ">IhhhHhhhhhhhhhhhH",
+            0x00010000, ascender, descender, 0, UNITS_PER_EM, 0, 0, UNITS_PER_EM,
+            1, 0, 0, 0, 0, 0, 0, 0, 2,
+        ),
+        b"hmtx": struct.pack(">HhHh", UNITS_PER_EM, 0, UNITS_PER_EM, 0),
+        b"maxp": struct.pack(">IH", 0x00005000, 2),
+        b"name": name_table(),
+    }
+
+    count = len(tables)
+    entry_selector = count.bit_length() - 1
+    search_range = (1 << entry_selector) * 16
+    font = bytearray(struct.pack(">IHHHH", 0x00010000, count, search_range, entry_selector, count * 16 - search_range))
+    offset = 12 + 16 * count
+    body = bytearray()
+    for tag in sorted(tables):
+        data = tables[tag]
+        font += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    font += body
+
+    head = 12 + 16 * count + sum(len(d) + (-len(d) % 4) for t, d in sorted(tables.items()) if t < b"head")
+    adjustment = (0xB1B0AFBA - checksum(bytes(font))) & 0xFFFFFFFF
+    font[head + 8 : head + 12] = struct.pack(">I", adjustment)
+
+    open(os.path.join(HERE, "color-emoji.ttf"), "wb").write(font)
+
+
+if __name__ == "__main__":
+    main()
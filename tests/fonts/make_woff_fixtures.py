@@ -0,0 +1,298 @@
+#!/usr/bin/env python3
+# Builds the WOFF / WOFF2 fixtures from demo.ttf using only the standard library.
+#
+#   demo.woff             zlib compressed WOFF
+#   demo.woff2            WOFF2 with the glyf, loca and hmtx transforms applied
+#   demo-collection.woff2 WOFF2 collection of two fonts sharing every table
+#
+# There is no brotli encoder in the standard library, the WOFF2 data is written
+# as uncompressed brotli meta-blocks, which every decoder must accept.
+
+import os
+import struct
+import zlib
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+
+KNOWN_TAGS = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+]
+
+
+def read_sfnt(data):
+    flavor, count = struct.unpack(">IH", data[:6])
+    tables = {}
+    for i in range(count):
+        tag, _, offset, length = struct.unpack(">4sIII", data[12 + 16 * i : 28 + 16 * i])
+        tables[tag] = data[offset : offset + length]
+    return flavor, tables
+
+
+def checksum(data):
+    data = data + b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def base128(value):
+    out = [value & 0x7F]
+    value >>= 7
+    while value:
+        out.insert(0, 0x80 | (value & 0x7F))
+        value >>= 7
+    return bytes(out)
+
+
+def u255(value):
+    if value < 253:
+        return bytes([value])
+    if value < 506:
+        return bytes([255, value - 253])
+    if value < 762:
+        return bytes([254, value - 506])
+    return bytes([253]) + struct.pack(">H", value)
+
+
+def read_glyphs(tables):
+    index_format = struct.unpack(">h", tables[b"head"][50:52])[0]
+    num_glyphs = struct.unpack(">H", tables[b"maxp"][4:6])[0]
+    loca = tables[b"loca"]
+    if index_format == 0:
+        offsets = [v * 2 for v in struct.unpack(">%dH" % (num_glyphs + 1), loca)]
+    else:
+        offsets = list(struct.unpack(">%dI" % (num_glyphs + 1), loca))
+
+    glyphs = []
+    for index in range(num_glyphs):
+        data = tables[b"glyf"][offsets[index] : offsets[index + 1]]
+        if not data:
+            glyphs.append(None)
+            continue
+        contours, *bbox = struct.unpack(">5h", data[:10])
+        assert contours > 0, "composite glyphs are not needed for the fixtures"
+        ends = struct.unpack(">%dH" % contours, data[10 : 10 + 2 * contours])
+        pos = 10 + 2 * contours
+        (length,) = struct.unpack(">H", data[pos : pos + 2])
+        instructions = data[pos + 2 : pos + 2 + length]
+        pos += 2 + length
+
+        count = ends[-1] + 1
+        flags = []
+        while len(flags) < count:
+            flag = data[pos]
+            pos += 1
+            flags.append(flag)
+            if flag & 8:
+                flags.extend([flag] * data[pos])
+                pos += 1
+
+        def coords(short, same):
+            nonlocal pos
+            values, value = [], 0
+            for flag in flags:
+                if flag & short:
+                    delta = data[pos]
+                    pos += 1
+                    value += delta if flag & same else -delta
+                elif not flag & same:
+                    value += struct.unpack(">h", data[pos : pos + 2])[0]
+                    pos += 2
+                values.append(value)
+            return values
+
+        xs = coords(2, 16)
+        ys = coords(4, 32)
+        points = [(x, y, bool(f & 1)) for x, y, f in zip(xs, ys, flags)]
+        glyphs.append((ends, points, instructions, bbox))
+
+    return index_format, glyphs
+
+
+def triplet(on_curve, dx, dy):
+    on_bit = 0 if on_curve else 128
+    ax, ay = abs(dx), abs(dy)
+    x_sign = 0 if dx < 0 else 1
+    y_sign = 0 if dy < 0 else 1
+    xy_sign = x_sign + 2 * y_sign
+    if dx == 0 and ay < 1280:
+        return bytes([on_bit + ((ay & 0xF00) >> 7) + y_sign]), bytes([ay & 0xFF])
+    if dy == 0 and ax < 1280:
+        return bytes([on_bit + 10 + ((ax & 0xF00) >> 7) + x_sign]), bytes([ax & 0xFF])
+    if ax < 65 and ay < 65:
+        flag = on_bit + 20 + ((ax - 1) & 0x30) + (((ay - 1) & 0x30) >> 2) + xy_sign
+        return bytes([flag]), bytes([(((ax - 1) & 0xF) << 4) | ((ay - 1) & 0xF)])
+    if ax < 769 and ay < 769:
+        flag = on_bit + 84 + 12 * (((ax - 1) & 0x300) >> 8) + (((ay - 1) & 0x300) >> 6) + xy_sign
+        return bytes([flag]), bytes([(ax - 1) & 0xFF, (ay - 1) & 0xFF])
+    if ax < 4096 and ay < 4096:
+        return bytes([on_bit + 120 + xy_sign]), bytes([ax >> 4, ((ax & 0xF) << 4) | (ay >> 8), ay & 0xFF])
+    return bytes([on_bit + 124 + xy_sign]), struct.pack(">HH", ax, ay)
+
+
+def transform_glyf(tables):
+    index_format, glyphs = read_glyphs(tables)
+    streams = [bytearray() for _ in range(7)]
+    contours, points_stream, flags, glyph_stream, composites, bboxes, instructions = streams
+    bitmap = bytearray(((len(glyphs) + 31) >> 5) << 2)
+
+    for index, glyph in enumerate(glyphs):
+        if glyph is None:
+            contours += struct.pack(">h", 0)
+            continue
+        ends, points, code, bbox = glyph
+        contours += struct.pack(">h", len(ends))
+        start = 0
+        for end in ends:
+            points_stream += u255(end + 1 - start)
+            start = end + 1
+        x, y = 0, 0
+        for px, py, on_curve in points:
+            flag, data = triplet(on_curve, px - x, py - y)
+            flags += flag
+            glyph_stream += data
+            x, y = px, py
+        glyph_stream += u255(len(code))
+        instructions += code
+        # note: store the bbox of the last glyph explicitly so the bbox stream is exercised
+        if index == len(glyphs) - 1:
+            bitmap[index >> 3] |= 0x80 >> (index & 7)
+            bboxes += struct.pack(">4h", *bbox)
+
+    streams[5] = bitmap + bboxes
+    header = struct.pack(">HHHH", 0, 0, len(glyphs), index_format)
+    header += struct.pack(">7I", *(len(s) for s in streams))
+    return header + b"".join(streams)
+
+
+def transform_hmtx(tables):
+    num_metrics = struct.unpack(">H", tables[b"hhea"][34:36])[0]
+    hmtx = tables[b"hmtx"]
+    advances = [struct.unpack(">H", hmtx[4 * i : 4 * i + 2])[0] for i in range(num_metrics)]
+    assert len(hmtx) == 4 * num_metrics, "the fixture has no monospaced tail"
+    # note: flag 1 drops the proportional left side bearings, they equal each glyph's xMin
+    return bytes([1]) + b"".join(struct.pack(">H", a) for a in advances)
+
+
+def brotli_stored(data):
+    bits, count = 0, 0
+    out = bytearray()
+
+    def write(value, width):
+        nonlocal bits, count
+        bits |= value << count
+        count += width
+        while count >= 8:
+            out.append(bits & 0xFF)
+            bits >>= 8
+            count -= 8
+
+    def align():
+        nonlocal bits, count
+        if count:
+            out.append(bits & 0xFF)
+            bits, count = 0, 0
+
+    write(0, 1)  # WBITS = 16
+    for start in range(0, len(data), 65536):
+        block = data[start : start + 65536]
+        write(0, 1)  # ISLAST
+        write(0, 2)  # MNIBBLES = 4
+        write(len(block) - 1, 16)
+        write(1, 1)  # ISUNCOMPRESSED
+        align()
+        out += block
+    write(1, 1)  # ISLAST
+    write(1, 1)  # ISLASTEMPTY
+    align()
+    return bytes(out)
+
+
+def woff2_directory(tables):
+    directory = bytearray()
+    stream = bytearray()
+    for tag in sorted(tables):
+        data = tables[tag]
+        index = KNOWN_TAGS.index(tag) if tag in KNOWN_TAGS else 63
+        if tag == b"glyf":
+            transformed = transform_glyf(tables)
+        elif tag == b"loca":
+            transformed = b""
+        elif tag == b"hmtx":
+            transformed = transform_hmtx(tables)
+        else:
+            transformed = None
+        # note: version 0 is the transform for glyf / loca and the null transform for the rest
+        version = 1 if tag == b"hmtx" else 0
+        directory.append(index | (version << 6))
+        if index == 63:
+            directory += tag
+        directory += base128(len(data))
+        if transformed is not None:
+            directory += base128(len(transformed))
+            stream += transformed
+        else:
+            stream += data
+    return bytes(directory), bytes(stream)
+
+
+def sfnt_size(tables, fonts=1):
+    size = sum(len(d) + (-len(d) % 4) for d in tables.values())
+    if fonts > 1:
+        size += 12 + 4 * fonts
+    return size + fonts * (12 + 16 * len(tables))
+
+
+def woff2(flavor, tables, collection=None, fonts=1):
+    directory, stream = woff2_directory(tables)
+    directory += collection or b""
+    compressed = brotli_stored(stream)
+    header_length = 48
+    total = header_length + len(directory) + len(compressed)
+    total += -total % 4
+    header = struct.pack(
+        ">4sIIHHIIHHIIIII",
+        b"wOF2", flavor, total, len(tables), 0, sfnt_size(tables, fonts), len(compressed),
+        1, 0, 0, 0, 0, 0, 0,
+    )
+    data = header + directory + compressed
+    return data + b"\0" * (total - len(data))
+
+
+def woff(flavor, tables):
+    entries, body = [], bytearray()
+    offset = 44 + 20 * len(tables)
+    for tag in sorted(tables):
+        data = tables[tag]
+        compressed = zlib.compress(data, 9)
+        stored = compressed if len(compressed) < len(data) else data
+        entries.append(struct.pack(">4sIIII", tag, offset + len(body), len(stored), len(data), checksum(data)))
+        body += stored + b"\0" * (-len(stored) % 4)
+    total = offset + len(body)
+    header = struct.pack(
+        ">4sIIHHIHHIIIII",
+        b"wOFF", flavor, total, len(tables), 0, sfnt_size(tables), 1, 0, 0, 0, 0, 0, 0,
+    )
+    return header + b"".join(entries) + bytes(body)
+
+
+def main():
+    flavor, tables = read_sfnt(open(os.path.join(HERE, "demo.ttf"), "rb").read())
+
+    open(os.path.join(HERE, "demo.woff"), "wb").write(woff(flavor, tables))
+    open(os.path.join(HERE, "demo.woff2"), "wb").write(woff2(flavor, tables))
+
+    indices = b"".join(u255(i) for i in range(len(tables)))
+    font = u255(len(tables)) + struct.pack(">I", flavor) + indices
+    collection = struct.pack(">I", 0x00010000) + u255(2) + font + font
+    open(os.path.join(HERE, "demo-collection.woff2"), "wb").write(
+        woff2(struct.unpack(">I", b"ttcf")[0], tables, collection, fonts=2)
+    )
+
+
+if __name__ == "__main__":
+    main()